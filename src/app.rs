@@ -6,17 +6,22 @@ mod terminal_setup;
 use std::io;
 
 use crate::app_state::AppState;
-use parse_args::get_file_from_args;
+use parse_args::get_path_from_args;
 use run_event_loop::run;
 use terminal_setup::setup_terminal;
 
 pub fn start_tui_editor() -> io::Result<()> {
-    let (file_content, directory_path) = get_file_from_args();
-    let mut app_state = AppState::new(file_content, directory_path);
+    let (file_path, directory_path) = get_path_from_args();
+    let mut app_state = AppState::new(directory_path);
+    app_state.load_config_files();
 
     // TODO: think where it should be initialized
     app_state.read_directory(app_state.working_directory.clone());
 
+    if let Some(file_path) = file_path {
+        app_state.open_file(file_path).expect("Could not open file");
+    }
+
     let mut terminal = setup_terminal().expect("Failed to set up terminal");
     let app_result = run(&mut terminal, &mut app_state);
 
@@ -4,21 +4,27 @@ use std::path::PathBuf;
 use std::process::exit;
 
 /// Parse passed parameters to the CLI command. It can be either a folder
-/// or a file (although folder is not supported right now).
-pub(super) fn get_file_from_args() -> (String, PathBuf) {
+/// or a file.
+///
+/// Returns canonical path to the file (if a file was passed) and the
+/// working directory.
+pub(super) fn get_path_from_args() -> (Option<PathBuf>, PathBuf) {
     let args: Vec<String> = env::args().collect();
 
     if args.len() != 2 {
-        println!("Please specify a filename to open");
+        println!("Please specify a file or a folder to open");
         exit(1);
     }
 
     let passed_path = args.last().expect("Could not read passed path");
-    let mut canonical_path = fs::canonicalize(passed_path).expect("Could not read passed path");
+    let canonical_path = fs::canonicalize(passed_path).expect("Could not read passed path");
 
-    let file_string = fs::read_to_string(&canonical_path).expect("Could not open file");
+    if canonical_path.is_dir() {
+        return (None, canonical_path);
+    }
 
-    canonical_path.pop();
+    let mut directory_path = canonical_path.clone();
+    directory_path.pop();
 
-    (file_string, canonical_path)
+    (Some(canonical_path), directory_path)
 }
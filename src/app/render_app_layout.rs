@@ -5,8 +5,11 @@ use ratatui::{
 
 use crate::app_state::AppState;
 use crate::editor::render_editor;
+use crate::file_finder::render_file_finder;
 use crate::file_tree::render_file_tree;
+use crate::project_search::render_project_search;
 use crate::status_bar::render_status_bar;
+use crate::tabs::render_tabs;
 
 /// Render general application layout without any specific details.
 /// For now the layout is fixed, but the plan is to allow full customization
@@ -15,11 +18,27 @@ pub(super) fn render(frame: &mut Frame, app_state: &mut AppState) {
     let vertical = Layout::vertical([Constraint::Fill(1), Constraint::Length(2)]);
     let [main_area, status_area] = vertical.areas(frame.area());
 
+    app_state.update_search_summary();
     render_status_bar(frame, status_area, app_state);
 
     let horizontal = Layout::horizontal([Constraint::Length(50), Constraint::Fill(1)]);
     let [left_area, right_area] = horizontal.areas(main_area);
 
-    render_file_tree(frame, left_area, app_state);
-    render_editor(frame, right_area, app_state);
+    let editor_vertical = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+    let [tabs_area, editor_area] = editor_vertical.areas(right_area);
+
+    app_state.set_layout_areas(left_area, editor_area);
+
+    if app_state.project_search.is_some() {
+        render_project_search(frame, left_area, app_state);
+    } else {
+        render_file_tree(frame, left_area, app_state);
+    }
+    render_tabs(frame, tabs_area, app_state);
+    render_editor(frame, editor_area, app_state);
+
+    // popups are drawn last, on top of everything else
+    if app_state.file_finder.is_some() {
+        render_file_finder(frame, main_area, app_state);
+    }
 }
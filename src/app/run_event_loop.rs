@@ -1,74 +1,260 @@
 use std::io;
+use std::time::Duration;
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{Terminal, backend::CrosstermBackend};
 
 use super::render_app_layout::render;
 use super::terminal_setup::restore_terminal;
-use crate::app_state::AppState;
+use crate::app_state::{AppState, Focus, SearchDirection, SearchInput};
 
 pub(super) fn run(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app_state: &mut AppState,
 ) -> io::Result<()> {
+    let mut needs_redraw = true;
+
     loop {
-        terminal.draw(|frame| render(frame, app_state))?;
+        if needs_redraw {
+            terminal.draw(|frame| render(frame, app_state))?;
+
+            if app_state.focus == Focus::Editor
+                && app_state.prompt.is_none()
+                && app_state.command_bar.is_none()
+                && app_state.file_finder.is_none()
+                && app_state.search_bar.is_none()
+                && let Some(buffer) = app_state.get_active_buffer_mut()
+            {
+                buffer.ui_state.show_cursor_if_needed();
+            }
+        }
+        needs_redraw = true;
 
-        app_state.ui_state.show_cursor_if_needed();
+        // results of the project search come from other threads, so we can't
+        // block on reading events while it runs; they are taken on every
+        // iteration, so typing does not hold them back
+        if app_state.is_project_search_running() {
+            let has_event = event::poll(Duration::from_millis(50))?;
+            let has_results = app_state.receive_project_search_results();
+            if !has_event {
+                needs_redraw = has_results;
+                continue;
+            }
+        }
 
         match event::read()? {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                match key_event.code {
-                    KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                        restore_terminal(terminal).expect("Could not shut down the app gracefully, terminal might not work properly");
-                        return Ok(());
-                    }
-                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app_state.ui_state.handle_copy();
-                    }
-                    KeyCode::Char('z') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app_state.undo_redo.undo_action(&mut app_state.ui_state);
-                    }
-                    KeyCode::Char('z')
-                        if key_event.modifiers.contains(KeyModifiers::CONTROL)
-                            && key_event.modifiers.contains(KeyModifiers::SHIFT) =>
-                    {
-                        app_state.undo_redo.redo_action(&mut app_state.ui_state);
-                    }
-                    KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app_state.undo_redo.redo_action(&mut app_state.ui_state);
-                    }
-                    KeyCode::Char(character) => app_state
-                        .ui_state
-                        .insert_character(character, &mut app_state.undo_redo),
-                    KeyCode::Home => app_state
-                        .ui_state
-                        .cursor_move_line_start(&key_event.modifiers),
-                    KeyCode::Left => app_state.ui_state.cursor_move_left(&key_event.modifiers),
-                    KeyCode::End => app_state
-                        .ui_state
-                        .cursor_move_line_end(&key_event.modifiers),
-                    KeyCode::Right => app_state.ui_state.cursor_move_right(&key_event.modifiers),
-                    KeyCode::Down => app_state.ui_state.cursor_move_down(&key_event.modifiers),
-                    KeyCode::Up => app_state.ui_state.cursor_move_up(&key_event.modifiers),
-                    KeyCode::Backspace => app_state
-                        .ui_state
-                        .remove_previous_character(&mut app_state.undo_redo),
-                    KeyCode::Delete => app_state
-                        .ui_state
-                        .remove_next_character(&mut app_state.undo_redo),
-                    KeyCode::Enter => app_state.ui_state.add_new_line(&mut app_state.undo_redo),
-                    KeyCode::BackTab => app_state.ui_state.handle_backtab_key(&app_state.config),
-                    KeyCode::Tab => app_state
+                if app_state.prompt.is_some() {
+                    handle_prompt_key(app_state, key_event);
+                } else if app_state.command_bar.is_some() {
+                    handle_command_bar_key(app_state, key_event);
+                } else if app_state.file_finder.is_some() {
+                    handle_file_finder_key(app_state, key_event);
+                } else if app_state.search_bar.is_some() {
+                    handle_search_bar_key(app_state, key_event);
+                } else if app_state.focus == Focus::ProjectSearch {
+                    handle_project_search_key(app_state, key_event);
+                } else {
+                    handle_key(app_state, key_event);
+                }
+            }
+            Event::Mouse(mouse_event) => app_state.handle_mouse_event(&mouse_event),
+            Event::Paste(data) if app_state.command_bar.is_some() => {
+                for character in data.chars().filter(|character| !character.is_control()) {
+                    app_state.command_bar_insert_char(character);
+                }
+            }
+            Event::Paste(data) if app_state.file_finder.is_some() => {
+                for character in data.chars().filter(|character| !character.is_control()) {
+                    app_state.file_finder_insert_char(character);
+                }
+            }
+            Event::Paste(data) if app_state.search_bar.is_some() => {
+                for character in data.chars().filter(|character| !character.is_control()) {
+                    app_state.search_bar_insert_char(character);
+                }
+            }
+            Event::Paste(data) if app_state.focus == Focus::ProjectSearch => {
+                for character in data.chars().filter(|character| !character.is_control()) {
+                    app_state.project_search_insert_char(character);
+                }
+            }
+            Event::Paste(data) if app_state.focus == Focus::Editor => {
+                if let Some(buffer) = app_state.get_active_buffer_mut() {
+                    buffer
                         .ui_state
-                        .handle_tab_key(&app_state.config, &mut app_state.undo_redo),
-                    _ => {}
+                        .paste_at_cursors(data, &mut buffer.undo_redo);
                 }
             }
-            Event::Paste(data) => app_state
-                .ui_state
-                .handle_paste(data, &mut app_state.undo_redo),
             _ => {}
         }
+
+        if app_state.should_quit {
+            restore_terminal(terminal)
+                .expect("Could not shut down the app gracefully, terminal might not work properly");
+            return Ok(());
+        }
+    }
+}
+
+/// All shortcuts come from the keymap; keys which are not bound to anything
+/// are typed into the editor
+fn handle_key(app_state: &mut AppState, key_event: KeyEvent) {
+    if app_state.handle_key_binding(&key_event) {
+        return;
+    }
+
+    // `ctrl+alt` is how AltGr is reported on some keyboards, it is used for typing
+    let is_shortcut = key_event.modifiers.contains(KeyModifiers::CONTROL)
+        != key_event.modifiers.contains(KeyModifiers::ALT);
+
+    if app_state.focus == Focus::Editor
+        && !is_shortcut
+        && let KeyCode::Char(character) = key_event.code
+        && let Some(buffer) = app_state.get_active_buffer_mut()
+    {
+        buffer
+            .ui_state
+            .for_each_cursor(&mut buffer.undo_redo, |ui_state, undo_redo| {
+                ui_state.pad_to_block(undo_redo);
+                ui_state.insert_character(character, undo_redo);
+            });
+    }
+}
+
+fn handle_command_bar_key(app_state: &mut AppState, key_event: KeyEvent) {
+    let is_shortcut = key_event
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+
+    match key_event.code {
+        KeyCode::Esc => app_state.close_command_bar(),
+        KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+            app_state.close_command_bar();
+        }
+        _ if is_shortcut && app_state.is_key_bound_to(&key_event, "command-bar") => {
+            app_state.close_command_bar();
+        }
+        // other shortcuts should not end up in the input
+        KeyCode::Char(_)
+            if key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+        KeyCode::Enter => app_state.submit_command_bar(),
+        KeyCode::Tab => app_state.command_bar_complete(),
+        KeyCode::Up => app_state.command_bar_history_previous(),
+        KeyCode::Down => app_state.command_bar_history_next(),
+        KeyCode::Left => app_state.command_bar_move_left(),
+        KeyCode::Right => app_state.command_bar_move_right(),
+        KeyCode::Backspace => app_state.command_bar_remove_previous_char(),
+        KeyCode::Char(character) => app_state.command_bar_insert_char(character),
+        _ => {}
+    }
+}
+
+fn handle_file_finder_key(app_state: &mut AppState, key_event: KeyEvent) {
+    let is_shortcut = key_event
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+
+    match key_event.code {
+        KeyCode::Esc => app_state.close_file_finder(),
+        KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+            app_state.close_file_finder();
+        }
+        _ if is_shortcut && app_state.is_key_bound_to(&key_event, "find-file") => {
+            app_state.close_file_finder();
+        }
+        KeyCode::Char(_)
+            if key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+        KeyCode::Enter => app_state.submit_file_finder(),
+        KeyCode::Up => app_state.file_finder_move_up(),
+        KeyCode::Down => app_state.file_finder_move_down(),
+        KeyCode::Left => app_state.file_finder_move_left(),
+        KeyCode::Right => app_state.file_finder_move_right(),
+        KeyCode::Backspace => app_state.file_finder_remove_previous_char(),
+        KeyCode::Char(character) => app_state.file_finder_insert_char(character),
+        _ => {}
+    }
+}
+
+/// The match stays selected after closing the search bar. With the
+/// replacement input focused, `Enter` replaces the match instead of skipping it.
+fn handle_search_bar_key(app_state: &mut AppState, key_event: KeyEvent) {
+    let modifiers = key_event.modifiers;
+    let is_replacement_focused = app_state
+        .search_bar
+        .as_ref()
+        .is_some_and(|search_bar| search_bar.focused_input == SearchInput::Replacement);
+
+    match key_event.code {
+        KeyCode::Esc => app_state.close_search_bar(),
+        KeyCode::Char('c' | 'f' | 'h') if modifiers.contains(KeyModifiers::CONTROL) => {
+            app_state.close_search_bar();
+        }
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::ALT) => {
+            app_state.toggle_search_case_sensitive();
+        }
+        KeyCode::Char('r') if modifiers.contains(KeyModifiers::ALT) => {
+            app_state.toggle_search_regex();
+        }
+        KeyCode::Char('a') if modifiers.contains(KeyModifiers::ALT) => app_state.replace_all(),
+        KeyCode::Enter if modifiers.contains(KeyModifiers::ALT) => app_state.replace_all(),
+        KeyCode::Char(_) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+        KeyCode::Enter if is_replacement_focused => app_state.replace_next(),
+        KeyCode::Enter | KeyCode::Down | KeyCode::F(3)
+            if !modifiers.contains(KeyModifiers::SHIFT) =>
+        {
+            app_state.find_next(SearchDirection::Forward);
+        }
+        KeyCode::Enter | KeyCode::Up | KeyCode::F(3) => {
+            app_state.find_next(SearchDirection::Backward);
+        }
+        KeyCode::Tab | KeyCode::BackTab => app_state.search_bar_switch_input(),
+        KeyCode::Left => app_state.search_bar_move_left(),
+        KeyCode::Right => app_state.search_bar_move_right(),
+        KeyCode::Backspace => app_state.search_bar_remove_previous_char(),
+        KeyCode::Char(character) => app_state.search_bar_insert_char(character),
+        _ => {}
+    }
+}
+
+/// `Enter` starts the search after the query was edited, and otherwise opens
+/// the selected match. Other shortcuts, like `ctrl+e`, work as usual.
+fn handle_project_search_key(app_state: &mut AppState, key_event: KeyEvent) {
+    let modifiers = key_event.modifiers;
+
+    match key_event.code {
+        KeyCode::Esc => app_state.close_project_search(),
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::ALT) => {
+            app_state.project_search_toggle_case_sensitive();
+        }
+        KeyCode::Char('r') if modifiers.contains(KeyModifiers::ALT) => {
+            app_state.project_search_toggle_regex();
+        }
+        KeyCode::Char(_) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            app_state.handle_key_binding(&key_event);
+        }
+        KeyCode::Enter => app_state.project_search_submit(),
+        KeyCode::Up => app_state.project_search_move_up(),
+        KeyCode::Down => app_state.project_search_move_down(),
+        KeyCode::Left => app_state.project_search_move_left(),
+        KeyCode::Right => app_state.project_search_move_right(),
+        KeyCode::Backspace => app_state.project_search_remove_previous_char(),
+        KeyCode::Char(character) => app_state.project_search_insert_char(character),
+        _ => {
+            app_state.handle_key_binding(&key_event);
+        }
+    }
+}
+
+fn handle_prompt_key(app_state: &mut AppState, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Char('y') => app_state.answer_prompt(Some(true)),
+        KeyCode::Char('n') => app_state.answer_prompt(Some(false)),
+        KeyCode::Char('c') | KeyCode::Esc => app_state.answer_prompt(None),
+        _ => {}
     }
 }
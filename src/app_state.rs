@@ -1,8 +1,41 @@
 mod app;
+mod block_selection;
+mod brackets;
+mod buffer;
+mod command_bar;
+mod commands;
+mod config_file;
+mod display_width;
 mod editor;
+mod editorconfig;
+mod file_finder;
+mod file_format;
+mod file_tree;
+mod fuzzy;
+mod highlighter;
+mod keymap;
+mod language;
+mod mouse;
+mod multi_cursor;
 mod navigation;
+mod project_search;
+mod replace;
+mod save_file;
+mod search;
 mod selection;
+#[cfg(test)]
+mod test_utils;
+mod text_buffer;
 mod undo_redo;
+mod viewport;
+mod words;
 
-pub use app::AppState;
-pub use editor::FileTreeEntry;
+pub use app::{AppState, Focus};
+pub use brackets::BracketMatch;
+pub use command_bar::CommandBar;
+pub use display_width::{LineLayout, get_display_text};
+pub use highlighter::HighlightSpan;
+pub use project_search::ProjectSearchRow;
+pub use save_file::get_file_name;
+pub use search::{SearchBar, SearchDirection, SearchInput, find_in_line};
+pub use selection::Selection;
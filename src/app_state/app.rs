@@ -1,26 +1,107 @@
+use ratatui::layout::Rect;
 use ratatui::style::Color;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::editor::{FileTreeEntry, UIState};
-use super::undo_redo::UndoRedo;
+use super::buffer::{Buffer, Prompt};
+use super::command_bar::CommandBar;
+use super::commands::CommandRegistry;
+use super::editor::FileTreeEntry;
+use super::file_finder::FileFinder;
+use super::file_format::LineEnding;
+use super::keymap::{KeyChord, Keymap};
+use super::language::{detect_language, get_default_auto_pairs};
+use super::mouse::Click;
+use super::project_search::ProjectSearch;
+use super::search::{SearchBar, SearchQuery};
+use super::words::WordRules;
 
 pub struct AppState {
     /// Directory of the entire project, can only be a single one
     /// It is either opened directly (like `love .`) or it is calculated
     /// based on the provided path
     pub working_directory: PathBuf,
+    /// Entries of every directory we have read so far, keyed by the directory
+    /// path. Nested directories are added when they are expanded.
     pub file_tree: HashMap<PathBuf, Vec<FileTreeEntry>>,
-    pub ui_state: UIState,
-    pub undo_redo: UndoRedo,
+    /// Index of the selected row among currently visible file tree entries
+    pub file_tree_selected: usize,
+    pub file_tree_scroll_offset: usize,
+    pub focus: Focus,
+    /// All opened files. It is empty if we opened a directory and didn't
+    /// pick any file yet, or if all files were closed.
+    pub buffers: Vec<Buffer>,
+    pub active_buffer: usize,
+    /// Paths of closed buffers, so that they can be reopened
+    pub closed_buffers: Vec<PathBuf>,
+    /// If set, all keys are used to answer it
+    pub prompt: Option<Prompt>,
+    /// Settings from the built-in defaults, the global config file and the
+    /// project config file, in this order
     pub config: Config,
+    /// Settings for specific languages, applied on top of `config`
+    pub language_configs: HashMap<String, ConfigOverride>,
+    /// Options changed with the `set` command, they win over all files
+    pub command_config: ConfigOverride,
     pub theme: Theme,
+    /// Short message shown in the status bar, e.g. the result of the last save
+    pub status_message: Option<String>,
+    pub commands: CommandRegistry,
+    /// If set, all keys go to the command bar
+    pub command_bar: Option<CommandBar>,
+    /// Previously executed commands, the most recent one is the last
+    pub command_history: Vec<String>,
+    /// If set, all keys go to the search bar
+    pub search_bar: Option<SearchBar>,
+    /// The last search, shared by all buffers
+    pub search: SearchQuery,
+    /// Text which replaces search matches
+    pub replacement: Vec<char>,
+    /// If set, all keys go to the file finder popup
+    pub file_finder: Option<FileFinder>,
+    /// Search in all files of the working directory, shown in place of the
+    /// file tree while it is opened
+    pub project_search: Option<ProjectSearch>,
+    /// Where the file tree and the editor were rendered last time, so mouse
+    /// events can be matched to them
+    pub file_tree_area: Rect,
+    pub editor_area: Rect,
+    pub last_click: Option<Click>,
+    /// Checked by the event loop after every event
+    pub should_quit: bool,
+    pub keymap: Keymap,
+    /// Beginning of a multi-key sequence typed so far, like `ctrl+k`
+    pub pending_keys: Vec<KeyChord>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    FileTree,
+    Editor,
+    ProjectSearch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     pub tabs_to_spaces: bool,
     pub whitespaces_amount: usize,
+    /// Width of a tab character on the screen; `None` means `whitespaces_amount`
+    pub tab_width: Option<usize>,
+    /// Line ending used when saving; `None` keeps the one the file had
+    pub end_of_line: Option<LineEnding>,
+    /// `Some(false)` removes the final newline on save, `None` keeps it as is
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: bool,
+    /// Initial value for every opened file, can be toggled per file
+    pub soft_wrap: bool,
+    /// Characters which are part of words besides letters, digits and `_`
+    pub word_chars: String,
+    /// Word navigation stops at camelCase humps and underscores as well
+    pub subword_navigation: bool,
+    /// Opening and closing characters one after another, like `()[]`;
+    /// `None` takes the default of the file's language
+    pub auto_pairs: Option<String>,
 }
 
 impl Config {
@@ -28,6 +109,122 @@ impl Config {
         Config {
             tabs_to_spaces: true,
             whitespaces_amount: 4,
+            tab_width: None,
+            end_of_line: None,
+            insert_final_newline: None,
+            trim_trailing_whitespace: false,
+            soft_wrap: false,
+            word_chars: String::new(),
+            subword_navigation: false,
+            auto_pairs: None,
+        }
+    }
+
+    pub fn get_tab_width(&self) -> usize {
+        self.tab_width.unwrap_or(self.whitespaces_amount)
+    }
+
+    pub fn get_word_rules(&self) -> WordRules {
+        WordRules {
+            extra_chars: self.word_chars.chars().collect(),
+            subwords: self.subword_navigation,
+        }
+    }
+
+    pub fn get_auto_pairs(&self) -> Vec<(char, char)> {
+        let auto_pairs = self
+            .auto_pairs
+            .as_deref()
+            .unwrap_or_else(|| get_default_auto_pairs(None));
+        let characters: Vec<char> = auto_pairs.chars().collect();
+        characters
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect()
+    }
+}
+
+/// Values set in a single config layer; everything which is not set there
+/// is taken from the layer below
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigOverride {
+    pub tabs_to_spaces: Option<bool>,
+    pub whitespaces_amount: Option<usize>,
+    pub tab_width: Option<usize>,
+    pub end_of_line: Option<LineEnding>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub soft_wrap: Option<bool>,
+    pub word_chars: Option<String>,
+    pub subword_navigation: Option<bool>,
+    pub auto_pairs: Option<String>,
+}
+
+impl ConfigOverride {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(tabs_to_spaces) = self.tabs_to_spaces {
+            config.tabs_to_spaces = tabs_to_spaces;
+        }
+        if let Some(whitespaces_amount) = self.whitespaces_amount {
+            config.whitespaces_amount = whitespaces_amount;
+        }
+        if self.tab_width.is_some() {
+            config.tab_width = self.tab_width;
+        }
+        if self.end_of_line.is_some() {
+            config.end_of_line = self.end_of_line;
+        }
+        if self.insert_final_newline.is_some() {
+            config.insert_final_newline = self.insert_final_newline;
+        }
+        if let Some(trim_trailing_whitespace) = self.trim_trailing_whitespace {
+            config.trim_trailing_whitespace = trim_trailing_whitespace;
+        }
+        if let Some(soft_wrap) = self.soft_wrap {
+            config.soft_wrap = soft_wrap;
+        }
+        if let Some(word_chars) = &self.word_chars {
+            config.word_chars = word_chars.clone();
+        }
+        if let Some(subword_navigation) = self.subword_navigation {
+            config.subword_navigation = subword_navigation;
+        }
+        if self.auto_pairs.is_some() {
+            config.auto_pairs = self.auto_pairs.clone();
+        }
+    }
+
+    /// Combine with a layer on top of this one
+    pub fn merge(&mut self, other: &ConfigOverride) {
+        if other.tabs_to_spaces.is_some() {
+            self.tabs_to_spaces = other.tabs_to_spaces;
+        }
+        if other.whitespaces_amount.is_some() {
+            self.whitespaces_amount = other.whitespaces_amount;
+        }
+        if other.tab_width.is_some() {
+            self.tab_width = other.tab_width;
+        }
+        if other.end_of_line.is_some() {
+            self.end_of_line = other.end_of_line;
+        }
+        if other.insert_final_newline.is_some() {
+            self.insert_final_newline = other.insert_final_newline;
+        }
+        if other.trim_trailing_whitespace.is_some() {
+            self.trim_trailing_whitespace = other.trim_trailing_whitespace;
+        }
+        if other.soft_wrap.is_some() {
+            self.soft_wrap = other.soft_wrap;
+        }
+        if other.word_chars.is_some() {
+            self.word_chars = other.word_chars.clone();
+        }
+        if other.subword_navigation.is_some() {
+            self.subword_navigation = other.subword_navigation;
+        }
+        if other.auto_pairs.is_some() {
+            self.auto_pairs = other.auto_pairs.clone();
         }
     }
 }
@@ -35,41 +232,160 @@ impl Config {
 pub struct Theme {
     pub bg_color: Color,
     pub status_bar_color: Color,
+    /// Colors for tree-sitter captures, like `keyword` or `function.method`
+    pub syntax_colors: HashMap<String, Color>,
 }
 
 impl Theme {
     pub fn new() -> Self {
+        let syntax_colors = [
+            ("keyword", Color::Rgb(255, 121, 198)),
+            ("operator", Color::Rgb(255, 121, 198)),
+            ("function", Color::Rgb(80, 250, 123)),
+            ("type", Color::Rgb(139, 233, 253)),
+            ("constructor", Color::Rgb(139, 233, 253)),
+            ("constant", Color::Rgb(189, 147, 249)),
+            ("number", Color::Rgb(189, 147, 249)),
+            ("boolean", Color::Rgb(189, 147, 249)),
+            ("string", Color::Rgb(241, 250, 140)),
+            ("escape", Color::Rgb(255, 184, 108)),
+            ("comment", Color::Rgb(110, 110, 130)),
+            ("attribute", Color::Rgb(255, 184, 108)),
+            ("label", Color::Rgb(255, 184, 108)),
+            ("property", Color::Rgb(139, 233, 253)),
+            ("variable.builtin", Color::Rgb(189, 147, 249)),
+            ("variable.parameter", Color::Rgb(255, 184, 108)),
+            ("punctuation.special", Color::Rgb(255, 121, 198)),
+            ("text.title", Color::Rgb(80, 250, 123)),
+            ("text.literal", Color::Rgb(241, 250, 140)),
+            ("text.uri", Color::Rgb(139, 233, 253)),
+            ("text.reference", Color::Rgb(189, 147, 249)),
+        ]
+        .into_iter()
+        .map(|(name, color)| (name.to_string(), color))
+        .collect();
+
         Theme {
             bg_color: Color::Rgb(21, 6, 3),
             status_bar_color: Color::Rgb(30, 9, 20),
+            syntax_colors,
+        }
+    }
+
+    /// Find a color for the capture; if there is no exact match, we try more
+    /// generic names, e.g. `function.method` -> `function`
+    pub fn get_syntax_color(&self, capture_name: &str) -> Option<Color> {
+        let mut name = capture_name;
+
+        loop {
+            if let Some(color) = self.syntax_colors.get(name) {
+                return Some(*color);
+            }
+
+            let (parent, _) = name.rsplit_once('.')?;
+            name = parent;
         }
     }
 }
 
 impl AppState {
-    pub fn new(file_content: String, working_directory: PathBuf) -> AppState {
-        let lines_number = file_content.lines().count();
-        let lines: Vec<Vec<char>> = file_content.lines().map(|s| s.chars().collect()).collect();
-
+    pub fn new(working_directory: PathBuf) -> AppState {
         AppState {
             working_directory,
             file_tree: HashMap::new(),
-            ui_state: UIState::new(lines_number.to_string().len(), lines),
+            file_tree_selected: 0,
+            file_tree_scroll_offset: 0,
+            focus: Focus::FileTree,
+            buffers: vec![],
+            active_buffer: 0,
+            closed_buffers: vec![],
+            prompt: None,
             config: Config::new(),
+            language_configs: HashMap::new(),
+            command_config: ConfigOverride::default(),
             theme: Theme::new(),
-            undo_redo: UndoRedo::new(),
+            status_message: None,
+            commands: CommandRegistry::new(),
+            command_bar: None,
+            command_history: vec![],
+            search_bar: None,
+            search: SearchQuery::default(),
+            replacement: vec![],
+            file_finder: None,
+            project_search: None,
+            file_tree_area: Rect::default(),
+            editor_area: Rect::default(),
+            last_click: None,
+            should_quit: false,
+            keymap: Keymap::new(),
+            pending_keys: vec![],
+        }
+    }
+
+    /// Settings for the file, with the language overrides applied
+    pub fn get_config_for(&self, path: &Path) -> Config {
+        let mut config = self.config.clone();
+        let language = detect_language(path);
+
+        if let Some(language) = language
+            && let Some(language_config) = self.language_configs.get(language)
+        {
+            language_config.apply(&mut config);
+        }
+        if config.auto_pairs.is_none() {
+            config.auto_pairs = Some(get_default_auto_pairs(language).to_string());
+        }
+
+        config
+    }
+
+    /// Switch between the side panel and the editor. We can focus the editor
+    /// only if some file is opened.
+    pub fn toggle_focus(&mut self) {
+        match self.focus {
+            Focus::FileTree | Focus::ProjectSearch if !self.buffers.is_empty() => {
+                self.focus = Focus::Editor
+            }
+            Focus::FileTree | Focus::ProjectSearch => {}
+            Focus::Editor => self.focus = self.get_side_panel_focus(),
+        }
+    }
+
+    /// The project search replaces the file tree while it is opened
+    pub fn get_side_panel_focus(&self) -> Focus {
+        if self.project_search.is_some() {
+            Focus::ProjectSearch
+        } else {
+            Focus::FileTree
         }
     }
 
     pub fn read_directory(&mut self, path: PathBuf) {
-        // TODO: save the error as a directory state
-        let dir_entries = fs::read_dir(&path).expect("Could not read directory");
+        let dir_entries = match fs::read_dir(&path) {
+            Ok(dir_entries) => dir_entries,
+            Err(error) => {
+                // we still insert an empty list so that we don't try to read it on every render
+                self.status_message = Some(format!("Could not read directory: {error}"));
+                self.file_tree.insert(path, vec![]);
+                return;
+            }
+        };
 
-        let values: Vec<FileTreeEntry> = dir_entries
+        let mut values: Vec<FileTreeEntry> = dir_entries
             .filter_map(|result| result.ok())
             .map(|entry| FileTreeEntry::new(entry.path()))
             .collect();
 
+        // directories go first, and both groups are sorted alphabetically
+        values.sort_by_cached_key(|entry| {
+            let name = entry
+                .path()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            (!entry.is_dir(), name)
+        });
+
         self.file_tree.insert(path, values);
     }
 }
@@ -0,0 +1,263 @@
+use std::ops::Range;
+
+use super::display_width::LineLayout;
+use super::editor::UIState;
+use super::undo_redo::UndoRedo;
+
+/// A rectangle of text: the same cells on every line between the anchor and
+/// the head. It is turned into a cursor with a selection on every line, so
+/// typing, deleting and copying work like with any other cursors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockSelection {
+    /// Line (from `1`) and the cell (from `0`) where the selection started
+    pub anchor: (usize, usize),
+    /// The corner which moves; the cell can be past the end of the line
+    pub head: (usize, usize),
+}
+
+impl BlockSelection {
+    /// Cells between the anchor and the head, the right one is not included
+    fn get_cells(&self) -> (usize, usize) {
+        (
+            self.anchor.1.min(self.head.1),
+            self.anchor.1.max(self.head.1),
+        )
+    }
+}
+
+pub enum BlockDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl UIState {
+    /// Lines are never wrapped here, otherwise cells of different rows
+    /// would end up in the same rectangle
+    fn get_unwrapped_layout(&self, line: usize) -> LineLayout {
+        LineLayout::new(&self.lines.get_line(line - 1), self.tab_width, None)
+    }
+
+    fn get_cursor_cell(&self) -> usize {
+        self.get_unwrapped_layout(self.cursor_line)
+            .get_position(self.cursor_column - 1)
+            .1
+    }
+
+    /// Grow or shrink the block from the head; without a block, it starts
+    /// at the cursor and the usual selection is dropped
+    pub fn extend_block_selection(&mut self, direction: BlockDirection) {
+        let mut block = self.block_selection.unwrap_or_else(|| {
+            let position = (self.cursor_line, self.get_cursor_cell());
+            BlockSelection {
+                anchor: position,
+                head: position,
+            }
+        });
+
+        let (line, cell) = block.head;
+        let layout = self.get_unwrapped_layout(line);
+        // the cells where characters can be put, past the end there is
+        // a space for every cell
+        let line_width = layout.get_position(usize::MAX).1;
+        let mut cluster_cells = layout
+            .positions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| layout.widths.get(*index).is_none_or(|width| *width > 0))
+            .map(|(_, (_, x))| *x);
+
+        block.head = match direction {
+            BlockDirection::Up => (line.saturating_sub(1).max(1), cell),
+            BlockDirection::Down => ((line + 1).min(self.lines.len_lines()), cell),
+            BlockDirection::Left if cell > line_width => (line, cell - 1),
+            BlockDirection::Left => (line, cluster_cells.rfind(|x| *x < cell).unwrap_or(0)),
+            BlockDirection::Right if cell >= line_width => (line, cell + 1),
+            BlockDirection::Right => (
+                line,
+                cluster_cells.find(|x| *x > cell).unwrap_or(line_width),
+            ),
+        };
+
+        self.set_block_selection(block);
+    }
+
+    /// Stretch the block to the point while the mouse is dragged with `alt`
+    pub fn drag_block_to(&mut self, x: usize, y: usize) {
+        let (line, _) = self.get_position_at(x, y);
+        // the line number is in front of the text
+        let cell = self.horizontal_scroll_offset
+            + x.saturating_sub(self.editor_offset_x + self.prefix_len + 1);
+
+        let anchor = match self.block_selection {
+            Some(block) => block.anchor,
+            None => (self.cursor_line, self.get_cursor_cell()),
+        };
+        self.set_block_selection(BlockSelection {
+            anchor,
+            head: (line, cell),
+        });
+    }
+
+    /// Replace all cursors with a cursor on every line of the block; the one
+    /// on the line of the head is the primary one
+    pub fn set_block_selection(&mut self, block: BlockSelection) {
+        let (left, right) = block.get_cells();
+        let (first_line, last_line) = (
+            block.anchor.0.min(block.head.0),
+            block.anchor.0.max(block.head.0),
+        );
+
+        let mut cursors = vec![];
+        for line in first_line..=last_line {
+            let layout = self.get_unwrapped_layout(line);
+            let start = layout.get_index_at(0, left) + 1;
+            let end = layout.get_index_at(0, right) + 1;
+            let (from, to) = if block.anchor.1 <= block.head.1 {
+                (start, end)
+            } else {
+                (end, start)
+            };
+
+            self.cursor_line = line;
+            self.cursor_column = to;
+            self.vertical_offset_target = 0;
+            self.selection = None;
+            if from != to {
+                self.set_selection((line, from), (line, to));
+            }
+            cursors.push(self.take_primary_cursor());
+        }
+
+        let head_index = block.head.0 - first_line;
+        self.set_primary_cursor(cursors.remove(head_index));
+        self.extra_cursors = cursors;
+        self.block_selection = Some(block);
+        self.handle_cursor_scrolling();
+    }
+
+    /// An empty block from the cursor down, with a cursor on every line
+    pub(super) fn start_block_column(&mut self, lines_number: usize) {
+        let cell = self.get_cursor_cell();
+        let last_line = (self.cursor_line + lines_number - 1).min(self.lines.len_lines());
+        self.set_block_selection(BlockSelection {
+            anchor: (self.cursor_line, cell),
+            head: (last_line, cell),
+        });
+    }
+
+    /// Lines which end before the block are padded with spaces, so text
+    /// typed or pasted there lines up with the rest of the column
+    pub fn pad_to_block(&mut self, undo_redo: &mut UndoRedo) {
+        let Some(block) = self.block_selection else {
+            return;
+        };
+        if self.selection.is_some() {
+            return;
+        }
+
+        let (left, _) = block.get_cells();
+        let cell = self.get_cursor_cell();
+        if cell < left && self.cursor_column == self.get_line_len(self.cursor_line - 1) + 1 {
+            self.handle_paste(" ".repeat(left - cell), undo_redo);
+        }
+    }
+
+    /// Cells of the block past the end of the line, they have no characters
+    /// but are still drawn, so the block looks like a rectangle
+    pub fn get_block_cells_past_line_end(&self, line: usize) -> Option<Range<usize>> {
+        let block = self.block_selection?;
+        if !(block.anchor.0.min(block.head.0)..=block.anchor.0.max(block.head.0)).contains(&line) {
+            return None;
+        }
+
+        let (left, right) = block.get_cells();
+        let line_width = self.get_unwrapped_layout(line).get_position(usize::MAX).1;
+        (right > line_width).then(|| left.max(line_width)..right)
+    }
+
+    /// A line which ends before a block wider than a column has nothing
+    /// selected, and deleting there should not touch the text before it
+    pub fn is_outside_of_block(&self) -> bool {
+        self.block_selection.is_some_and(|block| {
+            let (left, right) = block.get_cells();
+            left != right && self.selection.is_none()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::create_ui_state;
+
+    fn type_text(ui_state: &mut UIState, undo_redo: &mut UndoRedo, text: &str) {
+        for character in text.chars() {
+            ui_state.for_each_cursor(undo_redo, |ui_state, undo_redo| {
+                ui_state.pad_to_block(undo_redo);
+                ui_state.insert_character(character, undo_redo);
+            });
+        }
+    }
+
+    #[test]
+    fn selects_a_rectangle() {
+        let mut ui_state = create_ui_state("abcdef\nab\nabcdef");
+        ui_state.cursor_column = 2;
+
+        ui_state.extend_block_selection(BlockDirection::Down);
+        ui_state.extend_block_selection(BlockDirection::Down);
+        ui_state.extend_block_selection(BlockDirection::Right);
+        ui_state.extend_block_selection(BlockDirection::Right);
+        ui_state.extend_block_selection(BlockDirection::Right);
+
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 5), (1, 5), (2, 3)]);
+        assert_eq!(ui_state.get_selected_texts(), ["bcd", "b", "bcd"]);
+        // the short line still shows the whole width of the block
+        assert_eq!(ui_state.get_block_cells_past_line_end(2), Some(2..4));
+        assert_eq!(ui_state.get_block_cells_past_line_end(1), None);
+
+        let mut undo_redo = UndoRedo::new();
+        ui_state.for_each_cursor(&mut undo_redo, |ui_state, undo_redo| {
+            if !ui_state.is_outside_of_block() {
+                ui_state.remove_previous_character(undo_redo);
+            }
+        });
+        assert_eq!(ui_state.lines.to_text(), "aef\na\naef");
+        assert!(ui_state.block_selection.is_none());
+    }
+
+    #[test]
+    fn pads_short_lines_when_typing() {
+        let mut ui_state = create_ui_state("long line\n\nlong");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.cursor_column = 5;
+
+        ui_state.extend_block_selection(BlockDirection::Down);
+        ui_state.extend_block_selection(BlockDirection::Down);
+        type_text(&mut ui_state, &mut undo_redo, "|");
+        assert_eq!(ui_state.lines.to_text(), "long| line\n    |\nlong|");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "long line\n\nlong");
+    }
+
+    #[test]
+    fn copies_and_pastes_columns() {
+        let mut ui_state = create_ui_state("ab\ncd\nxy\nxy");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.extend_block_selection(BlockDirection::Right);
+        ui_state.extend_block_selection(BlockDirection::Down);
+        assert_eq!(ui_state.copy_selected_texts(), "a\nc");
+
+        ui_state.clear_extra_cursors();
+        ui_state.go_to_line(3, 2);
+        ui_state.paste_at_cursors(String::from("a\nc"), &mut undo_redo);
+        assert_eq!(ui_state.lines.to_text(), "ab\ncd\nxay\nxcy");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "ab\ncd\nxy\nxy");
+    }
+}
@@ -0,0 +1,346 @@
+use crossterm::event::KeyModifiers;
+
+use super::app::AppState;
+use super::editor::UIState;
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+/// Brackets are not searched further than this, so huge files stay fast
+const MAX_SCAN_LINES: usize = 5000;
+
+/// A bracket next to the cursor and its partner; positions are (line, column),
+/// both from `1`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BracketMatch {
+    pub bracket: (usize, usize),
+    /// `None` if the bracket is never closed (or opened)
+    pub partner: Option<(usize, usize)>,
+    /// The partner is a bracket of another kind, like in `(]`
+    pub is_mismatched: bool,
+    /// The partner was not found within `MAX_SCAN_LINES`, it can still be
+    /// further away
+    pub is_beyond_limit: bool,
+}
+
+/// The last match together with the cursor position and the buffer version
+/// it was found for, so it is not searched again on every render
+pub struct CachedBracketMatch {
+    position: (usize, usize),
+    version: usize,
+    bracket_match: Option<BracketMatch>,
+}
+
+/// Tells if the character at (line, byte column), both from `0`, is code
+/// and not a part of a string or a comment
+pub type IsCode<'a> = &'a dyn Fn(usize, usize) -> bool;
+
+fn get_pair(character: char) -> Option<(char, char)> {
+    BRACKETS
+        .into_iter()
+        .find(|(opening, closing)| *opening == character || *closing == character)
+}
+
+impl UIState {
+    /// The bracket right at the cursor, or the one right before it
+    fn get_bracket_at_cursor(&self, is_code: IsCode) -> Option<(usize, char)> {
+        let line: Vec<char> = self.lines.line_chars(self.cursor_line - 1).collect();
+        let index = self.cursor_column - 1;
+
+        [Some(index), index.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|index| line.get(index).map(|character| (index, *character)))
+            .find(|(index, character)| {
+                let byte_column = line[..*index].iter().map(|c| c.len_utf8()).sum();
+                get_pair(*character).is_some() && is_code(self.cursor_line - 1, byte_column)
+            })
+    }
+
+    /// Find the bracket next to the cursor and the one matching it; nested
+    /// brackets of all kinds are skipped, as well as brackets which are not code
+    pub fn find_bracket_match(&self, is_code: IsCode) -> Option<BracketMatch> {
+        let (index, bracket) = self.get_bracket_at_cursor(is_code)?;
+        let (opening, closing) = get_pair(bracket)?;
+        let is_forward = bracket == opening;
+        let line_index = self.cursor_line - 1;
+
+        let lines_number = self.lines.len_lines();
+        let line_indexes: Vec<usize> = if is_forward {
+            (line_index..lines_number.min(line_index + MAX_SCAN_LINES)).collect()
+        } else {
+            (line_index.saturating_sub(MAX_SCAN_LINES)..=line_index)
+                .rev()
+                .collect()
+        };
+
+        // closing brackets we wait for, going forward (or opening ones backward)
+        let mut expected: Vec<char> = vec![];
+        for current_line in line_indexes {
+            let line: Vec<char> = self.lines.line_chars(current_line).collect();
+            let mut byte_columns = Vec::with_capacity(line.len());
+            let mut byte_column = 0;
+            for character in &line {
+                byte_columns.push(byte_column);
+                byte_column += character.len_utf8();
+            }
+
+            let indexes: Vec<usize> = match (current_line == line_index, is_forward) {
+                (true, true) => (index + 1..line.len()).collect(),
+                (true, false) => (0..index).rev().collect(),
+                (false, true) => (0..line.len()).collect(),
+                (false, false) => (0..line.len()).rev().collect(),
+            };
+
+            for character_index in indexes {
+                let character = line[character_index];
+                let Some((pair_opening, pair_closing)) = get_pair(character) else {
+                    continue;
+                };
+                if !is_code(current_line, byte_columns[character_index]) {
+                    continue;
+                }
+
+                let (nested, closing_nested) = if is_forward {
+                    (pair_opening, pair_closing)
+                } else {
+                    (pair_closing, pair_opening)
+                };
+                if character == nested {
+                    expected.push(closing_nested);
+                    continue;
+                }
+
+                // a wrong bracket inside still closes the nested one
+                if expected.pop().is_none() {
+                    let partner_character = if is_forward { closing } else { opening };
+                    return Some(BracketMatch {
+                        bracket: (line_index + 1, index + 1),
+                        partner: Some((current_line + 1, character_index + 1)),
+                        is_mismatched: character != partner_character,
+                        is_beyond_limit: false,
+                    });
+                }
+            }
+        }
+
+        let is_beyond_limit = if is_forward {
+            line_index + MAX_SCAN_LINES < lines_number
+        } else {
+            line_index > MAX_SCAN_LINES
+        };
+        Some(BracketMatch {
+            bracket: (line_index + 1, index + 1),
+            partner: None,
+            is_mismatched: !is_beyond_limit,
+            is_beyond_limit,
+        })
+    }
+
+    /// Same as `find_bracket_match`, but the result is reused until the
+    /// cursor moves or the text changes
+    pub fn get_bracket_match(&mut self, is_code: IsCode) -> Option<BracketMatch> {
+        let position = (self.cursor_line, self.cursor_column);
+        let version = self.lines.version();
+        if let Some(cache) = &self.bracket_match_cache
+            && cache.position == position
+            && cache.version == version
+        {
+            return cache.bracket_match;
+        }
+
+        let bracket_match = self.find_bracket_match(is_code);
+        self.bracket_match_cache = Some(CachedBracketMatch {
+            position,
+            version,
+            bracket_match,
+        });
+        bracket_match
+    }
+
+    /// Move the cursor to the bracket matching the one next to it; with
+    /// `shift` the selection is extended to include both brackets
+    pub fn go_to_matching_bracket(
+        &mut self,
+        is_code: IsCode,
+        modifiers: &KeyModifiers,
+    ) -> Result<(), String> {
+        let bracket_match = self
+            .find_bracket_match(is_code)
+            .ok_or_else(|| String::from("No bracket at the cursor"))?;
+        let (line, column) = bracket_match.partner.ok_or_else(|| {
+            if bracket_match.is_beyond_limit {
+                format!("No matching bracket within {MAX_SCAN_LINES} lines")
+            } else {
+                String::from("No matching bracket")
+            }
+        })?;
+
+        self.clear_extra_cursors();
+        let is_forward = (line, column) > bracket_match.bracket;
+        if modifiers.contains(KeyModifiers::SHIFT) && self.selection.is_none() {
+            // the selection starts at the bracket itself, not at the cursor
+            let (bracket_line, bracket_column) = bracket_match.bracket;
+            let start_column = if is_forward {
+                bracket_column
+            } else {
+                bracket_column + 1
+            };
+            (self.cursor_line, self.cursor_column) = (bracket_line, start_column);
+        }
+
+        self.start_selection(modifiers);
+        self.cursor_line = line;
+        self.cursor_column = if is_forward && modifiers.contains(KeyModifiers::SHIFT) {
+            column + 1
+        } else {
+            column
+        };
+        self.vertical_offset_target = 0;
+        self.adjust_selection();
+        self.handle_cursor_scrolling();
+
+        Ok(())
+    }
+}
+
+impl AppState {
+    /// Jump to the matching bracket in the active buffer; brackets in strings
+    /// and comments are skipped when the language has a syntax tree
+    pub fn go_to_matching_bracket(&mut self, modifiers: &KeyModifiers) -> Result<(), String> {
+        let buffer = self
+            .get_active_buffer_mut()
+            .ok_or_else(|| String::from("No file is opened"))?;
+
+        if let Some(highlighter) = &mut buffer.highlighter {
+            highlighter.update(buffer.ui_state.lines.as_mut());
+        }
+        let highlighter = buffer.highlighter.as_ref();
+        let is_code = |line: usize, byte_column: usize| {
+            highlighter.is_none_or(|highlighter| highlighter.is_code_at(line, byte_column))
+        };
+
+        buffer.ui_state.go_to_matching_bracket(&is_code, modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::create_ui_state;
+    use crate::app_state::undo_redo::UndoRedo;
+    use std::cell::Cell;
+
+    fn is_code(_: usize, _: usize) -> bool {
+        true
+    }
+
+    #[test]
+    fn matches_brackets_across_lines() {
+        let mut ui_state = create_ui_state("fn main() {\n    let x = [(1), 2];\n}");
+        ui_state.cursor_column = 11;
+
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.bracket, (1, 11));
+        assert_eq!(bracket_match.partner, Some((3, 1)));
+        assert!(!bracket_match.is_mismatched);
+
+        // the bracket before the cursor
+        ui_state.go_to_line(2, 21);
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, Some((2, 13)));
+    }
+
+    #[test]
+    fn skips_brackets_which_are_not_code() {
+        let ui_state = create_ui_state("call(\")\", x)");
+        // the string is from the byte 5 to the byte 7
+        let is_code = |_: usize, byte_column: usize| !(5..8).contains(&byte_column);
+
+        let bracket_match = ui_state.find_bracket_match(&is_code);
+        assert_eq!(bracket_match, None);
+
+        let mut ui_state = ui_state;
+        ui_state.cursor_column = 5;
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, Some((1, 12)));
+    }
+
+    #[test]
+    fn flags_mismatched_brackets() {
+        let mut ui_state = create_ui_state("(a]\n{b");
+
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, Some((1, 3)));
+        assert!(bracket_match.is_mismatched);
+
+        ui_state.go_to_line(2, 1);
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, None);
+        assert!(bracket_match.is_mismatched);
+        assert!(!bracket_match.is_beyond_limit);
+    }
+
+    #[test]
+    fn stops_searching_at_the_limit() {
+        let text = format!("{{\n{}}}", "x\n".repeat(MAX_SCAN_LINES));
+        let mut ui_state = create_ui_state(&text);
+
+        let bracket_match = ui_state.find_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, None);
+        assert!(bracket_match.is_beyond_limit);
+        assert!(!bracket_match.is_mismatched);
+        assert_eq!(
+            ui_state.go_to_matching_bracket(&is_code, &KeyModifiers::NONE),
+            Err(format!("No matching bracket within {MAX_SCAN_LINES} lines"))
+        );
+    }
+
+    #[test]
+    fn reuses_the_match_until_something_changes() {
+        let mut ui_state = create_ui_state("(a)");
+        let mut undo_redo = UndoRedo::new();
+        let calls = Cell::new(0);
+        let is_code = |_: usize, _: usize| {
+            calls.set(calls.get() + 1);
+            true
+        };
+
+        let bracket_match = ui_state.get_bracket_match(&is_code);
+        let calls_number = calls.get();
+        assert_eq!(ui_state.get_bracket_match(&is_code), bracket_match);
+        assert_eq!(calls.get(), calls_number);
+
+        ui_state.cursor_column = 2;
+        ui_state.insert_character('b', &mut undo_redo);
+        ui_state.cursor_column = 1;
+        let bracket_match = ui_state.get_bracket_match(&is_code).unwrap();
+        assert_eq!(bracket_match.partner, Some((1, 4)));
+        assert!(calls.get() > calls_number);
+    }
+
+    #[test]
+    fn jumps_and_selects_to_the_matching_bracket() {
+        let mut ui_state = create_ui_state("if (a && (b)) {}");
+        ui_state.cursor_column = 4;
+
+        ui_state
+            .go_to_matching_bracket(&is_code, &KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(ui_state.cursor_column, 13);
+        ui_state
+            .go_to_matching_bracket(&is_code, &KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(ui_state.cursor_column, 4);
+
+        ui_state
+            .go_to_matching_bracket(&is_code, &KeyModifiers::SHIFT)
+            .unwrap();
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 4), (1, 14)));
+
+        ui_state.go_to_line(1, 2);
+        assert_eq!(
+            ui_state.go_to_matching_bracket(&is_code, &KeyModifiers::NONE),
+            Err(String::from("No bracket at the cursor"))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::app::{AppState, Config, ConfigOverride, Focus};
+use super::editor::UIState;
+use super::editorconfig::resolve_editorconfig;
+use super::file_format::FileFormat;
+use super::highlighter::Highlighter;
+use super::language::{detect_language, get_comment_tokens};
+use super::save_file::get_file_name;
+use super::text_buffer::{RopeBuffer, TextBuffer};
+use super::undo_redo::UndoRedo;
+
+/// A single opened file. Every buffer has its own lines, cursor, selection,
+/// scroll position and undo history, so switching between them is free.
+pub struct Buffer {
+    pub file_path: PathBuf,
+    pub ui_state: UIState,
+    pub undo_redo: UndoRedo,
+    /// Only set for languages we have a grammar for
+    pub highlighter: Option<Highlighter>,
+    /// Settings from `.editorconfig` files, resolved once when the file is opened
+    pub editorconfig: ConfigOverride,
+}
+
+impl Buffer {
+    pub fn open(file_path: PathBuf) -> io::Result<Buffer> {
+        let file_content = fs::read_to_string(&file_path)?;
+
+        let (file_format, text) = FileFormat::parse(&file_content);
+        let mut text_buffer = RopeBuffer::from_text(&text);
+        text_buffer.set_line_endings(file_format.parse_line_endings(&file_content));
+        let highlighter = Highlighter::for_path(&file_path, text.len());
+        if highlighter.is_some() {
+            text_buffer.track_edits();
+        }
+        let lines_number = text_buffer.len_lines();
+
+        let mut ui_state =
+            UIState::with_text_buffer(lines_number.to_string().len(), Box::new(text_buffer));
+        ui_state.file_format = file_format;
+        ui_state.comment_tokens = get_comment_tokens(detect_language(&file_path));
+
+        let editorconfig = resolve_editorconfig(&file_path);
+
+        Ok(Buffer {
+            file_path,
+            ui_state,
+            undo_redo: UndoRedo::new(),
+            highlighter,
+            editorconfig,
+        })
+    }
+
+    pub fn get_name(&self) -> String {
+        get_file_name(&self.file_path)
+    }
+}
+
+/// Questions we need to ask before performing an action
+pub enum Prompt {
+    /// Closing a buffer with unsaved changes, the value is the buffer index
+    CloseDirtyBuffer(usize),
+    /// Quitting while some buffers have unsaved changes
+    QuitWithDirtyBuffers,
+}
+
+impl AppState {
+    pub fn get_active_buffer(&self) -> Option<&Buffer> {
+        self.buffers.get(self.active_buffer)
+    }
+
+    /// Settings for the buffer: config files, then the language overrides
+    /// and then `.editorconfig` files, which win as they belong to the project;
+    /// options changed with the `set` command win over everything
+    pub fn get_buffer_config(&self, buffer: &Buffer) -> Config {
+        let mut config = self.get_config_for(&buffer.file_path);
+        buffer.editorconfig.apply(&mut config);
+        self.command_config.apply(&mut config);
+        config
+    }
+
+    pub fn get_active_buffer_mut(&mut self) -> Option<&mut Buffer> {
+        self.buffers.get_mut(self.active_buffer)
+    }
+
+    /// Open the file in a new buffer, or switch to it if it is already opened
+    pub fn open_file(&mut self, file_path: PathBuf) -> io::Result<()> {
+        if let Some(index) = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.file_path == file_path)
+        {
+            self.switch_to_buffer(index);
+            return Ok(());
+        }
+
+        let mut buffer = Buffer::open(file_path)?;
+        let config = self.get_buffer_config(&buffer);
+        buffer.ui_state.soft_wrap = config.soft_wrap;
+        buffer.ui_state.tab_width = config.get_tab_width();
+        buffer.ui_state.word_rules = config.get_word_rules();
+        buffer.ui_state.auto_pairs = config.get_auto_pairs();
+        self.buffers.push(buffer);
+        self.switch_to_buffer(self.buffers.len() - 1);
+
+        Ok(())
+    }
+
+    pub fn switch_to_buffer(&mut self, index: usize) {
+        if index < self.buffers.len() {
+            self.active_buffer = index;
+            self.focus = Focus::Editor;
+        }
+    }
+
+    pub fn switch_to_next_buffer(&mut self) {
+        if !self.buffers.is_empty() {
+            self.switch_to_buffer((self.active_buffer + 1) % self.buffers.len());
+        }
+    }
+
+    pub fn switch_to_previous_buffer(&mut self) {
+        if !self.buffers.is_empty() {
+            let buffers_num = self.buffers.len();
+            self.switch_to_buffer((self.active_buffer + buffers_num - 1) % buffers_num);
+        }
+    }
+
+    /// Close the active buffer, asking first if it has unsaved changes
+    pub fn request_close_active_buffer(&mut self) {
+        let Some(buffer) = self.get_active_buffer() else {
+            return;
+        };
+
+        if buffer.ui_state.is_dirty {
+            self.prompt = Some(Prompt::CloseDirtyBuffer(self.active_buffer));
+        } else {
+            self.close_buffer(self.active_buffer);
+        }
+    }
+
+    /// Quit the editor, asking first if any buffer has unsaved changes
+    pub fn request_quit(&mut self) {
+        if self.buffers.iter().any(|buffer| buffer.ui_state.is_dirty) {
+            self.prompt = Some(Prompt::QuitWithDirtyBuffers);
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// Close the buffer without checking for unsaved changes
+    pub fn close_buffer(&mut self, index: usize) {
+        if index >= self.buffers.len() {
+            return;
+        }
+
+        let buffer = self.buffers.remove(index);
+        self.closed_buffers.push(buffer.file_path);
+
+        if self.active_buffer > index || self.active_buffer >= self.buffers.len() {
+            self.active_buffer = self.active_buffer.saturating_sub(1);
+        }
+
+        if self.buffers.is_empty() {
+            self.focus = self.get_side_panel_focus();
+        }
+    }
+
+    /// Open the most recently closed buffer again
+    pub fn reopen_closed_buffer(&mut self) {
+        let Some(file_path) = self.closed_buffers.pop() else {
+            return;
+        };
+
+        if let Err(error) = self.open_file(file_path) {
+            self.status_message = Some(format!("Could not open the file: {error}"));
+        }
+    }
+
+    /// Answer to the current prompt; `None` means the user cancelled it
+    pub fn answer_prompt(&mut self, save_changes: Option<bool>) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+
+        match prompt {
+            Prompt::CloseDirtyBuffer(index) => match save_changes {
+                Some(true) => {
+                    self.active_buffer = index;
+                    self.save_file();
+
+                    // if saving failed, the error is shown and the buffer stays opened
+                    if let Some(buffer) = self.buffers.get(index)
+                        && !buffer.ui_state.is_dirty
+                    {
+                        self.close_buffer(index);
+                    }
+                }
+                Some(false) => self.close_buffer(index),
+                None => {}
+            },
+            Prompt::QuitWithDirtyBuffers => match save_changes {
+                Some(true) => {
+                    let active_buffer = self.active_buffer;
+                    for index in 0..self.buffers.len() {
+                        if self.buffers[index].ui_state.is_dirty {
+                            self.active_buffer = index;
+                            self.save_file();
+                        }
+                    }
+                    self.active_buffer = active_buffer;
+
+                    // if saving failed, the error is shown and the editor stays opened
+                    self.should_quit = !self.buffers.iter().any(|buffer| buffer.ui_state.is_dirty);
+                }
+                Some(false) => self.should_quit = true,
+                None => {}
+            },
+        }
+    }
+
+    pub fn get_prompt_message(&self) -> Option<String> {
+        match self.prompt.as_ref()? {
+            Prompt::CloseDirtyBuffer(index) => {
+                let name = self.buffers.get(*index)?.get_name();
+                Some(format!("Save changes to {name}? (y)es, (n)o, (c)ancel"))
+            }
+            Prompt::QuitWithDirtyBuffers => {
+                let names: Vec<String> = self
+                    .buffers
+                    .iter()
+                    .filter(|buffer| buffer.ui_state.is_dirty)
+                    .map(|buffer| buffer.get_name())
+                    .collect();
+                Some(format!(
+                    "Save changes to {} before quitting? (y)es, (n)o, (c)ancel",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils;
+
+    fn create_test_directory(name: &str) -> PathBuf {
+        let directory = test_utils::create_test_directory(name);
+        fs::write(directory.join("first.txt"), "First\n").unwrap();
+        fs::write(directory.join("second.txt"), "Second\n").unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn opens_and_switches_buffers() {
+        let directory = create_test_directory("buffers-switch");
+        let mut app_state = AppState::new(directory.clone());
+
+        app_state.open_file(directory.join("first.txt")).unwrap();
+        app_state.open_file(directory.join("second.txt")).unwrap();
+
+        assert_eq!(app_state.buffers.len(), 2);
+        assert_eq!(app_state.active_buffer, 1);
+
+        app_state.switch_to_next_buffer();
+        assert_eq!(app_state.active_buffer, 0);
+
+        app_state.switch_to_previous_buffer();
+        assert_eq!(app_state.active_buffer, 1);
+
+        // opening the same file again only switches to it
+        app_state.open_file(directory.join("first.txt")).unwrap();
+        assert_eq!(app_state.buffers.len(), 2);
+        assert_eq!(app_state.active_buffer, 0);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn closes_and_reopens_buffers() {
+        let directory = create_test_directory("buffers-close");
+        let mut app_state = AppState::new(directory.clone());
+
+        app_state.open_file(directory.join("first.txt")).unwrap();
+        app_state.open_file(directory.join("second.txt")).unwrap();
+
+        app_state.request_close_active_buffer();
+
+        assert_eq!(app_state.buffers.len(), 1);
+        assert_eq!(
+            app_state.get_active_buffer().unwrap().get_name(),
+            "first.txt"
+        );
+
+        app_state.reopen_closed_buffer();
+
+        assert_eq!(app_state.buffers.len(), 2);
+        assert_eq!(
+            app_state.get_active_buffer().unwrap().get_name(),
+            "second.txt"
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn asks_before_closing_dirty_buffer() {
+        let directory = create_test_directory("buffers-dirty");
+        let mut app_state = AppState::new(directory.clone());
+
+        app_state.open_file(directory.join("first.txt")).unwrap();
+        app_state.get_active_buffer_mut().unwrap().ui_state.is_dirty = true;
+
+        app_state.request_close_active_buffer();
+
+        assert!(app_state.prompt.is_some());
+        assert_eq!(app_state.buffers.len(), 1);
+
+        app_state.answer_prompt(None);
+
+        assert!(app_state.prompt.is_none());
+        assert_eq!(app_state.buffers.len(), 1);
+
+        app_state.request_close_active_buffer();
+        app_state.answer_prompt(Some(false));
+
+        assert!(app_state.buffers.is_empty());
+        assert!(app_state.focus == Focus::FileTree);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn asks_before_quitting_with_dirty_buffers() {
+        let directory = create_test_directory("buffers-quit");
+        let mut app_state = AppState::new(directory.clone());
+
+        app_state.open_file(directory.join("first.txt")).unwrap();
+        app_state.open_file(directory.join("second.txt")).unwrap();
+        app_state.buffers[0]
+            .ui_state
+            .insert_character('!', &mut UndoRedo::new());
+
+        app_state.request_quit();
+        assert!(!app_state.should_quit);
+        assert_eq!(
+            app_state.get_prompt_message().unwrap(),
+            "Save changes to first.txt before quitting? (y)es, (n)o, (c)ancel"
+        );
+
+        app_state.answer_prompt(Some(true));
+        assert!(app_state.should_quit);
+        assert_eq!(app_state.active_buffer, 1);
+        assert_eq!(
+            fs::read_to_string(directory.join("first.txt")).unwrap(),
+            "!First\n"
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
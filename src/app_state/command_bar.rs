@@ -0,0 +1,202 @@
+use super::app::AppState;
+use super::commands::Command;
+
+/// State of the command bar while it is opened. The input is stored as
+/// characters, so the cursor can be a simple index.
+pub struct CommandBar {
+    pub input: Vec<char>,
+    pub cursor: usize,
+    /// Position in the history while navigating with Up/Down; `None` means
+    /// we are editing a new command
+    history_index: Option<usize>,
+}
+
+impl AppState {
+    pub fn open_command_bar(&mut self) {
+        self.open_command_bar_with_input("");
+    }
+
+    /// Open the command bar with a part of the command already typed
+    pub fn open_command_bar_with_input(&mut self, input: &str) {
+        let input: Vec<char> = input.chars().collect();
+        self.command_bar = Some(CommandBar {
+            cursor: input.len(),
+            input,
+            history_index: None,
+        });
+    }
+
+    pub fn close_command_bar(&mut self) {
+        self.command_bar = None;
+    }
+
+    pub fn command_bar_insert_char(&mut self, character: char) {
+        if let Some(command_bar) = &mut self.command_bar {
+            command_bar.input.insert(command_bar.cursor, character);
+            command_bar.cursor += 1;
+        }
+    }
+
+    pub fn command_bar_remove_previous_char(&mut self) {
+        if let Some(command_bar) = &mut self.command_bar
+            && command_bar.cursor > 0
+        {
+            command_bar.cursor -= 1;
+            command_bar.input.remove(command_bar.cursor);
+        }
+    }
+
+    pub fn command_bar_move_left(&mut self) {
+        if let Some(command_bar) = &mut self.command_bar {
+            command_bar.cursor = command_bar.cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn command_bar_move_right(&mut self) {
+        if let Some(command_bar) = &mut self.command_bar {
+            command_bar.cursor = (command_bar.cursor + 1).min(command_bar.input.len());
+        }
+    }
+
+    /// Names of the commands matching what is typed so far; we complete only
+    /// the command name, so nothing is suggested once arguments are typed
+    pub fn get_command_bar_completions(&self) -> Vec<&Command> {
+        let Some(command_bar) = &self.command_bar else {
+            return vec![];
+        };
+
+        let input = String::from_iter(&command_bar.input);
+        if input.contains(' ') {
+            return vec![];
+        }
+
+        self.commands.complete(&input)
+    }
+
+    /// Replace the typed name with the best matching command
+    pub fn command_bar_complete(&mut self) {
+        let Some(name) = self
+            .get_command_bar_completions()
+            .first()
+            .map(|command| command.name)
+        else {
+            return;
+        };
+
+        if let Some(command_bar) = &mut self.command_bar {
+            command_bar.input = format!("{name} ").chars().collect();
+            command_bar.cursor = command_bar.input.len();
+        }
+    }
+
+    pub fn command_bar_history_previous(&mut self) {
+        let Some(command_bar) = &mut self.command_bar else {
+            return;
+        };
+
+        let index = match command_bar.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.command_history.is_empty() => return,
+            None => self.command_history.len() - 1,
+        };
+
+        command_bar.history_index = Some(index);
+        command_bar.input = self.command_history[index].chars().collect();
+        command_bar.cursor = command_bar.input.len();
+    }
+
+    pub fn command_bar_history_next(&mut self) {
+        let Some(command_bar) = &mut self.command_bar else {
+            return;
+        };
+
+        let Some(index) = command_bar.history_index else {
+            return;
+        };
+
+        if index + 1 < self.command_history.len() {
+            command_bar.history_index = Some(index + 1);
+            command_bar.input = self.command_history[index + 1].chars().collect();
+        } else {
+            command_bar.history_index = None;
+            command_bar.input = vec![];
+        }
+        command_bar.cursor = command_bar.input.len();
+    }
+
+    /// Close the command bar and run the typed command
+    pub fn submit_command_bar(&mut self) {
+        let Some(command_bar) = self.command_bar.take() else {
+            return;
+        };
+
+        let input = String::from_iter(&command_bar.input).trim().to_string();
+        if input.is_empty() {
+            return;
+        }
+
+        if self.command_history.last() != Some(&input) {
+            self.command_history.push(input.clone());
+        }
+
+        self.run_command(&input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn type_text(app_state: &mut AppState, text: &str) {
+        for character in text.chars() {
+            app_state.command_bar_insert_char(character);
+        }
+    }
+
+    fn get_input(app_state: &AppState) -> String {
+        String::from_iter(&app_state.command_bar.as_ref().unwrap().input)
+    }
+
+    #[test]
+    fn completes_and_runs_commands() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        app_state.open_command_bar();
+        type_text(&mut app_state, "wa");
+        app_state.command_bar_remove_previous_char();
+        app_state.command_bar_remove_previous_char();
+        type_text(&mut app_state, "se");
+        app_state.command_bar_complete();
+
+        assert_eq!(get_input(&app_state), "set ");
+
+        type_text(&mut app_state, "whitespaces_amount 8");
+        app_state.submit_command_bar();
+
+        assert!(app_state.command_bar.is_none());
+        assert_eq!(app_state.command_config.whitespaces_amount, Some(8));
+        assert_eq!(app_state.command_history, vec!["set whitespaces_amount 8"]);
+    }
+
+    #[test]
+    fn navigates_history() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        app_state.command_history = vec![String::from("goto 5"), String::from("save")];
+
+        app_state.open_command_bar();
+        app_state.command_bar_history_previous();
+        assert_eq!(get_input(&app_state), "save");
+
+        app_state.command_bar_history_previous();
+        app_state.command_bar_history_previous();
+        assert_eq!(get_input(&app_state), "goto 5");
+
+        app_state.command_bar_history_next();
+        assert_eq!(get_input(&app_state), "save");
+
+        app_state.command_bar_history_next();
+        assert_eq!(get_input(&app_state), "");
+    }
+}
@@ -0,0 +1,783 @@
+use crossterm::event::KeyModifiers;
+
+use super::app::{AppState, Config};
+use super::block_selection::BlockDirection;
+use super::config_file::MAX_WHITESPACES_AMOUNT;
+use super::editor::UIState;
+use super::file_format::LineEnding;
+use super::fuzzy::fuzzy_score;
+use super::search::SearchDirection;
+use super::undo_redo::UndoRedo;
+
+/// Receives the rest of the command bar input as arguments;
+/// the error is shown in the status bar
+pub type AppCommandHandler = fn(&mut AppState, &str) -> Result<(), String>;
+
+/// Works with the active buffer, does nothing if there is none
+pub type EditorCommandHandler = fn(&mut UIState, &mut UndoRedo, &Config);
+
+#[derive(Clone, Copy)]
+pub enum CommandHandler {
+    App(AppCommandHandler),
+    Editor(EditorCommandHandler),
+    /// Same as `Editor`, but runs at every cursor
+    Cursor(EditorCommandHandler),
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub handler: CommandHandler,
+}
+
+/// All actions which can be called by name, from the command bar or key bindings
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        let mut registry = CommandRegistry { commands: vec![] };
+
+        let app_commands: &[(&'static str, &'static str, AppCommandHandler)] = &[
+            ("save", "Save the current file", |app_state, _| {
+                app_state.save_file();
+                Ok(())
+            }),
+            ("open", "Open a file: open <path>", open_file),
+            ("goto", "Go to a line: goto <line>[:<column>]", go_to_line),
+            ("set", "Change an option: set <option> <value>", set_option),
+            ("quit", "Quit the editor", |app_state, _| {
+                app_state.request_quit();
+                Ok(())
+            }),
+            ("close", "Close the current file", |app_state, _| {
+                app_state.request_close_active_buffer();
+                Ok(())
+            }),
+            ("reopen", "Reopen the last closed file", |app_state, _| {
+                app_state.reopen_closed_buffer();
+                Ok(())
+            }),
+            ("next-buffer", "Switch to the next tab", |app_state, _| {
+                app_state.switch_to_next_buffer();
+                Ok(())
+            }),
+            (
+                "previous-buffer",
+                "Switch to the previous tab",
+                |app_state, _| {
+                    app_state.switch_to_previous_buffer();
+                    Ok(())
+                },
+            ),
+            (
+                "toggle-focus",
+                "Switch between the file tree and the editor",
+                |app_state, _| {
+                    app_state.toggle_focus();
+                    Ok(())
+                },
+            ),
+            (
+                "toggle-soft-wrap",
+                "Toggle wrapping of long lines in the current file",
+                |app_state, _| {
+                    if let Some(buffer) = app_state.get_active_buffer_mut() {
+                        buffer.ui_state.toggle_soft_wrap();
+                    }
+                    Ok(())
+                },
+            ),
+            (
+                "line-ending",
+                "Convert line endings: line-ending <lf|crlf>",
+                set_line_ending,
+            ),
+            ("command-bar", "Open the command bar", |app_state, _| {
+                app_state.open_command_bar();
+                Ok(())
+            }),
+            ("find", "Search in the file: find [text]", find),
+            (
+                "find-file",
+                "Open a file by a part of its path",
+                |app_state, _| {
+                    app_state.open_file_finder();
+                    Ok(())
+                },
+            ),
+            ("find-next", "Go to the next match", |app_state, _| {
+                app_state.find_next(SearchDirection::Forward);
+                Ok(())
+            }),
+            (
+                "find-previous",
+                "Go to the previous match",
+                |app_state, _| {
+                    app_state.find_next(SearchDirection::Backward);
+                    Ok(())
+                },
+            ),
+            ("replace", "Replace matches in the file", |app_state, _| {
+                app_state.open_search_bar(true);
+                Ok(())
+            }),
+            (
+                "replace-all",
+                "Replace all matches of the last search",
+                |app_state, _| {
+                    app_state.replace_all();
+                    Ok(())
+                },
+            ),
+            (
+                "toggle-regex",
+                "Toggle regular expressions in search",
+                |app_state, _| {
+                    app_state.toggle_search_regex();
+                    Ok(())
+                },
+            ),
+            (
+                "project-search",
+                "Search in all files: project-search [text]",
+                project_search,
+            ),
+            (
+                "toggle-case-sensitive",
+                "Toggle case sensitive search",
+                |app_state, _| {
+                    app_state.toggle_search_case_sensitive();
+                    Ok(())
+                },
+            ),
+            (
+                "tree-up",
+                "Select the previous file tree entry",
+                |app_state, _| {
+                    app_state.file_tree_move_up();
+                    Ok(())
+                },
+            ),
+            (
+                "tree-down",
+                "Select the next file tree entry",
+                |app_state, _| {
+                    app_state.file_tree_move_down();
+                    Ok(())
+                },
+            ),
+            (
+                "tree-expand",
+                "Expand the selected directory",
+                |app_state, _| {
+                    app_state.file_tree_expand();
+                    Ok(())
+                },
+            ),
+            (
+                "tree-collapse",
+                "Collapse the selected directory",
+                |app_state, _| {
+                    app_state.file_tree_collapse();
+                    Ok(())
+                },
+            ),
+            (
+                "tree-open",
+                "Open the selected file or toggle the directory",
+                |app_state, _| {
+                    app_state.file_tree_open_selected();
+                    Ok(())
+                },
+            ),
+            (
+                "go-to-bracket",
+                "Jump to the bracket matching the one at the cursor",
+                |app_state, _| app_state.go_to_matching_bracket(&KeyModifiers::NONE),
+            ),
+            (
+                "select-to-bracket",
+                "Extend the selection to the matching bracket",
+                |app_state, _| app_state.go_to_matching_bracket(&KeyModifiers::SHIFT),
+            ),
+            ("noop", "Do nothing, useful to disable a key", |_, _| Ok(())),
+        ];
+
+        for (name, description, handler) in app_commands {
+            registry.register(name, description, CommandHandler::App(*handler));
+        }
+
+        let editor_commands: &[(&'static str, &'static str, EditorCommandHandler)] = &[
+            ("copy", "Copy the selection", |ui_state, _, _| {
+                ui_state.handle_copy()
+            }),
+            ("undo", "Undo the last change", |ui_state, undo_redo, _| {
+                undo_redo.undo_action(ui_state)
+            }),
+            (
+                "redo",
+                "Redo the last undone change",
+                |ui_state, undo_redo, _| undo_redo.redo_action(ui_state),
+            ),
+            (
+                "toggle-line-ending",
+                "Switch between LF and CRLF",
+                |ui_state, _, _| ui_state.toggle_line_ending(),
+            ),
+            (
+                "add-cursor-above",
+                "Add a cursor on the line above",
+                |ui_state, _, _| ui_state.add_cursor_vertically(true),
+            ),
+            (
+                "add-cursor-below",
+                "Add a cursor on the line below",
+                |ui_state, _, _| ui_state.add_cursor_vertically(false),
+            ),
+            (
+                "select-next-occurrence",
+                "Select the word, or add a cursor at the next occurrence of the selection",
+                |ui_state, _, _| ui_state.select_next_occurrence(),
+            ),
+            (
+                "move-line-up",
+                "Move the line or the selected lines up",
+                |ui_state, undo_redo, _| ui_state.move_lines(true, undo_redo),
+            ),
+            (
+                "move-line-down",
+                "Move the line or the selected lines down",
+                |ui_state, undo_redo, _| ui_state.move_lines(false, undo_redo),
+            ),
+            (
+                "duplicate-line",
+                "Copy the line or the selected lines below them",
+                |ui_state, undo_redo, _| ui_state.duplicate_lines(undo_redo),
+            ),
+            (
+                "delete-line",
+                "Delete the line or the selected lines",
+                |ui_state, undo_redo, _| ui_state.delete_lines(undo_redo),
+            ),
+            (
+                "join-lines",
+                "Join the selected lines, or the line with the next one",
+                |ui_state, undo_redo, _| ui_state.join_lines(undo_redo),
+            ),
+            (
+                "toggle-comment",
+                "Comment out the line or the selected lines, or uncomment them",
+                |ui_state, undo_redo, _| ui_state.toggle_line_comment(undo_redo),
+            ),
+            (
+                "toggle-block-comment",
+                "Wrap the selection into a block comment, or unwrap it",
+                |ui_state, undo_redo, _| ui_state.toggle_block_comment(undo_redo),
+            ),
+            (
+                "select-block-up",
+                "Extend the block selection up",
+                |ui_state, _, _| ui_state.extend_block_selection(BlockDirection::Up),
+            ),
+            (
+                "select-block-down",
+                "Extend the block selection down",
+                |ui_state, _, _| ui_state.extend_block_selection(BlockDirection::Down),
+            ),
+            (
+                "select-block-left",
+                "Extend the block selection left",
+                |ui_state, _, _| ui_state.extend_block_selection(BlockDirection::Left),
+            ),
+            (
+                "select-block-right",
+                "Extend the block selection right",
+                |ui_state, _, _| ui_state.extend_block_selection(BlockDirection::Right),
+            ),
+            (
+                "single-cursor",
+                "Remove all cursors except the primary one",
+                |ui_state, _, _| ui_state.clear_extra_cursors(),
+            ),
+        ];
+
+        for (name, description, handler) in editor_commands {
+            registry.register(name, description, CommandHandler::Editor(*handler));
+        }
+
+        let cursor_commands: &[(&'static str, &'static str, EditorCommandHandler)] = &[
+            ("move-left", "Move the cursor left", |ui_state, _, _| {
+                ui_state.cursor_move_left(&KeyModifiers::NONE)
+            }),
+            ("move-right", "Move the cursor right", |ui_state, _, _| {
+                ui_state.cursor_move_right(&KeyModifiers::NONE)
+            }),
+            ("move-up", "Move the cursor up", |ui_state, _, _| {
+                ui_state.cursor_move_up(&KeyModifiers::NONE)
+            }),
+            ("move-down", "Move the cursor down", |ui_state, _, _| {
+                ui_state.cursor_move_down(&KeyModifiers::NONE)
+            }),
+            (
+                "move-line-start",
+                "Move the cursor to the line start",
+                |ui_state, _, _| ui_state.cursor_move_line_start(&KeyModifiers::NONE),
+            ),
+            (
+                "move-line-end",
+                "Move the cursor to the line end",
+                |ui_state, _, _| ui_state.cursor_move_line_end(&KeyModifiers::NONE),
+            ),
+            (
+                "select-left",
+                "Extend the selection left",
+                |ui_state, _, _| ui_state.cursor_move_left(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-right",
+                "Extend the selection right",
+                |ui_state, _, _| ui_state.cursor_move_right(&KeyModifiers::SHIFT),
+            ),
+            ("select-up", "Extend the selection up", |ui_state, _, _| {
+                ui_state.cursor_move_up(&KeyModifiers::SHIFT)
+            }),
+            (
+                "select-down",
+                "Extend the selection down",
+                |ui_state, _, _| ui_state.cursor_move_down(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-line-start",
+                "Extend the selection to the line start",
+                |ui_state, _, _| ui_state.cursor_move_line_start(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-line-end",
+                "Extend the selection to the line end",
+                |ui_state, _, _| ui_state.cursor_move_line_end(&KeyModifiers::SHIFT),
+            ),
+            (
+                "move-word-left",
+                "Move the cursor to the previous word",
+                |ui_state, _, _| ui_state.cursor_move_left(&KeyModifiers::CONTROL),
+            ),
+            (
+                "move-word-right",
+                "Move the cursor to the end of the next word",
+                |ui_state, _, _| ui_state.cursor_move_right(&KeyModifiers::CONTROL),
+            ),
+            (
+                "select-word-left",
+                "Extend the selection to the previous word",
+                |ui_state, _, _| {
+                    ui_state.cursor_move_left(&(KeyModifiers::CONTROL | KeyModifiers::SHIFT))
+                },
+            ),
+            (
+                "select-word-right",
+                "Extend the selection to the end of the next word",
+                |ui_state, _, _| {
+                    ui_state.cursor_move_right(&(KeyModifiers::CONTROL | KeyModifiers::SHIFT))
+                },
+            ),
+            (
+                "move-page-up",
+                "Move the cursor up by a screen",
+                |ui_state, _, _| ui_state.cursor_move_page_up(&KeyModifiers::NONE),
+            ),
+            (
+                "move-page-down",
+                "Move the cursor down by a screen",
+                |ui_state, _, _| ui_state.cursor_move_page_down(&KeyModifiers::NONE),
+            ),
+            (
+                "move-file-start",
+                "Move the cursor to the beginning of the file",
+                |ui_state, _, _| ui_state.cursor_move_file_start(&KeyModifiers::NONE),
+            ),
+            (
+                "move-file-end",
+                "Move the cursor to the end of the file",
+                |ui_state, _, _| ui_state.cursor_move_file_end(&KeyModifiers::NONE),
+            ),
+            (
+                "select-page-up",
+                "Extend the selection up by a screen",
+                |ui_state, _, _| ui_state.cursor_move_page_up(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-page-down",
+                "Extend the selection down by a screen",
+                |ui_state, _, _| ui_state.cursor_move_page_down(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-file-start",
+                "Extend the selection to the beginning of the file",
+                |ui_state, _, _| ui_state.cursor_move_file_start(&KeyModifiers::SHIFT),
+            ),
+            (
+                "select-file-end",
+                "Extend the selection to the end of the file",
+                |ui_state, _, _| ui_state.cursor_move_file_end(&KeyModifiers::SHIFT),
+            ),
+            (
+                "delete-backward",
+                "Delete the previous character",
+                |ui_state, undo_redo, _| {
+                    if !ui_state.is_outside_of_block() {
+                        ui_state.remove_previous_character(undo_redo)
+                    }
+                },
+            ),
+            (
+                "delete-forward",
+                "Delete the next character",
+                |ui_state, undo_redo, _| {
+                    if !ui_state.is_outside_of_block() {
+                        ui_state.remove_next_character(undo_redo)
+                    }
+                },
+            ),
+            (
+                "delete-word-backward",
+                "Delete up to the start of the previous word",
+                |ui_state, undo_redo, _| {
+                    if !ui_state.is_outside_of_block() {
+                        ui_state.remove_previous_word(undo_redo)
+                    }
+                },
+            ),
+            (
+                "delete-word-forward",
+                "Delete up to the end of the next word",
+                |ui_state, undo_redo, _| {
+                    if !ui_state.is_outside_of_block() {
+                        ui_state.remove_next_word(undo_redo)
+                    }
+                },
+            ),
+            ("newline", "Insert a new line", |ui_state, undo_redo, _| {
+                ui_state.add_new_line(undo_redo)
+            }),
+            (
+                "indent",
+                "Insert a tab or indent the selection",
+                |ui_state, undo_redo, config| ui_state.handle_tab_key(config, undo_redo),
+            ),
+            (
+                "unindent",
+                "Remove one level of indentation",
+                |ui_state, _, config| ui_state.handle_backtab_key(config),
+            ),
+        ];
+
+        for (name, description, handler) in cursor_commands {
+            registry.register(name, description, CommandHandler::Cursor(*handler));
+        }
+
+        registry
+    }
+
+    /// Add a new command, replacing the existing one with the same name
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        handler: CommandHandler,
+    ) {
+        self.commands.retain(|command| command.name != name);
+        self.commands.push(Command {
+            name,
+            description,
+            handler,
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    /// Commands matching the input, the best matches first
+    pub fn complete(&self, input: &str) -> Vec<&Command> {
+        let mut matches: Vec<(i64, &Command)> = self
+            .commands
+            .iter()
+            .filter_map(|command| Some((fuzzy_score(input, command.name)?, command)))
+            .collect();
+
+        matches.sort_by(|(score_a, _), (score_b, _)| score_b.cmp(score_a));
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+}
+
+impl AppState {
+    /// Run the command line like `goto 15`; errors are shown in the status bar
+    pub fn run_command(&mut self, input: &str) {
+        let input = input.trim();
+        let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+
+        let Some(command) = self.commands.get(name) else {
+            self.status_message = Some(format!("Unknown command: {name}"));
+            return;
+        };
+
+        match command.handler {
+            CommandHandler::App(handler) => {
+                if let Err(error) = handler(self, args.trim()) {
+                    self.status_message = Some(error);
+                }
+            }
+            CommandHandler::Editor(handler) => {
+                let Some(buffer) = self.get_active_buffer() else {
+                    return;
+                };
+                let config = self.get_buffer_config(buffer);
+
+                if let Some(buffer) = self.buffers.get_mut(self.active_buffer) {
+                    handler(&mut buffer.ui_state, &mut buffer.undo_redo, &config);
+                }
+            }
+            CommandHandler::Cursor(handler) => {
+                let Some(buffer) = self.get_active_buffer() else {
+                    return;
+                };
+                let config = self.get_buffer_config(buffer);
+
+                if let Some(buffer) = self.buffers.get_mut(self.active_buffer) {
+                    buffer
+                        .ui_state
+                        .for_each_cursor(&mut buffer.undo_redo, |ui_state, undo_redo| {
+                            handler(ui_state, undo_redo, &config)
+                        });
+                }
+            }
+        }
+    }
+}
+
+fn open_file(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    if args.is_empty() {
+        return Err(String::from("Usage: open <path>"));
+    }
+
+    let file_path = app_state.working_directory.join(args);
+    app_state
+        .open_file(file_path)
+        .map_err(|error| format!("Could not open the file: {error}"))
+}
+
+/// Without arguments, the search bar is opened; otherwise we jump straight
+/// to the next match of the text
+fn find(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    if args.is_empty() {
+        app_state.open_search_bar(false);
+    } else {
+        app_state.search.pattern = args.chars().collect();
+        app_state.find_next(SearchDirection::Forward);
+    }
+
+    Ok(())
+}
+
+/// Opens the search panel; with the text, the search is started right away
+fn project_search(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    app_state.open_project_search();
+
+    if !args.is_empty()
+        && let Some(project_search) = &mut app_state.project_search
+    {
+        project_search.query.pattern = args.chars().collect();
+        project_search.cursor = project_search.query.pattern.len();
+        app_state.start_project_search();
+    }
+
+    Ok(())
+}
+
+/// Without the line, the command bar is opened to type it
+fn go_to_line(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    if args.is_empty() {
+        app_state.open_command_bar_with_input("goto ");
+        return Ok(());
+    }
+
+    let (line, column) = match args.split_once(':') {
+        Some((line, column)) => (line, Some(column)),
+        None => (args, None),
+    };
+    let line: usize = line
+        .trim()
+        .parse()
+        .map_err(|_| format!("Not a line number: {line}"))?;
+    let column: usize = match column {
+        Some(column) => column
+            .trim()
+            .parse()
+            .map_err(|_| format!("Not a column number: {column}"))?,
+        None => 1,
+    };
+
+    let buffer = app_state
+        .get_active_buffer_mut()
+        .ok_or_else(|| String::from("No file is opened"))?;
+    buffer.ui_state.go_to_line(line, column);
+
+    Ok(())
+}
+
+fn set_option(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    let Some((option, value)) = args.split_once(' ') else {
+        return Err(String::from("Usage: set <option> <value>"));
+    };
+    let value = value.trim();
+    let parse_size = |value: &str| {
+        value
+            .parse()
+            .ok()
+            .filter(|size| (1..=MAX_WHITESPACES_AMOUNT as usize).contains(size))
+            .ok_or_else(|| {
+                format!("Expected a number from 1 to {MAX_WHITESPACES_AMOUNT}, got {value}")
+            })
+    };
+    let config = &mut app_state.command_config;
+
+    match option {
+        "tabs_to_spaces" => {
+            let tabs_to_spaces = value
+                .parse()
+                .map_err(|_| format!("Expected true or false, got {value}"))?;
+            config.tabs_to_spaces = Some(tabs_to_spaces);
+        }
+        "whitespaces_amount" => {
+            config.whitespaces_amount = Some(parse_size(value)?);
+        }
+        "tab_width" => {
+            config.tab_width = Some(parse_size(value)?);
+        }
+        "soft_wrap" => {
+            let soft_wrap = value
+                .parse()
+                .map_err(|_| format!("Expected true or false, got {value}"))?;
+            config.soft_wrap = Some(soft_wrap);
+
+            // unlike other options, it is stored in every opened file
+            for buffer in &mut app_state.buffers {
+                buffer.ui_state.soft_wrap = soft_wrap;
+                buffer.ui_state.handle_cursor_scrolling();
+            }
+        }
+        "word_chars" => {
+            config.word_chars = Some(value.to_string());
+        }
+        "subword_navigation" => {
+            let subword_navigation = value
+                .parse()
+                .map_err(|_| format!("Expected true or false, got {value}"))?;
+            config.subword_navigation = Some(subword_navigation);
+        }
+        "auto_pairs" => {
+            if value.chars().count() % 2 != 0 {
+                return Err(format!("Expected pairs of characters, got {value}"));
+            }
+            config.auto_pairs = Some(value.to_string());
+        }
+        _ => return Err(format!("Unknown option: {option}")),
+    }
+
+    Ok(())
+}
+
+fn set_line_ending(app_state: &mut AppState, args: &str) -> Result<(), String> {
+    let line_ending = match args.to_lowercase().as_str() {
+        "lf" => LineEnding::Lf,
+        "crlf" => LineEnding::Crlf,
+        _ => return Err(String::from("Usage: line-ending <lf|crlf>")),
+    };
+
+    let buffer = app_state
+        .get_active_buffer_mut()
+        .ok_or_else(|| String::from("No file is opened"))?;
+    buffer.ui_state.set_line_ending(line_ending);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::create_test_directory;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn completes_command_names() {
+        let registry = CommandRegistry::new();
+
+        assert_eq!(registry.complete("nb")[0].name, "next-buffer");
+        assert!(registry.complete("xyz").is_empty());
+        assert_eq!(registry.complete("sa")[0].name, "save");
+    }
+
+    #[test]
+    fn runs_commands_with_arguments() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        app_state.run_command("set whitespaces_amount 2");
+        assert_eq!(app_state.command_config.whitespaces_amount, Some(2));
+
+        app_state.run_command("set tabs_to_spaces maybe");
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Expected true or false, got maybe")
+        );
+
+        app_state.run_command("fly");
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Unknown command: fly")
+        );
+
+        app_state.run_command("goto 3:x");
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Not a column number: x")
+        );
+
+        // without the line, it waits for it in the command bar
+        app_state.run_command("goto");
+        let command_bar = app_state.command_bar.as_ref().unwrap();
+        assert_eq!(String::from_iter(&command_bar.input), "goto ");
+        assert_eq!(command_bar.cursor, 5);
+
+        app_state.run_command("quit");
+        assert!(app_state.should_quit);
+    }
+
+    #[test]
+    fn set_wins_over_config_files() {
+        let directory = create_test_directory("set-option");
+        fs::write(directory.join("main.rs"), "fn main() {}\n").unwrap();
+        let mut app_state = AppState::new(directory.clone());
+        app_state.apply_config("[languages.rust]\nwhitespaces_amount = 2");
+        app_state.open_file(directory.join("main.rs")).unwrap();
+
+        app_state.run_command("set whitespaces_amount 0");
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Expected a number from 1 to 16, got 0")
+        );
+        app_state.run_command("set tab_width 1000000");
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Expected a number from 1 to 16, got 1000000")
+        );
+
+        app_state.run_command("set whitespaces_amount 8");
+        let buffer = app_state.get_active_buffer().unwrap();
+        assert_eq!(app_state.get_buffer_config(buffer).whitespaces_amount, 8);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,417 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use toml::{Table, Value};
+
+use super::app::{AppState, ConfigOverride};
+use super::keymap::{KeyContext, parse_key_sequence};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const PROJECT_CONFIG_FILE_NAME: &str = ".love.toml";
+pub(super) const MAX_WHITESPACES_AMOUNT: i64 = 16;
+
+/// `$XDG_CONFIG_HOME/love`, or `~/.config/love` if the variable is not set
+pub fn get_config_directory() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_home.join("love"))
+}
+
+impl AppState {
+    /// Read the global config and then the project config on top of it, both
+    /// are optional. Problems are shown in the status bar, and all valid parts
+    /// of the files are still applied.
+    pub fn load_config_files(&mut self) {
+        let mut errors = vec![];
+
+        if let Some(config_directory) = get_config_directory() {
+            self.load_config_file(&config_directory.join(CONFIG_FILE_NAME), &mut errors);
+        }
+
+        let project_config_path = self.working_directory.join(PROJECT_CONFIG_FILE_NAME);
+        self.load_config_file(&project_config_path, &mut errors);
+
+        if let Some(first_error) = errors.first() {
+            let mut message = format!("Config error: {first_error}");
+            if errors.len() > 1 {
+                message.push_str(&format!(" (and {} more)", errors.len() - 1));
+            }
+            self.status_message = Some(message);
+        }
+    }
+
+    fn load_config_file(&mut self, path: &Path, errors: &mut Vec<String>) {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        match fs::read_to_string(path) {
+            Ok(content) => errors.extend(
+                self.apply_config(&content)
+                    .into_iter()
+                    .map(|error| format!("{file_name}: {error}")),
+            ),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => errors.push(format!("{file_name}: {error}")),
+        }
+    }
+
+    /// Apply the config file content and return all problems found in it
+    pub fn apply_config(&mut self, content: &str) -> Vec<String> {
+        let table: Table = match content.parse() {
+            Ok(table) => table,
+            Err(error) => return vec![error.to_string().trim().to_string()],
+        };
+
+        let mut errors = vec![];
+
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("editor", Value::Table(options)) => {
+                    parse_config_override(options, "editor", &mut errors).apply(&mut self.config);
+                }
+                ("languages", Value::Table(languages)) => {
+                    self.apply_language_configs(languages, &mut errors);
+                }
+                ("theme", Value::Table(theme)) => self.apply_theme(theme, &mut errors),
+                ("keys", Value::Table(keys)) => self.apply_key_bindings(keys, &mut errors),
+                (key, _) => errors.push(format!("Unknown section \"{key}\"")),
+            }
+        }
+
+        errors
+    }
+
+    /// Every section is a language name, with the same options as in `[editor]`
+    fn apply_language_configs(&mut self, languages: Table, errors: &mut Vec<String>) {
+        for (language, options) in languages {
+            let Value::Table(options) = options else {
+                errors.push(format!("\"languages.{language}\" should be a table"));
+                continue;
+            };
+
+            let prefix = format!("languages.{language}");
+            let config_override = parse_config_override(options, &prefix, errors);
+            self.language_configs
+                .entry(language)
+                .or_default()
+                .merge(&config_override);
+        }
+    }
+
+    fn apply_theme(&mut self, theme: Table, errors: &mut Vec<String>) {
+        for (key, value) in theme {
+            match (key.as_str(), value) {
+                ("bg_color", value) => {
+                    if let Some(color) = parse_color(&value, "theme.bg_color", errors) {
+                        self.theme.bg_color = color;
+                    }
+                }
+                ("status_bar_color", value) => {
+                    if let Some(color) = parse_color(&value, "theme.status_bar_color", errors) {
+                        self.theme.status_bar_color = color;
+                    }
+                }
+                ("syntax", Value::Table(syntax_colors)) => {
+                    for (capture_name, value) in syntax_colors {
+                        let name = format!("theme.syntax.{capture_name}");
+                        if let Some(color) = parse_color(&value, &name, errors) {
+                            self.theme.syntax_colors.insert(capture_name, color);
+                        }
+                    }
+                }
+                (key, _) => errors.push(format!("Unknown option \"theme.{key}\"")),
+            }
+        }
+    }
+
+    /// Every section is a context, with key sequences mapped to commands;
+    /// an empty command removes the binding
+    fn apply_key_bindings(&mut self, keys: Table, errors: &mut Vec<String>) {
+        for (context_name, bindings) in keys {
+            let Some(context) = KeyContext::parse(&context_name) else {
+                errors.push(format!("Unknown key context \"{context_name}\""));
+                continue;
+            };
+
+            let Value::Table(bindings) = bindings else {
+                errors.push(format!("\"keys.{context_name}\" should be a table"));
+                continue;
+            };
+
+            for (keys, command) in bindings {
+                let sequence = match parse_key_sequence(&keys) {
+                    Ok(sequence) => sequence,
+                    Err(error) => {
+                        errors.push(error);
+                        continue;
+                    }
+                };
+
+                let Value::String(command) = command else {
+                    errors.push(format!("Command for \"{keys}\" should be a string"));
+                    continue;
+                };
+
+                let command = command.trim();
+                if command.is_empty() {
+                    self.keymap.unbind(context, &sequence);
+                    continue;
+                }
+
+                let name = command.split_whitespace().next().unwrap_or_default();
+                if self.commands.get(name).is_none() {
+                    errors.push(format!("Unknown command \"{name}\" for \"{keys}\""));
+                    continue;
+                }
+
+                self.keymap.bind(context, sequence, command.to_string());
+            }
+        }
+    }
+}
+
+fn parse_config_override(options: Table, prefix: &str, errors: &mut Vec<String>) -> ConfigOverride {
+    let mut config_override = ConfigOverride::default();
+
+    for (key, value) in options {
+        match (key.as_str(), value) {
+            ("tabs_to_spaces", Value::Boolean(tabs_to_spaces)) => {
+                config_override.tabs_to_spaces = Some(tabs_to_spaces);
+            }
+            ("whitespaces_amount", Value::Integer(amount))
+                if (1..=MAX_WHITESPACES_AMOUNT).contains(&amount) =>
+            {
+                config_override.whitespaces_amount = Some(amount as usize);
+            }
+            ("tab_width", Value::Integer(width)) if (1..=MAX_WHITESPACES_AMOUNT).contains(&width) => {
+                config_override.tab_width = Some(width as usize);
+            }
+            ("soft_wrap", Value::Boolean(soft_wrap)) => {
+                config_override.soft_wrap = Some(soft_wrap);
+            }
+            ("word_chars", Value::String(word_chars)) => {
+                config_override.word_chars = Some(word_chars);
+            }
+            ("subword_navigation", Value::Boolean(subword_navigation)) => {
+                config_override.subword_navigation = Some(subword_navigation);
+            }
+            ("auto_pairs", Value::String(auto_pairs))
+                if auto_pairs.chars().count() % 2 == 0 =>
+            {
+                config_override.auto_pairs = Some(auto_pairs);
+            }
+            ("tabs_to_spaces", _) => {
+                errors.push(format!("\"{prefix}.tabs_to_spaces\" should be true or false"));
+            }
+            ("soft_wrap", _) => {
+                errors.push(format!("\"{prefix}.soft_wrap\" should be true or false"));
+            }
+            ("word_chars", _) => {
+                errors.push(format!("\"{prefix}.word_chars\" should be a string"));
+            }
+            ("subword_navigation", _) => errors.push(format!(
+                "\"{prefix}.subword_navigation\" should be true or false"
+            )),
+            ("auto_pairs", _) => errors.push(format!(
+                "\"{prefix}.auto_pairs\" should be a string of pairs, like \"()[]\""
+            )),
+            ("whitespaces_amount", _) => errors.push(format!(
+                "\"{prefix}.whitespaces_amount\" should be a number from 1 to {MAX_WHITESPACES_AMOUNT}"
+            )),
+            ("tab_width", _) => errors.push(format!(
+                "\"{prefix}.tab_width\" should be a number from 1 to {MAX_WHITESPACES_AMOUNT}"
+            )),
+            (key, _) => errors.push(format!("Unknown option \"{prefix}.{key}\"")),
+        }
+    }
+
+    config_override
+}
+
+/// Colors can be names like `red`, hex values like `#ff0000` or indexes like `42`
+fn parse_color(value: &Value, name: &str, errors: &mut Vec<String>) -> Option<Color> {
+    let color = match value {
+        Value::String(value) => Color::from_str(value).ok(),
+        _ => None,
+    };
+
+    if color.is_none() {
+        errors.push(format!("\"{name}\" should be a color like \"#ff0000\""));
+    }
+
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::app::{Config, Theme};
+    use crate::app_state::keymap::KeyLookup;
+
+    #[test]
+    fn overrides_key_bindings() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        let errors = app_state.apply_config(
+            r#"
+            [keys.global]
+            "ctrl+k ctrl+s" = "save"
+            "ctrl+s" = ""
+
+            [keys.editor]
+            "alt+g" = "goto 1"
+            "#,
+        );
+
+        assert!(errors.is_empty());
+
+        let lookup = |app_state: &AppState, keys: &str| {
+            app_state
+                .keymap
+                .lookup(KeyContext::Editor, &parse_key_sequence(keys).unwrap())
+        };
+
+        assert!(matches!(lookup(&app_state, "ctrl+s"), KeyLookup::NotFound));
+        assert!(matches!(lookup(&app_state, "ctrl+k"), KeyLookup::Pending));
+        assert!(matches!(
+            lookup(&app_state, "ctrl+k ctrl+s"),
+            KeyLookup::Command(command) if command == "save"
+        ));
+        assert!(matches!(
+            lookup(&app_state, "alt+g"),
+            KeyLookup::Command(command) if command == "goto 1"
+        ));
+    }
+
+    #[test]
+    fn layers_editor_options() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        // global config
+        let errors = app_state.apply_config(
+            r#"
+            [editor]
+            whitespaces_amount = 3
+
+            [languages.yaml]
+            whitespaces_amount = 2
+
+            [languages.makefile]
+            tabs_to_spaces = false
+            "#,
+        );
+        assert!(errors.is_empty());
+
+        // project config
+        let errors = app_state.apply_config(
+            r##"
+            [editor]
+            tabs_to_spaces = true
+
+            [languages.makefile]
+            whitespaces_amount = 8
+
+            [theme]
+            bg_color = "#101010"
+
+            [theme.syntax]
+            keyword = "red"
+            "##,
+        );
+        assert!(errors.is_empty());
+
+        assert_eq!(
+            app_state
+                .get_config_for(Path::new("main.rs"))
+                .whitespaces_amount,
+            3
+        );
+        assert_eq!(
+            app_state
+                .get_config_for(Path::new("ci.yml"))
+                .whitespaces_amount,
+            2
+        );
+
+        let makefile_config = app_state.get_config_for(Path::new("Makefile"));
+        assert!(!makefile_config.tabs_to_spaces);
+        assert_eq!(makefile_config.whitespaces_amount, 8);
+
+        assert_eq!(app_state.theme.bg_color, Color::Rgb(16, 16, 16));
+        assert_eq!(
+            app_state.theme.get_syntax_color("keyword"),
+            Some(Color::Red)
+        );
+    }
+
+    #[test]
+    fn auto_pairs_depend_on_language() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        let quote = ('\'', '\'');
+        let get_auto_pairs = |app_state: &AppState, path: &str| {
+            app_state.get_config_for(Path::new(path)).get_auto_pairs()
+        };
+
+        // a single quote is closed only where it can't be a lifetime
+        assert!(!get_auto_pairs(&app_state, "main.rs").contains(&quote));
+        assert!(get_auto_pairs(&app_state, "main.py").contains(&quote));
+
+        let errors = app_state.apply_config("[editor]\nauto_pairs = \"''\"");
+        assert!(errors.is_empty());
+        assert_eq!(get_auto_pairs(&app_state, "main.rs"), [quote]);
+    }
+
+    #[test]
+    fn validates_values() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        let errors = app_state.apply_config(
+            r#"
+            [editor]
+            whitespaces_amount = 0
+            tabs_to_spaces = "yes"
+            line_numbers = true
+
+            [theme]
+            bg_color = "not a color"
+            "#,
+        );
+
+        assert_eq!(errors.len(), 4);
+        assert_eq!(app_state.config, Config::new());
+        assert_eq!(app_state.theme.bg_color, Theme::new().bg_color);
+    }
+
+    #[test]
+    fn reports_errors_instead_of_panicking() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        let errors = app_state.apply_config(
+            r#"
+            [keys.editor]
+            "ctrl+nope" = "save"
+            "ctrl+y" = "fly"
+            "ctrl+u" = 5
+            "ctrl+b" = "save"
+
+            [keys.sidebar]
+            "#,
+        );
+
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            app_state
+                .keymap
+                .lookup(KeyContext::Editor, &parse_key_sequence("ctrl+b").unwrap()),
+            KeyLookup::Command(_)
+        ));
+
+        assert_eq!(app_state.apply_config("[keys").len(), 1);
+    }
+}
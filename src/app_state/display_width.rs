@@ -0,0 +1,169 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Where every character of a line ends up on the screen. Columns in the
+/// buffer count characters, while the terminal counts cells: a tab takes
+/// up to the next tab stop, CJK characters and most emoji take two cells,
+/// and combining marks take none.
+pub struct LineLayout {
+    /// Row and the cell within the row for every character, plus one more
+    /// for the position after the last character
+    pub positions: Vec<(usize, usize)>,
+    /// Cells taken by every character; all of them are on the first
+    /// character of a grapheme cluster, the rest have `0`
+    pub widths: Vec<usize>,
+    pub rows: usize,
+}
+
+impl LineLayout {
+    /// Break the line into rows of the given width, if any. Characters are
+    /// never split between rows, so a wide one can leave an empty cell.
+    pub fn new(line: &[char], tab_width: usize, wrap_width: Option<usize>) -> Self {
+        let widths = get_char_widths(line, tab_width);
+        let mut positions = Vec::with_capacity(line.len() + 1);
+        let (mut row, mut x) = (0, 0);
+
+        for width in &widths {
+            if let Some(wrap_width) = wrap_width
+                && x > 0
+                && x + width > wrap_width
+            {
+                row += 1;
+                x = 0;
+            }
+
+            positions.push((row, x));
+            x += width;
+        }
+        positions.push((row, x));
+
+        LineLayout {
+            positions,
+            widths,
+            rows: row + 1,
+        }
+    }
+
+    /// The cell of the character at the index, counting from `0`
+    pub fn get_position(&self, index: usize) -> (usize, usize) {
+        self.positions[index.min(self.positions.len() - 1)]
+    }
+
+    /// Index of the character shown at the cell of the row, or the closest
+    /// one if the row is shorter; the result is always a cluster start
+    pub fn get_index_at(&self, row: usize, x: usize) -> usize {
+        let last_index = self.positions.len() - 1;
+
+        self.positions
+            .iter()
+            .enumerate()
+            .filter(|(index, (character_row, character_x))| {
+                // zero width characters belong to the cluster before them
+                let is_cluster_start = *index == last_index || self.widths[*index] > 0;
+                *character_row == row && *character_x <= x && is_cluster_start
+            })
+            .map(|(index, _)| index)
+            .next_back()
+            .unwrap_or(last_index)
+    }
+}
+
+/// Widths of the characters in cells; tabs are expanded to the next tab stop
+fn get_char_widths(line: &[char], tab_width: usize) -> Vec<usize> {
+    let text = String::from_iter(line);
+    let mut widths = Vec::with_capacity(line.len());
+    let mut x = 0;
+
+    for cluster in text.graphemes(true) {
+        let first_char = cluster.chars().next().unwrap_or_default();
+        let width = if first_char == '\t' {
+            tab_width.max(1) - x % tab_width.max(1)
+        } else if first_char.is_control() {
+            // shown as a replacement character
+            1
+        } else {
+            cluster.width().max(1)
+        };
+
+        widths.push(width);
+        widths.extend(std::iter::repeat_n(0, cluster.chars().count() - 1));
+        x += width;
+    }
+
+    widths
+}
+
+/// Indexes of characters where grapheme clusters start, plus the line length;
+/// the cursor can only be put at these
+pub fn get_cluster_boundaries(line: &[char]) -> Vec<usize> {
+    let text = String::from_iter(line);
+    let mut boundaries = Vec::with_capacity(line.len() + 1);
+    let mut index = 0;
+
+    for cluster in text.graphemes(true) {
+        boundaries.push(index);
+        index += cluster.chars().count();
+    }
+    boundaries.push(index);
+
+    boundaries
+}
+
+/// How the character is drawn: tabs become spaces and other control
+/// characters a replacement character, so they don't break the terminal
+pub fn get_display_text(character: char, width: usize) -> String {
+    match character {
+        '\t' => " ".repeat(width),
+        character if character.is_control() => String::from('\u{fffd}'),
+        character => String::from(character),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn expands_tabs_and_wide_characters() {
+        let layout = LineLayout::new(&chars("a\tb日本x"), 4, None);
+
+        assert_eq!(layout.widths, [1, 3, 1, 2, 2, 1]);
+        let xs: Vec<usize> = layout.positions.iter().map(|(_, x)| *x).collect();
+        assert_eq!(xs, [0, 1, 4, 5, 7, 9, 10]);
+
+        // the second cell of a wide character belongs to it
+        assert_eq!(layout.get_index_at(0, 6), 3);
+        assert_eq!(layout.get_index_at(0, 2), 1);
+        assert_eq!(layout.get_index_at(0, 100), 6);
+    }
+
+    #[test]
+    fn keeps_clusters_together() {
+        // `e` with a combining accent, and a family emoji made of several characters
+        let line = chars("e\u{301}👨\u{200d}👩\u{200d}👧!");
+        let layout = LineLayout::new(&line, 4, None);
+
+        assert_eq!(get_cluster_boundaries(&line), [0, 2, 7, 8]);
+        assert_eq!(layout.widths[..2], [1, 0]);
+        assert_eq!(layout.get_position(7), (0, 3));
+        assert_eq!(layout.get_index_at(0, 1), 2);
+        assert_eq!(layout.get_index_at(0, 2), 2);
+    }
+
+    #[test]
+    fn wraps_without_splitting_wide_characters() {
+        let layout = LineLayout::new(&chars("abc日本"), 4, Some(4));
+
+        assert_eq!(layout.rows, 2);
+        assert_eq!(
+            layout.positions,
+            [(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (1, 4)]
+        );
+        assert_eq!(layout.get_index_at(0, 3), 2);
+        assert_eq!(layout.get_index_at(1, 3), 4);
+    }
+}
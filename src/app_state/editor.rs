@@ -1,6 +1,8 @@
 mod clipboard;
+mod comment_actions;
 mod deleting_actions;
 mod insert_character_action;
+mod line_actions;
 mod newline_action;
 mod selection_actions;
 mod tab_actions;
@@ -9,11 +11,18 @@ use std::io;
 use std::path::PathBuf;
 
 use crossterm::{
-    cursor::{MoveTo, SetCursorStyle, Show},
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
     execute,
 };
 
+use super::block_selection::BlockSelection;
+use super::brackets::CachedBracketMatch;
+use super::file_format::FileFormat;
+use super::language::CommentTokens;
+use super::multi_cursor::Cursor;
 use super::selection::Selection;
+use super::text_buffer::TextBuffer;
+use super::words::WordRules;
 
 pub struct FileEntry {
     pub path: PathBuf,
@@ -21,8 +30,9 @@ pub struct FileEntry {
 
 pub struct DirEntry {
     pub path: PathBuf,
-    // file_tree: HashMap<PathBuf, Vec<FileTreeEntry>>,
-    // expanded: bool,
+    /// Children are not stored here, they are read lazily into
+    /// `AppState::file_tree` when the directory is expanded for the first time
+    pub expanded: bool,
 }
 
 pub enum FileTreeEntry {
@@ -35,26 +45,55 @@ impl FileTreeEntry {
         if path.is_dir() {
             FileTreeEntry::Dir(DirEntry {
                 path,
-                // expanded: false,
-                // file_tree: HashMap::new(),
+                expanded: false,
             })
         } else {
             FileTreeEntry::File(FileEntry { path })
         }
     }
+
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            FileTreeEntry::File(file) => &file.path,
+            FileTreeEntry::Dir(dir) => &dir.path,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, FileTreeEntry::Dir(_))
+    }
 }
 
 pub struct UIState {
     pub cursor_line: usize,
     pub cursor_column: usize,
-    pub lines: Vec<Vec<char>>,
+    pub lines: Box<dyn TextBuffer>,
     /// horizontal offset on the screen; needed to place the cursor
-    editor_offset_x: usize,
+    pub(super) editor_offset_x: usize,
     /// vertical offset on the screen; needed to place the cursor
-    editor_offset_y: usize,
+    pub(super) editor_offset_y: usize,
     /// we don't want to process more lines than visible
     pub editor_lines_num: usize,
     pub editor_scroll_offset: usize,
+    /// Characters which fit into a single row, without the line number
+    pub editor_text_width: usize,
+    /// Characters hidden on the left when the cursor goes past the right edge
+    pub horizontal_scroll_offset: usize,
+    /// Long lines are broken into several rows instead of scrolling horizontally
+    pub soft_wrap: bool,
+    /// Cells between tab stops, tabs take up to the next one
+    pub tab_width: usize,
+    /// Used by word navigation and deletion, and by double clicks
+    pub word_rules: WordRules,
+    /// Characters closed automatically when typed, with their closing ones
+    pub auto_pairs: Vec<(char, char)>,
+    /// Positions of the closing characters inserted automatically, only
+    /// they are typed over; any other edit changes the buffer version and
+    /// forgets them
+    pub(super) auto_closed: Vec<(usize, usize)>,
+    pub(super) auto_closed_version: usize,
+    /// Comment tokens of the file's language
+    pub comment_tokens: CommentTokens,
 
     /// There are multiple widgets which can be focused, plus we might
     /// not even have a valid editor open (e.g. if all files are closed)
@@ -63,7 +102,7 @@ pub struct UIState {
     /// Each line is prefixed with the line number. To have a consistent
     /// prefix width, we take the highest number, take number of digits,
     /// and add `|` symbol and a space afterwards.
-    prefix_len: usize,
+    pub(super) prefix_len: usize,
 
     /// When we navigate using up/down directions, we ideally want to stay
     /// on the same column vertically. It is not always possible, because
@@ -74,10 +113,32 @@ pub struct UIState {
     pub(super) vertical_offset_target: usize,
 
     pub selection: Option<Selection>,
+    /// Cursors besides the primary one, in no particular order
+    pub extra_cursors: Vec<Cursor>,
+    /// Set while the cursors come from a rectangular selection
+    pub block_selection: Option<BlockSelection>,
+    /// The last copied block, pasted as a column too
+    pub(super) block_clipboard: Option<String>,
+    pub(super) bracket_match_cache: Option<CachedBracketMatch>,
+
+    /// Set by every action which changes `lines`, and cleared after
+    /// the buffer is successfully written back to disk.
+    pub is_dirty: bool,
+
+    /// Line endings, final newline and BOM of the file on disk
+    pub file_format: FileFormat,
 }
 
 impl UIState {
+    /// Shortcut for tests, which are much easier to read with plain lines
+    #[cfg(test)]
     pub fn new(prefix_len: usize, lines: Vec<Vec<char>>) -> Self {
+        use super::text_buffer::RopeBuffer;
+
+        Self::with_text_buffer(prefix_len, Box::new(RopeBuffer::from_lines(&lines)))
+    }
+
+    pub fn with_text_buffer(prefix_len: usize, lines: Box<dyn TextBuffer>) -> Self {
         UIState {
             cursor_line: 1,
             cursor_column: 1,
@@ -87,11 +148,27 @@ impl UIState {
             // big random number
             editor_lines_num: 1000,
             editor_scroll_offset: 0,
+            // big random number, the real one is set on render
+            editor_text_width: 1000,
+            horizontal_scroll_offset: 0,
+            soft_wrap: false,
+            tab_width: 4,
+            word_rules: WordRules::default(),
+            auto_pairs: vec![],
+            auto_closed: vec![],
+            auto_closed_version: 0,
+            comment_tokens: CommentTokens::default(),
             should_show_cursor: false,
             // 1 character at the beginning, one space at the end
             prefix_len: prefix_len + 2,
             vertical_offset_target: 0,
             selection: None,
+            extra_cursors: vec![],
+            block_selection: None,
+            block_clipboard: None,
+            bracket_match_cache: None,
+            is_dirty: false,
+            file_format: FileFormat::new(),
         }
     }
 
@@ -105,19 +182,28 @@ impl UIState {
             // 1 for the border at the top, 1 for the border at the bottom
             self.editor_lines_num = height - 2;
             self.should_show_cursor = true;
+            // the cursor could be moved before the first render, e.g. to a search match
+            self.handle_cursor_scrolling();
         }
     }
 
     pub fn show_cursor_if_needed(&mut self) {
         if self.should_show_cursor {
-            let x = self.cursor_column + self.editor_offset_x + self.prefix_len;
-            let y = self.cursor_line + self.editor_offset_y - self.editor_scroll_offset;
-            let result = execute!(
-                io::stdout(),
-                MoveTo(x as u16, y as u16),
-                SetCursorStyle::SteadyBlock,
-                Show
-            );
+            // the cursor can be scrolled out of view with the mouse wheel
+            let result = match self.get_cursor_screen_position() {
+                Some((x, y)) => {
+                    // 1 for the padding on the left and at the top
+                    let x = x + 1 + self.editor_offset_x + self.prefix_len;
+                    let y = y + 1 + self.editor_offset_y;
+                    execute!(
+                        io::stdout(),
+                        MoveTo(x as u16, y as u16),
+                        SetCursorStyle::SteadyBlock,
+                        Show
+                    )
+                }
+                None => execute!(io::stdout(), Hide),
+            };
 
             match result {
                 Ok(_) => {
@@ -33,22 +33,21 @@ impl UIState {
 
     /// insert text at the current cursor position. It WILL move the cursor to the end
     pub fn insert_text(&mut self, data: String, add_whitespaces: bool) {
+        if data.is_empty() {
+            return;
+        }
+
+        self.is_dirty = true;
+
         // in my iTerm on macOS, newlines are replaced by `\r` by default
         // to be safe, we normalize all possible line endings into '\n'
         let normalized = data.replace("\r\n", "\n").replace("\r", "\n");
-        let mut total_pasted_lines = normalized.lines().count();
-        let mut lines: Vec<(usize, Vec<char>)> = normalized
-            .split_inclusive('\n')
-            // line endings are handled on the array level
-            .map(|line| line.strip_suffix('\n').unwrap_or(line))
+        let lines: Vec<(usize, Vec<char>)> = normalized
+            .split('\n')
             .map(|s| s.chars().collect())
             .enumerate()
             .collect();
-
-        if normalized.ends_with('\n') {
-            lines.push((lines.len(), vec![]));
-            total_pasted_lines += 1;
-        }
+        let total_pasted_lines = lines.len();
 
         let prefix_len = if add_whitespaces {
             Self::get_common_whitespaces_prefix(&lines)
@@ -57,145 +56,71 @@ impl UIState {
         };
 
         let prev_line_whitespaces = if add_whitespaces {
-            match self.lines.get(self.cursor_line - 1) {
-                Some(line) => Self::calculate_whitespace_num(line),
-                None => 0,
-            }
+            self.get_line_whitespace_num(self.cursor_line - 1)
         } else {
             0
         };
 
-        // if there is only one pasted line, we need to just insert it
-        // if there are more lines, it means we need to remove the rest
-        // of the current line (to the right of the cursor), insert all
-        // text, and append that part to the last line (without moving the cursor)
-
-        if total_pasted_lines == 1 {
-            // handle a single line case
-            if let Some((_, pasted_line)) = lines.into_iter().next()
-                && let Some(line) = self.lines.get_mut(self.cursor_line - 1)
-            {
-                // we need to calculate index first, as we might change cursor next
-                let index = self.cursor_column - 1;
-                self.cursor_column += pasted_line.len();
-                line.splice(index..index, pasted_line);
-            }
-        } else {
-            let cut_text = self.cut_text_after_cursor();
-
-            for (i, pasted_line) in lines {
-                if i == 0 {
-                    if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
-                        line.extend(pasted_line);
-                    }
-                } else {
-                    // 1. get the previous line and calculate whitespaces (done before)
-                    // 2. create a new line with those whitespaces and add new data
-                    // 3. append that new line in the new index (self.cursor_line + i)
-                    // 4. if that is the last line (i + 1 == total_pasted_lines), put cursor at the end
-                    // let whitespaces_num = Self::calculate_whitespace_num(&pasted_line);
-                    let prefixed_line: Vec<char> = if prev_line_whitespaces >= prefix_len {
-                        vec![' '; prev_line_whitespaces - prefix_len]
-                            .into_iter()
-                            .chain(pasted_line)
-                            .collect()
-                    } else {
-                        pasted_line
-                            .iter()
-                            .cloned()
-                            .skip(prefix_len - prev_line_whitespaces)
-                            .collect()
-                    };
-
-                    let old_cursor_line = self.cursor_line;
-                    if total_pasted_lines == i + 1 {
-                        self.cursor_line += i;
-                        self.cursor_column = prefixed_line.len() + 1;
-                    }
-
-                    self.lines.insert(old_cursor_line - 1 + i, prefixed_line);
-                }
+        // the first line is inserted right at the cursor, and all the next ones
+        // are re-indented to match the indentation of the current line. The rest
+        // of the current line (to the right of the cursor) ends up after the last
+        // pasted line, without moving the cursor
+        let mut text = String::new();
+        let mut last_line_len = 0;
+
+        for (i, pasted_line) in lines {
+            let prefixed_line: Vec<char> = if i == 0 {
+                pasted_line
+            } else if prev_line_whitespaces >= prefix_len {
+                vec![' '; prev_line_whitespaces - prefix_len]
+                    .into_iter()
+                    .chain(pasted_line)
+                    .collect()
+            } else {
+                pasted_line
+                    .iter()
+                    .cloned()
+                    .skip(prefix_len - prev_line_whitespaces)
+                    .collect()
+            };
+
+            if i != 0 {
+                text.push('\n');
             }
 
-            if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
-                line.extend(cut_text)
-            }
+            last_line_len = prefixed_line.len();
+            text.extend(prefixed_line);
         }
-    }
 
-    fn cut_text_after_cursor(&mut self) -> Vec<char> {
-        if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
-            line.drain((self.cursor_column - 1)..).collect()
+        self.lines
+            .insert(self.cursor_line - 1, self.cursor_column - 1, &text);
+
+        if total_pasted_lines == 1 {
+            self.cursor_column += last_line_len;
         } else {
-            vec![]
+            self.cursor_line += total_pasted_lines - 1;
+            self.cursor_column = last_line_len + 1;
         }
     }
 
-    pub fn handle_copy(&self) {
-        // 1. read selection, if none do nothing
-        // 2. copy selection (join all strings with '\n' character)
+    pub fn handle_copy(&mut self) {
+        // 1. read selections of all cursors, if none do nothing
+        // 2. copy them, one per line (lines are joined with '\n' character)
         // 3. Execute a crossterm command: https://docs.rs/crossterm/0.29.0/crossterm/clipboard/struct.CopyToClipboard.html
-        if let Some(selection) = &self.selection {
-            let (start_line, start_column) = selection.start;
-            let (end_line, end_column) = selection.end;
-
-            if start_line == end_line {
-                if let Some(line) = self.lines.get(start_line - 1) {
-                    let start_index = start_column.min(end_column) - 1;
-                    let end_index = start_column.max(end_column);
-                    let text = line[start_index..end_index].iter().collect::<String>();
-                    Self::execute_terminal_copy(text);
-                }
-            } else {
-                let mut result = vec![];
-
-                let start_line_index = start_line.min(end_line);
-                let end_line_index = start_line.max(end_line);
-                let first_line_column = if start_line < end_line {
-                    start_column
-                } else {
-                    end_column
-                };
-                let last_line_column = if start_line > end_line {
-                    start_column
-                } else {
-                    end_column
-                };
-                for i in start_line_index..=end_line_index {
-                    if i != start_line {
-                        result.push('\n');
-                    }
-
-                    if i == start_line_index
-                        && let Some(line) = self.lines.get(i - 1)
-                    {
-                        let start_index = first_line_column - 1;
-                        result.extend_from_slice(&line[start_index..]);
-                    }
-
-                    if i == end_line_index
-                        && let Some(line) = self.lines.get(i - 1)
-                    {
-                        let end_index = last_line_column - 1;
-                        result.extend_from_slice(&line[..end_index]);
-                    }
-
-                    if i != start_line_index
-                        && i != end_line_index
-                        && let Some(line) = self.lines.get(i - 1)
-                    {
-                        result.extend_from_slice(line);
-                    }
-                }
-
-                let text = result.iter().collect::<String>();
-                Self::execute_terminal_copy(text);
-            }
-        } else {
-            // do nothing
+        let text = self.copy_selected_texts();
+        if !text.is_empty() {
+            Self::execute_terminal_copy(text);
         }
     }
 
+    /// Text for the clipboard; a copied block is remembered, so pasting
+    /// it back puts it in a column again
+    pub fn copy_selected_texts(&mut self) -> String {
+        let text = self.get_selected_texts().join("\n");
+        self.block_clipboard = self.block_selection.is_some().then(|| text.clone());
+        text
+    }
+
     fn execute_terminal_copy(data: String) {
         match execute!(io::stdout(), CopyToClipboard::to_clipboard_from(data)) {
             Ok(_) => {
@@ -319,7 +244,7 @@ mod test {
 
         ui_state.handle_paste(data.to_string(), &mut undo_redo);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "Hello Planet world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "Hello Planet world!");
 
         assert_eq!(ui_state.cursor_column, 14);
     }
@@ -346,16 +271,16 @@ mod test {
 
         ui_state.handle_paste(data.to_string(), &mut undo_redo);
 
-        assert_eq!(ui_state.lines.len(), 5);
+        assert_eq!(ui_state.lines.len_lines(), 5);
         assert_eq!(ui_state.cursor_line, 3);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "Hello Planet!");
+        assert_eq!(ui_state.lines.line_to_string(0), "Hello Planet!");
         assert_eq!(
-            String::from_iter(&ui_state.lines[1]),
+            ui_state.lines.line_to_string(1),
             "Another line with some text"
         );
-        assert_eq!(String::from_iter(&ui_state.lines[2]), "world!");
-        assert_eq!(String::from_iter(&ui_state.lines[3]), "");
-        assert_eq!(String::from_iter(&ui_state.lines[4]), "Description");
+        assert_eq!(ui_state.lines.line_to_string(2), "world!");
+        assert_eq!(ui_state.lines.line_to_string(3), "");
+        assert_eq!(ui_state.lines.line_to_string(4), "Description");
     }
 }
@@ -0,0 +1,333 @@
+use crate::app_state::editor::UIState;
+use crate::app_state::editor::line_actions::Range;
+use crate::app_state::undo_redo::UndoRedo;
+
+/// Characters inserted (positive) or removed (negative) at the index of
+/// a line, in the coordinates of the line before the change
+type LineEdit = (usize, isize);
+
+/// Where the column ends up after the edits; text inserted right at the
+/// column goes after it, so a selection from the line start keeps the comment
+fn shift_column(column: usize, edits: &[LineEdit]) -> usize {
+    let index = column - 1;
+    let mut shift: isize = 0;
+
+    for (edit_index, delta) in edits {
+        if index <= *edit_index {
+            continue;
+        }
+        if *delta > 0 {
+            shift += delta;
+        } else {
+            shift -= (index - edit_index).min(delta.unsigned_abs()) as isize;
+        }
+    }
+
+    column.saturating_add_signed(shift)
+}
+
+fn is_blank(line: &[char]) -> bool {
+    line.iter().all(|character| character.is_whitespace())
+}
+
+fn get_trailing_whitespaces(line: &[char]) -> usize {
+    line.iter()
+        .rev()
+        .take_while(|character| character.is_whitespace())
+        .count()
+}
+
+impl UIState {
+    /// Comment out the line or every selected line, or uncomment them if all
+    /// of them are comments already. Comments are put at the smallest
+    /// indentation of the lines, and empty lines are left alone.
+    pub fn toggle_line_comment(&mut self, undo_redo: &mut UndoRedo) {
+        let (prefix, suffix) = match (self.comment_tokens.line, self.comment_tokens.block) {
+            (Some(token), _) => (token, ""),
+            (None, Some((start, end))) => (start, end),
+            (None, None) => return,
+        };
+        let prefix: Vec<char> = prefix.chars().collect();
+        let suffix: Vec<char> = suffix.chars().collect();
+
+        self.clear_extra_cursors();
+        let (first_line, last_line) = self.get_selected_lines();
+        let lines: Vec<Vec<char>> = self
+            .get_lines_text(first_line, last_line)
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+
+        // a single empty line can be commented, so the comment can be typed
+        let skip_blank = !lines.iter().all(|line| is_blank(line));
+        let is_changed = |line: &Vec<char>| !skip_blank || !is_blank(line);
+
+        let indent = lines
+            .iter()
+            .filter(|line| is_changed(line))
+            .map(|line| Self::calculate_whitespace_num(line))
+            .min()
+            .unwrap_or_default();
+        let should_uncomment = lines.iter().filter(|line| is_changed(line)).all(|line| {
+            let start = Self::calculate_whitespace_num(line);
+            let end = line.len() - get_trailing_whitespaces(line);
+            end >= start + prefix.len() + suffix.len()
+                && line[start..].starts_with(&prefix)
+                && line[..end].ends_with(&suffix)
+        });
+
+        let mut new_lines = vec![];
+        let mut edits: Vec<Vec<LineEdit>> = vec![];
+        for line in lines {
+            if !is_changed(&line) {
+                new_lines.push(String::from_iter(&line));
+                edits.push(vec![]);
+                continue;
+            }
+
+            let (new_line, line_edits) = if should_uncomment {
+                remove_comment(&line, &prefix, &suffix)
+            } else {
+                add_comment(&line, indent, &prefix, &suffix)
+            };
+            new_lines.push(String::from_iter(new_line));
+            edits.push(line_edits);
+        }
+
+        let move_position = |(line, column): (usize, usize)| {
+            let line_edits = line
+                .checked_sub(first_line)
+                .and_then(|index| edits.get(index));
+            match line_edits {
+                Some(line_edits) => (line, shift_column(column, line_edits)),
+                None => (line, column),
+            }
+        };
+        let cursor = move_position((self.cursor_line, self.cursor_column));
+        let selection: Option<Range> = self
+            .get_selection_ends()
+            .map(|(start, end)| (move_position(start), move_position(end)));
+        self.replace_lines(
+            (first_line, last_line),
+            new_lines,
+            cursor,
+            selection,
+            undo_redo,
+        );
+    }
+
+    /// Wrap the selection (or the line without its indentation) into block
+    /// comment tokens, or remove them if it is wrapped already
+    pub fn toggle_block_comment(&mut self, undo_redo: &mut UndoRedo) {
+        let Some((start_token, end_token)) = self.comment_tokens.block else {
+            return;
+        };
+        let prefix: Vec<char> = start_token.chars().collect();
+        let suffix: Vec<char> = end_token.chars().collect();
+
+        self.clear_extra_cursors();
+        let selection = self.get_selection_ends();
+        let (start, end) = match selection {
+            Some((start, end)) => (start.min(end), start.max(end)),
+            None => {
+                let line = self.lines.get_line(self.cursor_line - 1);
+                let indent = Self::calculate_whitespace_num(&line);
+                let end = (line.len() - get_trailing_whitespaces(&line)).max(indent);
+                ((self.cursor_line, indent + 1), (self.cursor_line, end + 1))
+            }
+        };
+
+        let mut lines: Vec<Vec<char>> = self
+            .get_lines_text(start.0, end.0)
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        let last_index = lines.len() - 1;
+        let is_single_line = last_index == 0;
+
+        // spaces around the selected text don't count
+        let mut from = start.1 - 1;
+        let mut to = end.1 - 1;
+        while from < lines[0].len() && (!is_single_line || from < to) && lines[0][from] == ' ' {
+            from += 1;
+        }
+        while to > 0 && (!is_single_line || to > from) && lines[last_index][to - 1] == ' ' {
+            to -= 1;
+        }
+
+        let is_commented = (!is_single_line || to >= from + prefix.len() + suffix.len())
+            && lines[0][from..].starts_with(&prefix)
+            && lines[last_index][..to].ends_with(&suffix);
+
+        // the end is changed first, so `from` stays valid
+        let (start_delta, end_delta) = if is_commented {
+            let mut end_from = to - suffix.len();
+            if end_from > 0
+                && lines[last_index][end_from - 1] == ' '
+                && (!is_single_line || end_from > from + prefix.len())
+            {
+                end_from -= 1;
+            }
+            lines[last_index].drain(end_from..to);
+
+            let mut start_to = from + prefix.len();
+            if lines[0].get(start_to) == Some(&' ') {
+                start_to += 1;
+            }
+            lines[0].drain(from..start_to);
+
+            (-((start_to - from) as isize), -((to - end_from) as isize))
+        } else {
+            let end_text: Vec<char> = [vec![' '], suffix].concat();
+            lines[last_index].splice(to..to, end_text.iter().copied());
+            let start_text: Vec<char> = [prefix, vec![' ']].concat();
+            lines[0].splice(from..from, start_text.iter().copied());
+
+            (start_text.len() as isize, end_text.len() as isize)
+        };
+
+        // the selection keeps covering the comment with its tokens
+        let end_shift = if is_single_line {
+            start_delta + end_delta
+        } else {
+            end_delta
+        };
+        let new_end = (end.0, end.1.saturating_add_signed(end_shift).max(1));
+        let is_cursor_at_start = (self.cursor_line, self.cursor_column) == start;
+        let (cursor, selection) = match selection {
+            Some(_) if is_cursor_at_start => (start, Some((new_end, start))),
+            Some(_) => (new_end, Some((start, new_end))),
+            None => {
+                let column = self.cursor_column;
+                let mut line_edits = vec![(from, start_delta)];
+                if is_single_line {
+                    line_edits.push((to, end_delta));
+                }
+                ((self.cursor_line, shift_column(column, &line_edits)), None)
+            }
+        };
+
+        let new_lines = lines.iter().map(String::from_iter).collect();
+        self.replace_lines((start.0, end.0), new_lines, cursor, selection, undo_redo);
+    }
+}
+
+fn add_comment(
+    line: &[char],
+    indent: usize,
+    prefix: &[char],
+    suffix: &[char],
+) -> (Vec<char>, Vec<LineEdit>) {
+    let mut new_line = line[..indent].to_vec();
+    new_line.extend(prefix);
+    new_line.push(' ');
+    new_line.extend(&line[indent..]);
+
+    let mut edits = vec![(indent, prefix.len() as isize + 1)];
+    if !suffix.is_empty() {
+        new_line.push(' ');
+        new_line.extend(suffix);
+        edits.push((line.len(), suffix.len() as isize + 1));
+    }
+
+    (new_line, edits)
+}
+
+/// The comment tokens are removed together with a space after the opening
+/// token and before the closing one
+fn remove_comment(line: &[char], prefix: &[char], suffix: &[char]) -> (Vec<char>, Vec<LineEdit>) {
+    let start = UIState::calculate_whitespace_num(line);
+    let mut start_to = start + prefix.len();
+    if line.get(start_to) == Some(&' ') {
+        start_to += 1;
+    }
+
+    let end = line.len() - get_trailing_whitespaces(line);
+    let mut end_from = end - suffix.len();
+    if !suffix.is_empty() && end_from > start_to && line[end_from - 1] == ' ' {
+        end_from -= 1;
+    }
+    let start_to = start_to.min(end_from);
+
+    let mut new_line = line[..start].to_vec();
+    new_line.extend(&line[start_to..end_from]);
+    new_line.extend(&line[end..]);
+
+    let edits = vec![
+        (start, -((start_to - start) as isize)),
+        (end_from, -((end - end_from) as isize)),
+    ];
+    (new_line, edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::language::get_comment_tokens;
+    use crate::app_state::test_utils::{self, get_text};
+
+    fn create_ui_state(text: &str, language: &str) -> UIState {
+        let mut ui_state = test_utils::create_ui_state(text);
+        ui_state.comment_tokens = get_comment_tokens(Some(language));
+        ui_state
+    }
+
+    #[test]
+    fn toggles_line_comments_at_the_smallest_indentation() {
+        let mut ui_state =
+            create_ui_state("fn main() {\n    if x {\n\n        y();\n    }", "rust");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.set_selection((2, 1), (5, 6));
+        (ui_state.cursor_line, ui_state.cursor_column) = (5, 6);
+
+        ui_state.toggle_line_comment(&mut undo_redo);
+        assert_eq!(
+            get_text(&ui_state),
+            "fn main() {\n    // if x {\n\n    //     y();\n    // }"
+        );
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((2, 1), (5, 9)));
+
+        ui_state.toggle_line_comment(&mut undo_redo);
+        assert_eq!(
+            get_text(&ui_state),
+            "fn main() {\n    if x {\n\n        y();\n    }"
+        );
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(
+            get_text(&ui_state),
+            "fn main() {\n    // if x {\n\n    //     y();\n    // }"
+        );
+    }
+
+    #[test]
+    fn comments_lines_with_block_tokens() {
+        let mut ui_state = create_ui_state("  <p>text</p>", "html");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.toggle_line_comment(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "  <!-- <p>text</p> -->");
+
+        ui_state.toggle_line_comment(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "  <p>text</p>");
+    }
+
+    #[test]
+    fn toggles_block_comments() {
+        let mut ui_state = create_ui_state("let x = a + b;", "rust");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.set_selection((1, 9), (1, 14));
+        ui_state.cursor_column = 14;
+
+        ui_state.toggle_block_comment(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "let x = /* a + b */;");
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 9), (1, 20)));
+
+        ui_state.toggle_block_comment(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "let x = a + b;");
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 9), (1, 14)));
+    }
+}
@@ -19,6 +19,20 @@ impl UIState {
         let index = self.cursor_column - 1;
         let start = (self.cursor_line, self.cursor_column);
 
+        if self.is_inside_empty_pair() {
+            let end = (self.cursor_line, self.cursor_column - 1);
+            if let Some(removed) = self.delete_range(end, (start.0, start.1 + 1)) {
+                self.cursor_column -= 1;
+                undo_redo.add_undo_action(UndoAction::RemoveText(
+                    removed.text,
+                    (start.0, start.1 + 1),
+                    end,
+                    RemoveBufferType::Backspace,
+                ));
+            }
+            return;
+        }
+
         if self.cursor_column == 1 && self.cursor_line == 1 {
             // nothing to remove, we are already at the beginning
             return;
@@ -33,29 +47,17 @@ impl UIState {
             0
         };
 
-        if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
+        if self.cursor_line <= self.lines.len_lines() {
             if index == 0 {
-                // we need to prepend current line to the previous one
-                // 1. call `let line = self.lines.remove(self.cursor_line - 1)`
-                // 2. call self.lines[self.cursor_line - 2].append(line)
-                // 3. call `self.cursor_line -= 1`
-                // 4. call `self.cursor_column = previous_line_len + 1`
+                // we need to prepend current line to the previous one, which is
+                // the same as removing the line ending of the previous line
                 // similar idea with delete, but at the end of the line
+                self.lines.remove(
+                    (self.cursor_line - 2, previous_line_len),
+                    (self.cursor_line - 1, 0),
+                );
 
-                if line.is_empty() {
-                    self.lines.remove(self.cursor_line - 1);
-                } else {
-                    let mut current_line = self.lines.remove(self.cursor_line - 1);
-                    match self.lines.get_mut(self.cursor_line - 2) {
-                        Some(previous_line) => {
-                            previous_line.append(&mut current_line);
-                        }
-                        None => {
-                            return;
-                        }
-                    }
-                }
-
+                self.is_dirty = true;
                 self.cursor_line -= 1;
                 self.cursor_column = previous_line_len + 1;
 
@@ -67,9 +69,16 @@ impl UIState {
                     (self.cursor_line, self.cursor_column),
                     RemoveBufferType::Backspace,
                 ));
-            } else if index <= line.len() {
-                let deleted_character = line.get(index - 1).copied();
-                line.remove(index - 1);
+            } else if index <= self.get_line_len(self.cursor_line - 1) {
+                let line_index = self.cursor_line - 1;
+                self.shift_auto_closed(start, -1);
+                let deleted_character = self
+                    .lines
+                    .remove((line_index, index - 1), (line_index, index))
+                    .chars()
+                    .next();
+                self.auto_closed_version = self.lines.version();
+                self.is_dirty = true;
                 self.cursor_move_left(&KeyModifiers::NONE);
 
                 if let Some(ch) = deleted_character {
@@ -84,6 +93,51 @@ impl UIState {
         }
     }
 
+    /// Remove everything up to the start of the previous word in one undo step;
+    /// at the start of the line, it works the same way as `backspace`
+    pub fn remove_previous_word(&mut self, undo_redo: &mut UndoRedo) {
+        if self.selection.is_some() || self.cursor_column == 1 {
+            self.remove_previous_character(undo_redo);
+            return;
+        }
+
+        self.vertical_offset_target = 0;
+        let start = (self.cursor_line, self.cursor_column);
+        let end = (self.cursor_line, self.get_previous_word_column());
+
+        if let Some(removed) = self.delete_range(end, start) {
+            self.cursor_column = end.1;
+            undo_redo.add_undo_action(UndoAction::RemoveText(
+                removed.text,
+                start,
+                end,
+                RemoveBufferType::Backspace,
+            ));
+        }
+    }
+
+    /// Remove everything up to the end of the next word; the cursor stays
+    pub fn remove_next_word(&mut self, undo_redo: &mut UndoRedo) {
+        if self.selection.is_some() || self.cursor_column > self.get_line_len(self.cursor_line - 1)
+        {
+            self.remove_next_character(undo_redo);
+            return;
+        }
+
+        self.vertical_offset_target = 0;
+        let start = (self.cursor_line, self.cursor_column);
+        let end = (self.cursor_line, self.get_next_word_column());
+
+        if let Some(removed) = self.delete_range(start, end) {
+            undo_redo.add_undo_action(UndoAction::RemoveText(
+                removed.text,
+                start,
+                end,
+                RemoveBufferType::Delete,
+            ));
+        }
+    }
+
     // if `delete` is pressed, we delete the next character
     pub fn remove_next_character(&mut self, undo_redo: &mut UndoRedo) {
         self.vertical_offset_target = 0;
@@ -100,20 +154,19 @@ impl UIState {
 
         let index = self.cursor_column - 1;
 
-        if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
-            let line_len = line.len();
+        if self.cursor_line <= self.lines.len_lines() {
+            let line_index = self.cursor_line - 1;
+            let line_len = self.get_line_len(line_index);
             if index == line_len {
                 // we need to get the next line and append it to the current line
 
-                let is_last_line = self.lines.len() == self.cursor_line;
+                let is_last_line = self.lines.len_lines() == self.cursor_line;
                 if is_last_line {
                     // do nothing, we are at the end of the file
                 } else {
-                    let mut next_line = self.lines.remove(self.cursor_line);
-
-                    if let Some(current_line) = self.lines.get_mut(self.cursor_line - 1) {
-                        current_line.append(&mut next_line);
-                    }
+                    self.lines
+                        .remove((line_index, line_len), (line_index + 1, 0));
+                    self.is_dirty = true;
 
                     undo_redo.add_undo_action(UndoAction::RemoveCharacter(
                         '\n',
@@ -125,8 +178,12 @@ impl UIState {
                     ));
                 }
             } else if line_len > 0 && index < line_len {
-                let deleted_character = line.get(index).copied();
-                line.remove(index);
+                let deleted_character = self
+                    .lines
+                    .remove((line_index, index), (line_index, index + 1))
+                    .chars()
+                    .next();
+                self.is_dirty = true;
 
                 if let Some(ch) = deleted_character {
                     undo_redo.add_undo_action(UndoAction::RemoveCharacter(
@@ -167,7 +224,7 @@ mod test {
             ui_state.remove_previous_character(&mut undo_redo);
         }
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "world!");
         assert_eq!(ui_state.cursor_column, 1);
     }
 
@@ -186,7 +243,7 @@ mod test {
             ui_state.remove_next_character(&mut undo_redo);
         }
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "world!");
         assert_eq!(ui_state.cursor_column, 1);
     }
 
@@ -205,7 +262,7 @@ mod test {
 
         ui_state.remove_previous_character(&mut undo_redo);
 
-        assert_eq!(ui_state.lines.len(), 2);
+        assert_eq!(ui_state.lines.len_lines(), 2);
         assert_eq!(ui_state.cursor_column, 13);
         assert_eq!(ui_state.cursor_line, 1);
 
@@ -224,11 +281,8 @@ mod test {
         assert_eq!(ui_state.cursor_column, 13);
         assert_eq!(ui_state.cursor_line, 1);
 
-        assert_eq!(ui_state.lines.len(), 1);
-        assert_eq!(
-            String::from_iter(&ui_state.lines[0]),
-            "Hello world!Description"
-        );
+        assert_eq!(ui_state.lines.len_lines(), 1);
+        assert_eq!(ui_state.lines.line_to_string(0), "Hello world!Description");
     }
 
     #[test]
@@ -252,15 +306,12 @@ mod test {
 
         ui_state.remove_next_character(&mut undo_redo);
 
-        assert_eq!(ui_state.lines.len(), 2);
+        assert_eq!(ui_state.lines.len_lines(), 2);
 
         ui_state.remove_next_character(&mut undo_redo);
-        assert_eq!(ui_state.lines.len(), 1);
+        assert_eq!(ui_state.lines.len_lines(), 1);
 
-        assert_eq!(
-            String::from_iter(&ui_state.lines[0]),
-            "Hello world!Description"
-        );
+        assert_eq!(ui_state.lines.line_to_string(0), "Hello world!Description");
 
         // navigate to the end of the last line
         ui_state.cursor_move_down(&KeyModifiers::NONE);
@@ -274,4 +325,36 @@ mod test {
         assert_eq!(ui_state.cursor_column, 24);
         assert_eq!(ui_state.cursor_line, 1);
     }
+
+    #[test]
+    fn deletes_words_in_single_undo_steps() {
+        let mut undo_redo = UndoRedo::new();
+        let lines = vec!["let value = 42;".chars().collect()];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 50);
+
+        ui_state.cursor_move_right(&KeyModifiers::CONTROL);
+        ui_state.cursor_move_right(&KeyModifiers::CONTROL);
+        assert_eq!(ui_state.cursor_column, 10);
+
+        ui_state.remove_previous_word(&mut undo_redo);
+        assert_eq!(ui_state.lines.line_to_string(0), "let  = 42;");
+        assert_eq!(ui_state.cursor_column, 5);
+
+        ui_state.remove_next_word(&mut undo_redo);
+        assert_eq!(ui_state.lines.line_to_string(0), "let  42;");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(0), "let  = 42;");
+        assert_eq!(ui_state.cursor_column, 5);
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(0), "let value = 42;");
+        assert_eq!(ui_state.cursor_column, 10);
+
+        undo_redo.redo_action(&mut ui_state);
+        undo_redo.redo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(0), "let  42;");
+        assert_eq!(ui_state.cursor_column, 5);
+    }
 }
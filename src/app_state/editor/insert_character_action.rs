@@ -6,38 +6,179 @@ impl UIState {
     pub fn insert_character(&mut self, character: char, undo_redo: &mut UndoRedo) {
         self.vertical_offset_target = 0;
 
+        let closing = self.get_closing_char(character);
+        if let Some(closing) = closing
+            && self.selection.is_some()
+        {
+            self.surround_selection(character, closing, undo_redo);
+            return;
+        }
+
+        if self.should_skip_closing_char(character) {
+            let position = (self.cursor_line, self.cursor_column);
+            self.auto_closed.retain(|closing| *closing != position);
+            self.cursor_move_right(&KeyModifiers::NONE);
+            return;
+        }
+
         // after deleting selection, we need to insert the character normally
         let deleted_selection = self.delete_selection();
 
-        let result = self.lines.get_mut(self.cursor_line - 1);
-
-        match result {
-            Some(line) => {
-                let index = self.cursor_column - 1;
-                if index <= line.len() {
-                    line.insert(index, character);
-                    let start = (self.cursor_line, self.cursor_column);
-                    // manually set to have no modifiers, so the newly inserted character is not selected
-                    self.cursor_move_right(&KeyModifiers::NONE);
-                    let end = (self.cursor_line, self.cursor_column);
-                    undo_redo.add_undo_action(UndoAction::AddCharacter(
-                        character,
-                        start,
-                        end,
-                        deleted_selection,
-                    ));
-                }
-            }
-            None => {
-                // ????
+        if self.cursor_line > self.lines.len_lines() {
+            // ????
+            return;
+        }
+
+        let index = self.cursor_column - 1;
+        if let Some(closing) = closing
+            && self.should_close_pair(character)
+        {
+            let start = (self.cursor_line, self.cursor_column);
+            let pair = String::from_iter([character, closing]);
+            self.shift_auto_closed(start, 2);
+            self.lines.insert(self.cursor_line - 1, index, &pair);
+            self.auto_closed.push((start.0, start.1 + 1));
+            self.auto_closed_version = self.lines.version();
+            self.is_dirty = true;
+            self.cursor_column += 1;
+            // the pair is a single step, the text typed inside is another one
+            undo_redo.add_undo_action(UndoAction::Paste(
+                pair,
+                start,
+                (start.0, start.1 + 2),
+                deleted_selection,
+            ));
+        } else if index <= self.get_line_len(self.cursor_line - 1) {
+            let start = (self.cursor_line, self.cursor_column);
+            self.shift_auto_closed(start, 1);
+            self.lines.insert(
+                self.cursor_line - 1,
+                index,
+                character.encode_utf8(&mut [0; 4]),
+            );
+            self.auto_closed_version = self.lines.version();
+            self.is_dirty = true;
+            // manually set to have no modifiers, so the newly inserted character is not selected
+            self.cursor_move_right(&KeyModifiers::NONE);
+            let end = (self.cursor_line, self.cursor_column);
+            undo_redo.add_undo_action(UndoAction::AddCharacter(
+                character,
+                start,
+                end,
+                deleted_selection,
+            ));
+        }
+    }
+
+    fn get_closing_char(&self, character: char) -> Option<char> {
+        self.auto_pairs
+            .iter()
+            .find(|(opening, _)| *opening == character)
+            .map(|(_, closing)| *closing)
+    }
+
+    /// Closing characters after the position move with the text typed or
+    /// removed before them; they are forgotten if the buffer was edited otherwise
+    pub(super) fn shift_auto_closed(&mut self, (line, column): (usize, usize), length: isize) {
+        if self.auto_closed_version != self.lines.version() {
+            self.auto_closed.clear();
+        }
+
+        for (closing_line, closing_column) in &mut self.auto_closed {
+            if *closing_line == line && *closing_column >= column {
+                *closing_column = closing_column.saturating_add_signed(length);
             }
         }
     }
+
+    /// Typing the closing character right before the same one only moves
+    /// the cursor over it, if it was inserted together with the opening one
+    fn should_skip_closing_char(&self, character: char) -> bool {
+        let is_closing = self
+            .auto_pairs
+            .iter()
+            .any(|(_, closing)| *closing == character);
+        let is_auto_closed = self.auto_closed_version == self.lines.version()
+            && self
+                .auto_closed
+                .contains(&(self.cursor_line, self.cursor_column));
+
+        is_closing
+            && is_auto_closed
+            && self.selection.is_none()
+            && self
+                .lines
+                .line_chars(self.cursor_line - 1)
+                .nth(self.cursor_column - 1)
+                == Some(character)
+    }
+
+    /// The pair is closed only before spaces and closing characters, and
+    /// quotes are not closed right after a word, like in `don't`
+    fn should_close_pair(&self, opening: char) -> bool {
+        let line = self.lines.get_line(self.cursor_line - 1);
+        let index = self.cursor_column - 1;
+
+        let is_next_free = line.get(index).is_none_or(|next| {
+            next.is_whitespace() || self.auto_pairs.iter().any(|(_, closing)| closing == next)
+        });
+        let is_quote = self.get_closing_char(opening) == Some(opening);
+        let is_after_word = index
+            .checked_sub(1)
+            .and_then(|index| line.get(index))
+            .is_some_and(|previous| previous.is_alphanumeric() || *previous == opening);
+
+        is_next_free && !(is_quote && is_after_word)
+    }
+
+    /// Put the pair around the selection; the text inside stays selected
+    fn surround_selection(&mut self, opening: char, closing: char, undo_redo: &mut UndoRedo) {
+        let Some((start, end)) = self.get_selection_ends() else {
+            return;
+        };
+        let (first, last) = (start.min(end), start.max(end));
+
+        let mut lines: Vec<Vec<char>> = self
+            .get_lines_text(first.0, last.0)
+            .iter()
+            .map(|line| line.chars().collect())
+            .collect();
+        let last_index = lines.len() - 1;
+        lines[last_index].insert(last.1 - 1, closing);
+        lines[0].insert(first.1 - 1, opening);
+
+        let move_position = |(line, column): (usize, usize)| {
+            if line == first.0 && column >= first.1 {
+                (line, column + 1)
+            } else {
+                (line, column)
+            }
+        };
+        let cursor = move_position((self.cursor_line, self.cursor_column));
+        let selection = Some((move_position(start), move_position(end)));
+        let new_lines = lines.iter().map(String::from_iter).collect();
+        self.replace_lines((first.0, last.0), new_lines, cursor, selection, undo_redo);
+    }
+
+    /// Backspace right between an opening character and its closing one
+    /// removes both
+    pub(super) fn is_inside_empty_pair(&self) -> bool {
+        let Some(index) = self.cursor_column.checked_sub(2) else {
+            return false;
+        };
+        let mut characters = self.lines.line_chars(self.cursor_line - 1).skip(index);
+        let (Some(previous), Some(next)) = (characters.next(), characters.next()) else {
+            return false;
+        };
+
+        self.auto_pairs.contains(&(previous, next))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app_state::test_utils;
 
     #[test]
     fn inserts_new_character_correctly() {
@@ -58,6 +199,93 @@ mod tests {
         ui_state.insert_character('y', &mut undo_redo);
         ui_state.insert_character(' ', &mut undo_redo);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "Hello my world!")
+        assert_eq!(ui_state.lines.line_to_string(0), "Hello my world!")
+    }
+
+    fn create_ui_state(text: &str) -> UIState {
+        let mut ui_state = test_utils::create_ui_state(text);
+        ui_state.auto_pairs = vec![('(', ')'), ('"', '"'), ('\'', '\'')];
+        ui_state
+    }
+
+    fn type_text(ui_state: &mut UIState, undo_redo: &mut UndoRedo, text: &str) {
+        for character in text.chars() {
+            ui_state.insert_character(character, undo_redo);
+        }
+    }
+
+    #[test]
+    fn closes_pairs_and_skips_closing_characters() {
+        let mut ui_state = create_ui_state("");
+        let mut undo_redo = UndoRedo::new();
+
+        type_text(&mut ui_state, &mut undo_redo, "f(\"x");
+        assert_eq!(ui_state.lines.to_text(), "f(\"x\")");
+        type_text(&mut ui_state, &mut undo_redo, "\")");
+        assert_eq!(ui_state.lines.to_text(), "f(\"x\")");
+        assert_eq!(ui_state.cursor_column, 7);
+
+        // no pair after a word, or before one
+        type_text(&mut ui_state, &mut undo_redo, " don't");
+        assert_eq!(ui_state.lines.to_text(), "f(\"x\") don't");
+        ui_state.cursor_column = 1;
+        type_text(&mut ui_state, &mut undo_redo, "(");
+        assert_eq!(ui_state.lines.to_text(), "(f(\"x\") don't");
+    }
+
+    #[test]
+    fn skips_only_automatically_inserted_characters() {
+        let mut ui_state = create_ui_state("f(a)");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.cursor_column = 4;
+
+        // the bracket was already in the file
+        type_text(&mut ui_state, &mut undo_redo, ")");
+        assert_eq!(ui_state.lines.to_text(), "f(a))");
+
+        ui_state.cursor_move_line_end(&KeyModifiers::NONE);
+        type_text(&mut ui_state, &mut undo_redo, " (x");
+        ui_state.remove_previous_character(&mut undo_redo);
+        type_text(&mut ui_state, &mut undo_redo, "y)");
+        assert_eq!(ui_state.lines.to_text(), "f(a)) (y)");
+
+        // other edits forget the inserted characters
+        type_text(&mut ui_state, &mut undo_redo, " (z");
+        ui_state.remove_next_character(&mut undo_redo);
+        type_text(&mut ui_state, &mut undo_redo, ")");
+        assert_eq!(ui_state.lines.to_text(), "f(a)) (y) (z)");
+    }
+
+    #[test]
+    fn removes_empty_pairs_in_one_step() {
+        let mut ui_state = create_ui_state("call");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.cursor_column = 5;
+
+        type_text(&mut ui_state, &mut undo_redo, "(");
+        assert_eq!(ui_state.lines.to_text(), "call()");
+        ui_state.remove_previous_character(&mut undo_redo);
+        assert_eq!(ui_state.lines.to_text(), "call");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "call()");
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "call");
+    }
+
+    #[test]
+    fn surrounds_the_selection() {
+        let mut ui_state = create_ui_state("let x = a + b;");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.set_selection((1, 9), (1, 14));
+        ui_state.cursor_column = 14;
+
+        type_text(&mut ui_state, &mut undo_redo, "(");
+        assert_eq!(ui_state.lines.to_text(), "let x = (a + b);");
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 10), (1, 15)));
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "let x = a + b;");
     }
 }
@@ -0,0 +1,342 @@
+use crate::app_state::editor::UIState;
+use crate::app_state::selection::Selection;
+use crate::app_state::undo_redo::{LineReplacement, LinesChange, UndoAction, UndoRedo};
+
+pub(super) type Range = ((usize, usize), (usize, usize));
+
+impl UIState {
+    /// Lines touched by the selection, or the line of the cursor; a selection
+    /// which ends at the very start of a line does not include that line
+    pub(super) fn get_selected_lines(&self) -> (usize, usize) {
+        let Some(selection) = &self.selection else {
+            return (self.cursor_line, self.cursor_line);
+        };
+
+        let start = selection.start.min(selection.end);
+        let end = selection.start.max(selection.end);
+        if end.1 == 1 && end.0 > start.0 {
+            (start.0, end.0 - 1)
+        } else {
+            (start.0, end.0)
+        }
+    }
+
+    pub(super) fn get_lines_text(&self, first_line: usize, last_line: usize) -> Vec<String> {
+        (first_line..=last_line)
+            .map(|line| self.lines.line_chars(line - 1).collect())
+            .collect()
+    }
+
+    pub(crate) fn get_selection_ends(&self) -> Option<Range> {
+        self.selection
+            .as_ref()
+            .map(|selection| (selection.start, selection.end))
+    }
+
+    /// Replace the text of `lines_number` lines starting from the line,
+    /// used by line actions and by undo/redo of replaced lines
+    pub(crate) fn set_lines_text(&mut self, first_line: usize, lines_number: usize, text: &str) {
+        let last_line = first_line + lines_number - 1;
+        let last_line_len = self.get_line_len(last_line - 1);
+
+        self.lines
+            .remove((first_line - 1, 0), (last_line - 1, last_line_len));
+        self.lines.insert(first_line - 1, 0, text);
+        self.is_dirty = true;
+    }
+
+    pub(crate) fn set_cursor_and_selection(
+        &mut self,
+        (line, column): (usize, usize),
+        selection: Option<Range>,
+    ) {
+        self.cursor_line = line;
+        self.cursor_column = column;
+        self.vertical_offset_target = 0;
+        self.selection = None;
+        if let Some((start, end)) = selection {
+            self.set_selection(start, end);
+        }
+        self.handle_cursor_scrolling();
+    }
+
+    /// Put new lines instead of the ones from `first_line` to `last_line`,
+    /// as a single undo step
+    pub(super) fn replace_lines(
+        &mut self,
+        (first_line, last_line): (usize, usize),
+        new_lines: Vec<String>,
+        cursor: (usize, usize),
+        selection: Option<Range>,
+        undo_redo: &mut UndoRedo,
+    ) {
+        let replacement = LineReplacement {
+            line: first_line,
+            old_text: self.get_lines_text(first_line, last_line).join("\n"),
+            new_text: new_lines.join("\n"),
+        };
+        self.set_lines_text(
+            first_line,
+            last_line - first_line + 1,
+            &replacement.new_text,
+        );
+
+        let change = LinesChange {
+            lines: vec![replacement],
+            cursor_before: (self.cursor_line, self.cursor_column),
+            cursor_after: cursor,
+            selection_before: self.get_selection_ends(),
+            selection_after: selection,
+        };
+
+        self.set_cursor_and_selection(cursor, selection);
+        undo_redo.add_undo_action(UndoAction::Replace(change));
+    }
+
+    /// Swap the selected lines with the one above or below them; the cursor
+    /// and the selection move together with the text
+    pub fn move_lines(&mut self, is_up: bool, undo_redo: &mut UndoRedo) {
+        self.clear_extra_cursors();
+        let (first_line, last_line) = self.get_selected_lines();
+
+        let (range, new_lines, shift) = if is_up {
+            if first_line == 1 {
+                return;
+            }
+            let mut lines = self.get_lines_text(first_line, last_line);
+            lines.extend(self.get_lines_text(first_line - 1, first_line - 1));
+            ((first_line - 1, last_line), lines, -1)
+        } else {
+            if last_line == self.lines.len_lines() {
+                return;
+            }
+            let mut lines = self.get_lines_text(last_line + 1, last_line + 1);
+            lines.extend(self.get_lines_text(first_line, last_line));
+            ((first_line, last_line + 1), lines, 1)
+        };
+
+        let move_position =
+            |(line, column): (usize, usize)| (line.saturating_add_signed(shift), column);
+        let cursor = move_position((self.cursor_line, self.cursor_column));
+        let selection = self
+            .get_selection_ends()
+            .map(|(start, end)| (move_position(start), move_position(end)));
+        self.replace_lines(range, new_lines, cursor, selection, undo_redo);
+    }
+
+    /// Copy the selected lines below them, the cursor goes to the copy
+    pub fn duplicate_lines(&mut self, undo_redo: &mut UndoRedo) {
+        self.clear_extra_cursors();
+        let (first_line, last_line) = self.get_selected_lines();
+        let lines = self.get_lines_text(first_line, last_line);
+        let lines_number = lines.len();
+
+        let move_position = |(line, column): (usize, usize)| (line + lines_number, column);
+        let cursor = move_position((self.cursor_line, self.cursor_column));
+        let selection = self
+            .get_selection_ends()
+            .map(|(start, end)| (move_position(start), move_position(end)));
+        self.replace_lines(
+            (first_line, last_line),
+            [lines.clone(), lines].concat(),
+            cursor,
+            selection,
+            undo_redo,
+        );
+    }
+
+    /// Remove the selected lines completely, together with their line breaks
+    pub fn delete_lines(&mut self, undo_redo: &mut UndoRedo) {
+        self.clear_extra_cursors();
+        let (first_line, last_line) = self.get_selected_lines();
+        let lines_number = self.lines.len_lines();
+
+        // the line which stays is replaced too, so the line break goes away
+        let (range, kept_line) = if last_line < lines_number {
+            ((first_line, last_line + 1), last_line + 1)
+        } else if first_line > 1 {
+            ((first_line - 1, last_line), first_line - 1)
+        } else {
+            ((first_line, last_line), 0)
+        };
+
+        let new_lines = if kept_line == 0 {
+            vec![String::new()]
+        } else {
+            self.get_lines_text(kept_line, kept_line)
+        };
+        let cursor_line = range.0;
+        let cursor_column = self.cursor_column.min(new_lines[0].chars().count() + 1);
+        self.replace_lines(
+            range,
+            new_lines,
+            (cursor_line, cursor_column),
+            None,
+            undo_redo,
+        );
+    }
+
+    /// Remove spaces and tabs at the end of every line as a single undo step;
+    /// the cursor and the selection stay within the shortened lines
+    pub fn trim_trailing_whitespace(&mut self, undo_redo: &mut UndoRedo) {
+        let trim = |line: &str| line.trim_end_matches([' ', '\t']).to_string();
+
+        let changed_lines: Vec<usize> = (1..=self.lines.len_lines())
+            .filter(|line| matches!(self.lines.line_chars(line - 1).last(), Some(' ' | '\t')))
+            .collect();
+        let (Some(first_line), Some(last_line)) = (changed_lines.first(), changed_lines.last())
+        else {
+            return;
+        };
+
+        let new_lines: Vec<String> = self
+            .get_lines_text(*first_line, *last_line)
+            .iter()
+            .map(|line| trim(line))
+            .collect();
+        let new_lengths: Vec<usize> = new_lines.iter().map(|line| line.chars().count()).collect();
+
+        let clamp = |(line, column): (usize, usize)| match line
+            .checked_sub(*first_line)
+            .and_then(|index| new_lengths.get(index))
+        {
+            Some(new_length) => (line, column.min(new_length + 1)),
+            None => (line, column),
+        };
+        let cursor = clamp((self.cursor_line, self.cursor_column));
+        let selection = self
+            .get_selection_ends()
+            .map(|(start, end)| (clamp(start), clamp(end)))
+            .filter(|(start, end)| start != end);
+
+        // other cursors are kept too, e.g. when the file is saved
+        for extra_cursor in &mut self.extra_cursors {
+            (extra_cursor.line, extra_cursor.column) =
+                clamp((extra_cursor.line, extra_cursor.column));
+            extra_cursor.selection = extra_cursor
+                .selection
+                .take()
+                .map(|selection| (clamp(selection.start), clamp(selection.end)))
+                .filter(|(start, end)| start != end)
+                .map(|(start, end)| {
+                    let mut selection = Selection::new(start.0, start.1);
+                    selection.set_end(end.0, end.1);
+                    selection
+                });
+        }
+        self.replace_lines(
+            (*first_line, *last_line),
+            new_lines,
+            cursor,
+            selection,
+            undo_redo,
+        );
+    }
+
+    /// Join the selected lines, or the line of the cursor with the next one.
+    /// Indentation of the joined lines and spaces at the line ends become
+    /// a single space.
+    pub fn join_lines(&mut self, undo_redo: &mut UndoRedo) {
+        self.clear_extra_cursors();
+        let (first_line, mut last_line) = self.get_selected_lines();
+        if first_line == last_line {
+            if last_line == self.lines.len_lines() {
+                return;
+            }
+            last_line += 1;
+        }
+
+        let mut lines = self.get_lines_text(first_line, last_line).into_iter();
+        let mut joined = lines.next().unwrap_or_default();
+        let mut cursor_column = 1;
+        for line in lines {
+            joined.truncate(joined.trim_end().len());
+            let line = line.trim_start();
+            if !joined.is_empty() && !line.is_empty() {
+                joined.push(' ');
+            }
+            cursor_column = joined.chars().count() + 1;
+            joined.push_str(line);
+        }
+
+        self.replace_lines(
+            (first_line, last_line),
+            vec![joined],
+            (first_line, cursor_column),
+            None,
+            undo_redo,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::{create_ui_state, get_text};
+
+    #[test]
+    fn moves_lines_with_the_selection() {
+        let mut ui_state = create_ui_state("one\ntwo\nthree\nfour");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.set_selection((2, 2), (3, 3));
+        (ui_state.cursor_line, ui_state.cursor_column) = (3, 3);
+
+        ui_state.move_lines(true, &mut undo_redo);
+        assert_eq!(get_text(&ui_state), "two\nthree\none\nfour");
+        ui_state.move_lines(true, &mut undo_redo);
+        assert_eq!(get_text(&ui_state), "two\nthree\none\nfour");
+
+        ui_state.move_lines(false, &mut undo_redo);
+        ui_state.move_lines(false, &mut undo_redo);
+        assert_eq!(get_text(&ui_state), "one\nfour\ntwo\nthree");
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (4, 3));
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((3, 2), (4, 3)));
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "one\ntwo\nthree\nfour");
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((2, 2), (3, 3)));
+    }
+
+    #[test]
+    fn duplicates_and_deletes_lines() {
+        let mut ui_state = create_ui_state("one\ntwo\nthree");
+        let mut undo_redo = UndoRedo::new();
+        (ui_state.cursor_line, ui_state.cursor_column) = (2, 3);
+
+        ui_state.duplicate_lines(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "one\ntwo\ntwo\nthree");
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (3, 3));
+
+        ui_state.go_to_line(4, 5);
+        ui_state.delete_lines(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "one\ntwo\ntwo");
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (3, 4));
+
+        ui_state.set_selection((1, 1), (3, 1));
+        ui_state.delete_lines(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "two");
+
+        undo_redo.undo_action(&mut ui_state);
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "one\ntwo\ntwo\nthree");
+    }
+
+    #[test]
+    fn joins_lines() {
+        let mut ui_state = create_ui_state("fn main() {  \n    call();\n\n}");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.join_lines(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "fn main() { call();\n\n}");
+        assert_eq!(ui_state.cursor_column, 13);
+
+        ui_state.set_selection((1, 1), (3, 2));
+        ui_state.join_lines(&mut undo_redo);
+        assert_eq!(get_text(&ui_state), "fn main() { call(); }");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "fn main() { call();\n\n}");
+    }
+}
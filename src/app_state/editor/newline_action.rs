@@ -10,49 +10,37 @@ impl UIState {
 
         let start = (self.cursor_line, self.cursor_column);
 
-        let current_line_len = self.get_line_len(self.cursor_line - 1);
-
-        let whitespaces = match self.lines.get(self.cursor_line - 1) {
-            Some(line) => Self::calculate_whitespace_num(line),
-            None => 0,
-        };
-
-        if self.cursor_column > current_line_len {
-            self.lines.insert(self.cursor_line, vec![' '; whitespaces]);
-
-            self.cursor_column = 1 + whitespaces;
-            self.cursor_line += 1;
-
-            self.handle_cursor_scrolling();
+        if self.cursor_line > self.lines.len_lines() {
+            // ???
+            return;
+        }
 
-            self.add_newline_undo(undo_redo, whitespaces, start, removed_selection);
-        } else {
-            match self.lines.get_mut(self.cursor_line - 1) {
-                Some(line) => {
-                    let new_line = line.split_off(self.cursor_column - 1);
+        // copy the indentation as it is, so tabs stay tabs
+        let indent: String = self
+            .lines
+            .line_chars(self.cursor_line - 1)
+            .take(self.cursor_column - 1)
+            .take_while(|c| c.is_whitespace())
+            .collect();
 
-                    let prefixed_line =
-                        vec![' '; whitespaces].into_iter().chain(new_line).collect();
+        // the rest of the line (if any) is moved to the new line after the indentation
+        let text = format!("\n{indent}");
+        self.lines
+            .insert(self.cursor_line - 1, self.cursor_column - 1, &text);
+        self.is_dirty = true;
 
-                    self.lines.insert(self.cursor_line, prefixed_line);
-                    self.cursor_line += 1;
-                    self.cursor_column = 1 + whitespaces;
+        self.cursor_line += 1;
+        self.cursor_column = 1 + indent.chars().count();
 
-                    self.handle_cursor_scrolling();
+        self.handle_cursor_scrolling();
 
-                    self.add_newline_undo(undo_redo, whitespaces, start, removed_selection);
-                }
-                None => {
-                    // ???
-                }
-            }
-        }
+        self.add_newline_undo(undo_redo, &indent, start, removed_selection);
     }
 
     fn add_newline_undo(
         &self,
         undo_redo: &mut UndoRedo,
-        whitespaces: usize,
+        indent: &str,
         start: (usize, usize),
         removed_selection: Option<UndoSelection>,
     ) {
@@ -65,16 +53,14 @@ impl UIState {
         ));
 
         // after that, we need to insert whitespaces individually to reuse the same API
-        if whitespaces > 0 {
-            for i in 0..whitespaces {
-                undo_redo.add_undo_action(UndoAction::AddCharacter(
-                    ' ',
-                    start,
-                    // 1 because column is counted from 1, 1 because `i` starts from 0
-                    (self.cursor_line, i + 2),
-                    None,
-                ));
-            }
+        for (i, character) in indent.chars().enumerate() {
+            undo_redo.add_undo_action(UndoAction::AddCharacter(
+                character,
+                start,
+                // 1 because column is counted from 1, 1 because `i` starts from 0
+                (self.cursor_line, i + 2),
+                None,
+            ));
         }
     }
 }
@@ -105,8 +91,8 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 1);
         assert_eq!(ui_state.cursor_line, 2);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "Hel");
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "lo world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "Hel");
+        assert_eq!(ui_state.lines.line_to_string(1), "lo world!");
 
         ui_state.cursor_move_line_end(&KeyModifiers::NONE);
         ui_state.add_new_line(&mut undo_redo);
@@ -114,8 +100,8 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 1);
         assert_eq!(ui_state.cursor_line, 3);
 
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "lo world!");
-        assert_eq!(String::from_iter(&ui_state.lines[4]), "Description");
+        assert_eq!(ui_state.lines.line_to_string(1), "lo world!");
+        assert_eq!(ui_state.lines.line_to_string(4), "Description");
     }
 
     #[test]
@@ -135,7 +121,7 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 5);
         assert_eq!(ui_state.cursor_line, 1);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "    Hello world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "    Hello world!");
 
         ui_state.cursor_move_line_end(&KeyModifiers::NONE);
         ui_state.add_new_line(&mut undo_redo);
@@ -143,6 +129,20 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 5);
         assert_eq!(ui_state.cursor_line, 2);
 
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "    ");
+        assert_eq!(ui_state.lines.line_to_string(1), "    ");
+    }
+
+    #[test]
+    fn copies_tab_indentation() {
+        let mut undo_redo = UndoRedo::new();
+        let lines = vec![vec!['\t', ' ', 'a']];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 50);
+
+        ui_state.cursor_move_line_end(&KeyModifiers::NONE);
+        ui_state.add_new_line(&mut undo_redo);
+
+        assert_eq!(ui_state.lines.line_to_string(1), "\t ");
+        assert_eq!(ui_state.cursor_column, 3);
     }
 }
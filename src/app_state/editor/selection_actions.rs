@@ -29,23 +29,16 @@ impl UIState {
         }
     }
 
+    #[cfg(test)]
     /// a single check to see if we have _any_ selection
     pub fn has_any_selection(&self) -> bool {
         self.selection.is_some()
     }
 
-    /// a single check to see if the entire line is selected
-    pub fn is_entire_line_selected(&self, line: usize) -> bool {
-        match &self.selection {
-            Some(selection) => selection.is_line_selected(line),
-            None => false,
-        }
-    }
-
+    #[cfg(test)]
     /// Get selection range -- intended to be called once per line
     /// and is not meant to return range of the full line.
     ///
-    /// To check if the entire line is selected, call `is_entire_line_selected` method.
     /// Line needs to be in cursor mode, starting with `1``
     pub fn get_selection_range(&self, line: usize) -> Option<(usize, usize)> {
         match &self.selection {
@@ -81,61 +74,23 @@ impl UIState {
         start: (usize, usize),
         end: (usize, usize),
     ) -> Option<UndoSelection> {
-        let (start_line, start_column) = start;
-        let (end_line, end_column) = end;
-        let min_line = start_line.min(end_line) - 1;
-        let max_line = start_line.max(end_line) - 1;
-
-        if min_line == max_line {
-            // if the line is equal, we need to remove selected range
-            let min_column = start_column.min(end_column);
-            let max_column = start_column.max(end_column);
-
-            self.lines.get_mut(min_line).map(|line| UndoSelection {
-                text: line.drain((min_column - 1)..(max_column - 1)).collect(),
-                start,
-                end,
-            })
-        } else {
-            // if there are multiple lines, we need to get the last line, remove selected elements;
-            // get the first line, remove selected elements; append last line to the first
-            // if there are lines between first and last, we need to remove them
-
-            let mut result = String::new();
+        let min_position = start.min(end);
+        let max_position = start.max(end);
+        let (min_line, min_column) = min_position;
+        let (max_line, max_column) = max_position;
 
-            let mut last_line = self.lines.remove(max_line);
-
-            let (last_line_col, first_line_col) = if end_line > start_line {
-                (end_column, start_column)
-            } else {
-                (start_column, end_column)
-            };
-
-            let last_line_data: Vec<char> = last_line.drain(0..(last_line_col - 1)).collect();
-
-            let first_line = self.lines.get_mut(min_line)?;
-
-            result.extend(first_line.drain((first_line_col - 1)..));
-            first_line.append(&mut last_line);
+        if min_line > self.lines.len_lines() {
+            return None;
+        }
 
-            let lines_between = max_line - min_line - 1;
-            // it means that we have some lines we need to completely remove
-            if lines_between > 0 {
-                for i in 0..lines_between {
-                    result.push('\n');
-                    result.extend(self.lines.remove(max_line - i - 1));
-                }
-            }
+        self.is_dirty = true;
 
-            result.push('\n');
-            result.extend(last_line_data);
+        let text = self.lines.remove(
+            (min_line - 1, min_column - 1),
+            (max_line - 1, max_column - 1),
+        );
 
-            Some(UndoSelection {
-                text: result,
-                start,
-                end,
-            })
-        }
+        Some(UndoSelection { text, start, end })
     }
 }
 
@@ -231,7 +186,7 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 4);
         assert_eq!(ui_state.cursor_line, 1);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "Hel world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "Hel world!");
 
         ui_state.cursor_move_left(&KeyModifiers::SHIFT);
         ui_state.cursor_move_left(&KeyModifiers::SHIFT);
@@ -241,7 +196,7 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 2);
         assert_eq!(ui_state.cursor_line, 1);
 
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "H world!");
+        assert_eq!(ui_state.lines.line_to_string(0), "H world!");
 
         ui_state.cursor_move_down(&KeyModifiers::NONE);
         ui_state.cursor_move_right(&KeyModifiers::NONE);
@@ -253,7 +208,7 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 4);
         assert_eq!(ui_state.cursor_line, 2);
 
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "Anocription");
+        assert_eq!(ui_state.lines.line_to_string(1), "Anocription");
 
         ui_state.cursor_move_right(&KeyModifiers::SHIFT);
         ui_state.cursor_move_right(&KeyModifiers::SHIFT);
@@ -266,7 +221,7 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 8);
         assert_eq!(ui_state.cursor_line, 2);
 
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "AnoROOTiption");
+        assert_eq!(ui_state.lines.line_to_string(1), "AnoROOTiption");
 
         ui_state.cursor_move_left(&KeyModifiers::SHIFT);
         ui_state.cursor_move_left(&KeyModifiers::SHIFT);
@@ -276,9 +231,9 @@ mod tests {
         assert_eq!(ui_state.cursor_column, 1);
         assert_eq!(ui_state.cursor_line, 3);
 
-        assert_eq!(String::from_iter(&ui_state.lines[1]), "AnoRO");
-        assert_eq!(String::from_iter(&ui_state.lines[2]), "iption");
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "H world!");
+        assert_eq!(ui_state.lines.line_to_string(1), "AnoRO");
+        assert_eq!(ui_state.lines.line_to_string(2), "iption");
+        assert_eq!(ui_state.lines.line_to_string(0), "H world!");
 
         ui_state.cursor_move_line_end(&KeyModifiers::NONE);
         ui_state.add_new_line(&mut undo_redo);
@@ -293,7 +248,7 @@ mod tests {
         ui_state.insert_character('n', &mut undo_redo);
         ui_state.insert_character('g', &mut undo_redo);
 
-        assert_eq!(String::from_iter(&ui_state.lines[3]), "Something");
+        assert_eq!(ui_state.lines.line_to_string(3), "Something");
 
         ui_state.cursor_move_left(&KeyModifiers::NONE);
         ui_state.cursor_move_left(&KeyModifiers::NONE);
@@ -309,8 +264,8 @@ mod tests {
 
         ui_state.insert_character(' ', &mut undo_redo);
 
-        assert_eq!(ui_state.lines.len(), 1);
-        assert_eq!(String::from_iter(&ui_state.lines[0]), "H world ng");
+        assert_eq!(ui_state.lines.len_lines(), 1);
+        assert_eq!(ui_state.lines.line_to_string(0), "H world ng");
     }
 
     #[test]
@@ -1,31 +1,31 @@
 use crate::app_state::app::Config;
 use crate::app_state::editor::UIState;
+use crate::app_state::text_buffer::TextBuffer;
 use crate::app_state::undo_redo::UndoRedo;
 
 impl UIState {
     pub fn handle_tab_key(&mut self, config: &Config, undo_redo: &mut UndoRedo) {
         self.vertical_offset_target = 0;
-        if config.tabs_to_spaces {
-            if let Some(selection) = &mut self.selection {
-                let (start_line, start_column) = selection.start;
-                let (end_line, end_column) = selection.end;
-
-                for line_num in start_line.min(end_line)..=start_line.max(end_line) {
-                    if let Some(line) = self.lines.get_mut(line_num - 1) {
-                        line.splice(0..0, vec![' '; config.whitespaces_amount]);
-                    }
-                }
 
-                selection.start = (start_line, start_column + config.whitespaces_amount);
-                selection.set_end(end_line, end_column + config.whitespaces_amount);
-                self.cursor_column += config.whitespaces_amount;
-            } else {
-                for _ in 0..config.whitespaces_amount {
-                    self.insert_character(' ', undo_redo);
-                }
+        let indent = get_indent(config);
+
+        if let Some(selection) = &mut self.selection {
+            let (start_line, start_column) = selection.start;
+            let (end_line, end_column) = selection.end;
+            let indent_len = indent.chars().count();
+
+            for line_num in start_line.min(end_line)..=start_line.max(end_line) {
+                self.lines.insert(line_num - 1, 0, &indent);
             }
+
+            self.is_dirty = true;
+            selection.start = (start_line, start_column + indent_len);
+            selection.set_end(end_line, end_column + indent_len);
+            self.cursor_column += indent_len;
         } else {
-            self.insert_character('\t', undo_redo);
+            for character in indent.chars() {
+                self.insert_character(character, undo_redo);
+            }
         }
     }
 
@@ -33,81 +33,168 @@ impl UIState {
         line.iter().take_while(|c| c.is_whitespace()).count()
     }
 
+    /// Same as `calculate_whitespace_num`, but without copying the line
+    pub fn get_line_whitespace_num(&self, index: usize) -> usize {
+        self.lines
+            .line_chars(index)
+            .take_while(|c| c.is_whitespace())
+            .count()
+    }
+
     pub fn handle_backtab_key(&mut self, config: &Config) {
-        if config.tabs_to_spaces {
-            if let Some(selection) = &mut self.selection {
-                let (start_line, start_column) = selection.start;
-                let (end_line, end_column) = selection.end;
-                let mut start_line_removed = 0;
-                let mut end_line_removed = 0;
-
-                for line_num in start_line.min(end_line)..=start_line.max(end_line) {
-                    if let Some(line) = self.lines.get_mut(line_num - 1) {
-                        let whitespaces = Self::calculate_whitespace_num(line);
-                        let remove_num = whitespaces.min(config.whitespaces_amount);
-
-                        if remove_num != 0 {
-                            line.drain(0..remove_num);
-
-                            if line_num == start_line {
-                                start_line_removed = remove_num;
-                            }
+        if let Some(selection) = &mut self.selection {
+            let (start_line, start_column) = selection.start;
+            let (end_line, end_column) = selection.end;
+            let mut start_line_removed = 0;
+            let mut end_line_removed = 0;
+
+            for line_num in start_line.min(end_line)..=start_line.max(end_line) {
+                if line_num <= self.lines.len_lines() {
+                    // `selection` is borrowed, so we can't use a method here
+                    let remove_num = get_unindent_len(self.lines.as_ref(), line_num - 1, config);
+
+                    if remove_num != 0 {
+                        self.lines
+                            .remove((line_num - 1, 0), (line_num - 1, remove_num));
+                        self.is_dirty = true;
+
+                        if line_num == start_line {
+                            start_line_removed = remove_num;
+                        }
 
-                            if line_num == self.cursor_line {
-                                if self.cursor_column <= remove_num {
-                                    self.cursor_column = 1;
-                                } else {
-                                    self.cursor_column -= remove_num;
-                                }
+                        if line_num == self.cursor_line {
+                            if self.cursor_column <= remove_num {
+                                self.cursor_column = 1;
+                            } else {
+                                self.cursor_column -= remove_num;
                             }
+                        }
 
-                            if line_num == end_line {
-                                end_line_removed = remove_num;
-                            }
+                        if line_num == end_line {
+                            end_line_removed = remove_num;
                         }
                     }
                 }
+            }
 
-                if start_line_removed != 0 {
-                    if start_column <= start_line_removed {
-                        selection.start = (start_line, 1);
-                    } else {
-                        selection.start = (start_line, start_column - start_line_removed);
-                    }
+            if start_line_removed != 0 {
+                if start_column <= start_line_removed {
+                    selection.start = (start_line, 1);
+                } else {
+                    selection.start = (start_line, start_column - start_line_removed);
                 }
+            }
 
-                if end_line_removed != 0 {
-                    if end_column <= end_line_removed {
-                        selection.set_end(end_line, 1);
-                    } else {
-                        selection.set_end(end_line, end_column - end_line_removed);
-                    }
-                } else if start_line_removed != 0 {
-                    selection.set_end(end_line, end_column);
+            if end_line_removed != 0 {
+                if end_column <= end_line_removed {
+                    selection.set_end(end_line, 1);
+                } else {
+                    selection.set_end(end_line, end_column - end_line_removed);
                 }
-            } else if let Some(line) = self.lines.get_mut(self.cursor_line - 1) {
-                let whitespaces = Self::calculate_whitespace_num(line);
-                let remove_num = whitespaces.min(config.whitespaces_amount);
-
-                if remove_num != 0 {
-                    line.drain(0..remove_num);
-
-                    if self.cursor_column <= remove_num {
-                        self.cursor_column = 1;
-                    } else {
-                        self.cursor_column -= remove_num;
-                    }
+            } else if start_line_removed != 0 {
+                selection.set_end(end_line, end_column);
+            }
+        } else if self.cursor_line <= self.lines.len_lines() {
+            let remove_num = get_unindent_len(self.lines.as_ref(), self.cursor_line - 1, config);
+
+            if remove_num != 0 {
+                self.lines.remove(
+                    (self.cursor_line - 1, 0),
+                    (self.cursor_line - 1, remove_num),
+                );
+                self.is_dirty = true;
+
+                if self.cursor_column <= remove_num {
+                    self.cursor_column = 1;
+                } else {
+                    self.cursor_column -= remove_num;
                 }
             }
-        } else {
-            // pass for now, handle tabs later
         }
     }
 }
 
+/// A single level of indentation
+fn get_indent(config: &Config) -> String {
+    if config.tabs_to_spaces {
+        " ".repeat(config.whitespaces_amount)
+    } else {
+        String::from("\t")
+    }
+}
+
+/// How many characters to remove from the line start to unindent it by one
+/// level: either a single tab or up to `whitespaces_amount` spaces, no
+/// matter which style the config prefers, as files often mix them
+fn get_unindent_len(lines: &dyn TextBuffer, index: usize, config: &Config) -> usize {
+    let mut spaces = 0;
+
+    for character in lines.line_chars(index) {
+        match character {
+            '\t' => return spaces + 1,
+            ' ' => spaces += 1,
+            _ => break,
+        }
+
+        if spaces == config.whitespaces_amount {
+            break;
+        }
+    }
+
+    spaces
+}
+
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::app_state::selection::Selection;
+
+    fn select(from: (usize, usize), to: (usize, usize)) -> Option<Selection> {
+        let mut selection = Selection::new(from.0, from.1);
+        selection.set_end(to.0, to.1);
+        Some(selection)
+    }
+
+    fn tabs_config() -> Config {
+        Config {
+            tabs_to_spaces: false,
+            ..Config::new()
+        }
+    }
 
-    // TODO: write tests for all tab actions
+    #[test]
+    fn indents_with_tabs() {
+        let mut undo_redo = UndoRedo::new();
+        let lines = vec![vec!['a'], vec!['b'], vec!['c']];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 50);
+
+        ui_state.handle_tab_key(&tabs_config(), &mut undo_redo);
+        assert_eq!(ui_state.lines.line_to_string(0), "\ta");
+        assert_eq!(ui_state.cursor_column, 2);
+
+        ui_state.selection = select((2, 1), (3, 2));
+        ui_state.handle_tab_key(&tabs_config(), &mut undo_redo);
+        assert_eq!(ui_state.lines.line_to_string(1), "\tb");
+        assert_eq!(ui_state.lines.line_to_string(2), "\tc");
+    }
+
+    #[test]
+    fn unindents_tabs_and_spaces() {
+        let lines = vec![
+            vec!['\t', '\t', 'a'],
+            vec![' ', ' ', 'b'],
+            vec![' ', ' ', ' ', ' ', ' ', 'c'],
+        ];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 50);
+
+        ui_state.handle_backtab_key(&tabs_config());
+        assert_eq!(ui_state.lines.line_to_string(0), "\ta");
+
+        ui_state.selection = select((2, 1), (3, 2));
+        ui_state.handle_backtab_key(&Config::new());
+        assert_eq!(ui_state.lines.line_to_string(1), "b");
+        assert_eq!(ui_state.lines.line_to_string(2), " c");
+    }
 }
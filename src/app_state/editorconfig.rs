@@ -0,0 +1,359 @@
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use super::app::ConfigOverride;
+use super::config_file::MAX_WHITESPACES_AMOUNT;
+use super::file_format::LineEnding;
+
+const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// Settings for the file from all `.editorconfig` files, starting from its
+/// directory and going up until a file with `root = true`.
+/// See https://spec.editorconfig.org for the format.
+pub fn resolve_editorconfig(file_path: &Path) -> ConfigOverride {
+    let mut editorconfigs = vec![];
+
+    for directory in file_path.ancestors().skip(1) {
+        let Ok(content) = fs::read_to_string(directory.join(EDITORCONFIG_FILE_NAME)) else {
+            continue;
+        };
+
+        let editorconfig = parse_editorconfig(&content);
+        let is_root = editorconfig.is_root;
+        editorconfigs.push((directory, editorconfig));
+
+        if is_root {
+            break;
+        }
+    }
+
+    // files closer to the opened file take precedence, so they are applied last
+    let mut config_override = ConfigOverride::default();
+
+    for (directory, editorconfig) in editorconfigs.iter().rev() {
+        let Ok(relative_path) = file_path.strip_prefix(directory) else {
+            continue;
+        };
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+
+        for section in &editorconfig.sections {
+            if section.matches(&relative_path) {
+                config_override.merge(&section.get_config_override());
+            }
+        }
+    }
+
+    config_override
+}
+
+struct Editorconfig {
+    is_root: bool,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    glob: Option<Glob>,
+    /// Lowercased key-value pairs, in the order they appear in the file
+    properties: Vec<(String, String)>,
+}
+
+impl Section {
+    fn matches(&self, relative_path: &str) -> bool {
+        self.glob
+            .as_ref()
+            .is_some_and(|glob| glob.matches(relative_path))
+    }
+
+    /// Unknown properties and invalid values are ignored, as the spec requires
+    fn get_config_override(&self) -> ConfigOverride {
+        let get_property = |name: &str| {
+            self.properties
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let get_bool = |name: &str| match get_property(name)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+
+        // sizes out of the range the config files allow are ignored too
+        let parse_size = |value: &str| {
+            value
+                .parse()
+                .ok()
+                .filter(|size| (1..=MAX_WHITESPACES_AMOUNT).contains(size))
+                .map(|size| size as usize)
+        };
+        let tab_width = get_property("tab_width").and_then(parse_size);
+        let indent_size = match get_property("indent_size") {
+            Some("tab") => tab_width,
+            Some(value) => parse_size(value),
+            None => tab_width,
+        };
+
+        ConfigOverride {
+            tabs_to_spaces: match get_property("indent_style") {
+                Some("space") => Some(true),
+                Some("tab") => Some(false),
+                _ => None,
+            },
+            whitespaces_amount: indent_size,
+            tab_width,
+            end_of_line: match get_property("end_of_line") {
+                Some("lf") => Some(LineEnding::Lf),
+                Some("crlf") => Some(LineEnding::Crlf),
+                _ => None,
+            },
+            insert_final_newline: get_bool("insert_final_newline"),
+            trim_trailing_whitespace: get_bool("trim_trailing_whitespace"),
+            ..ConfigOverride::default()
+        }
+    }
+}
+
+fn parse_editorconfig(content: &str) -> Editorconfig {
+    let mut editorconfig = Editorconfig {
+        is_root: false,
+        sections: vec![],
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(pattern) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            editorconfig.sections.push(Section {
+                glob: Glob::new(pattern),
+                properties: vec![],
+            });
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_lowercase();
+
+        match editorconfig.sections.last_mut() {
+            Some(section) => section.properties.push((key, value)),
+            // the preamble before the first section can only set `root`
+            None if key == "root" => editorconfig.is_root = value == "true",
+            None => {}
+        }
+    }
+
+    editorconfig
+}
+
+/// Section name pattern, converted into a regular expression. `{1..3}`
+/// ranges can't be expressed that way, so we capture the numbers and check
+/// them separately.
+struct Glob {
+    regex: Regex,
+    number_ranges: Vec<(i64, i64)>,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Option<Glob> {
+        // patterns without a slash match files in any directory
+        let pattern = match pattern.strip_prefix('/') {
+            Some(pattern) => pattern.to_string(),
+            None if pattern.contains('/') => pattern.to_string(),
+            None => format!("**/{pattern}"),
+        };
+
+        let characters: Vec<char> = pattern.chars().collect();
+        let mut regex = String::from("^");
+        let mut number_ranges = vec![];
+        let mut braces_depth = 0;
+        let mut index = 0;
+
+        while index < characters.len() {
+            let character = characters[index];
+
+            match character {
+                '\\' if index + 1 < characters.len() => {
+                    index += 1;
+                    regex.push_str(&regex::escape(&characters[index].to_string()));
+                }
+                '*' if characters.get(index + 1) == Some(&'*') => {
+                    index += 1;
+                    // `**/` can also match no directories at all
+                    if characters.get(index + 1) == Some(&'/') {
+                        index += 1;
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => {
+                    let closing = characters[index..].iter().position(|c| *c == ']');
+                    match closing {
+                        Some(closing) if closing > 1 => {
+                            let mut class: String =
+                                characters[index + 1..index + closing].iter().collect();
+                            if let Some(negated) = class.strip_prefix('!') {
+                                class = format!("^{negated}");
+                            }
+                            regex.push_str(&format!("[{}]", class.replace('\\', "\\\\")));
+                            index += closing;
+                        }
+                        _ => regex.push_str("\\["),
+                    }
+                }
+                '{' => {
+                    let closing = characters[index..].iter().position(|c| *c == '}');
+                    let content: Option<String> = closing
+                        .map(|closing| characters[index + 1..index + closing].iter().collect());
+
+                    if let Some(range) = content.as_deref().and_then(parse_number_range) {
+                        number_ranges.push(range);
+                        regex.push_str("([+-]?[0-9]+)");
+                        index += closing.unwrap_or_default();
+                    } else if content.is_some_and(|content| content.contains(',')) {
+                        braces_depth += 1;
+                        regex.push_str("(?:");
+                    } else {
+                        regex.push_str("\\{");
+                    }
+                }
+                ',' if braces_depth > 0 => regex.push('|'),
+                '}' if braces_depth > 0 => {
+                    braces_depth -= 1;
+                    regex.push(')');
+                }
+                character => regex.push_str(&regex::escape(&character.to_string())),
+            }
+
+            index += 1;
+        }
+
+        regex.push('$');
+
+        Some(Glob {
+            regex: Regex::new(&regex).ok()?,
+            number_ranges,
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let Some(captures) = self.regex.captures(path) else {
+            return false;
+        };
+
+        self.number_ranges
+            .iter()
+            .enumerate()
+            .all(|(index, (start, end))| {
+                captures
+                    .get(index + 1)
+                    .and_then(|number| number.as_str().parse::<i64>().ok())
+                    .is_some_and(|number| (*start..=*end).contains(&number))
+            })
+    }
+}
+
+fn parse_number_range(content: &str) -> Option<(i64, i64)> {
+    let (start, end) = content.split_once("..")?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::create_test_directory;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(matches("*", "main.rs"));
+        assert!(matches("*", "src/app/main.rs"));
+        assert!(matches("*.rs", "src/main.rs"));
+        assert!(!matches("*.rs", "src/main.rsx"));
+        assert!(matches("*.{yml,yaml}", "ci/build.yaml"));
+        assert!(matches("Makefile", "Makefile"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/app/main.rs"));
+        assert!(!matches("src/*.rs", "lib/src/main.rs"));
+        assert!(matches("src/**.rs", "src/app/main.rs"));
+        assert!(matches("/docs/**/*.md", "docs/README.md"));
+        assert!(matches("file[0-9].txt", "file5.txt"));
+        assert!(!matches("file[!0-9].txt", "file5.txt"));
+        assert!(matches("part{1..3}.txt", "part2.txt"));
+        assert!(!matches("part{1..3}.txt", "part4.txt"));
+    }
+
+    #[test]
+    fn resolves_settings_from_nested_files() {
+        let directory = create_test_directory("editorconfig");
+        fs::create_dir_all(directory.join("project").join("docs")).unwrap();
+
+        fs::write(
+            directory.join(EDITORCONFIG_FILE_NAME),
+            "[*]\nindent_size = 8\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("project").join(EDITORCONFIG_FILE_NAME),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\ntab_width = 8\nend_of_line = crlf\n\n[Makefile]\nindent_style = tab\ntab_width = 8\nindent_size = tab\n\n[*.md]\ntrim_trailing_whitespace = false\n",
+        )
+        .unwrap();
+        fs::write(
+            directory
+                .join("project")
+                .join("docs")
+                .join(EDITORCONFIG_FILE_NAME),
+            "[*.md]\nindent_size = 2\ninsert_final_newline = true\n\n[*.txt]\nindent_size = 100\n",
+        )
+        .unwrap();
+
+        let rust_config = resolve_editorconfig(&directory.join("project").join("main.rs"));
+        assert_eq!(
+            rust_config,
+            ConfigOverride {
+                tabs_to_spaces: Some(true),
+                whitespaces_amount: Some(4),
+                tab_width: Some(8),
+                end_of_line: Some(LineEnding::Crlf),
+                ..ConfigOverride::default()
+            }
+        );
+
+        let makefile_config = resolve_editorconfig(&directory.join("project").join("Makefile"));
+        assert_eq!(makefile_config.tabs_to_spaces, Some(false));
+        assert_eq!(makefile_config.whitespaces_amount, Some(8));
+
+        let docs_path = directory.join("project").join("docs").join("README.md");
+        let docs_config = resolve_editorconfig(&docs_path);
+        assert_eq!(docs_config.whitespaces_amount, Some(2));
+        assert_eq!(docs_config.tab_width, Some(8));
+        assert_eq!(docs_config.insert_final_newline, Some(true));
+        assert_eq!(docs_config.trim_trailing_whitespace, Some(false));
+
+        // too large, so the size from the parent directory stays
+        let notes_path = directory.join("project").join("docs").join("notes.txt");
+        assert_eq!(
+            resolve_editorconfig(&notes_path).whitespaces_amount,
+            Some(4)
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
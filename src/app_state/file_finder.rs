@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+
+use super::app::AppState;
+use super::fuzzy::fuzzy_score;
+
+/// Nobody reads further than this, and sorting fewer paths is faster
+const MAX_RESULTS: usize = 100;
+
+/// Popup for opening files by typing a part of their path
+pub struct FileFinder {
+    pub input: Vec<char>,
+    pub cursor: usize,
+    /// All files of the project, relative to the working directory. They are
+    /// read once when the popup is opened.
+    files: Vec<PathBuf>,
+    /// Indexes into `files`, the best match is the first one
+    pub results: Vec<usize>,
+    /// Index in `results`
+    pub selected: usize,
+    pub scroll_offset: usize,
+}
+
+impl FileFinder {
+    fn new(files: Vec<PathBuf>) -> Self {
+        let mut file_finder = FileFinder {
+            input: vec![],
+            cursor: 0,
+            files,
+            results: vec![],
+            selected: 0,
+            scroll_offset: 0,
+        };
+        file_finder.update_results();
+
+        file_finder
+    }
+
+    pub fn get_path(&self, result_index: usize) -> Option<&Path> {
+        let file_index = self.results.get(result_index)?;
+        Some(self.files[*file_index].as_path())
+    }
+
+    pub fn get_files_number(&self) -> usize {
+        self.files.len()
+    }
+
+    fn update_results(&mut self) {
+        let pattern = String::from_iter(&self.input);
+        // paths are matched with `/` on every platform
+        let mut scored: Vec<(i64, usize)> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(index, path)| {
+                let path = path.to_string_lossy().replace('\\', "/");
+                Some((fuzzy_score(&pattern, &path)?, index))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.truncate(MAX_RESULTS);
+
+        self.results = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = 0;
+        self.scroll_offset = 0;
+    }
+
+    pub fn handle_scrolling(&mut self, height: usize) {
+        if height == 0 {
+            return;
+        }
+
+        if self.selected < self.scroll_offset {
+            self.scroll_offset = self.selected;
+        } else if self.selected >= self.scroll_offset + height {
+            self.scroll_offset = self.selected + 1 - height;
+        }
+    }
+}
+
+/// Every file under the directory, except for gitignored ones and hidden
+/// ones like `.git`. `target` is skipped even without a `.gitignore`.
+fn read_project_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkBuilder::new(root)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != "target" && entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect();
+
+    // the walk order depends on the file system, so we sort to make equal
+    // scores come in the same order every time
+    files.sort();
+
+    files
+}
+
+impl AppState {
+    pub fn open_file_finder(&mut self) {
+        let files = read_project_files(&self.working_directory);
+        self.file_finder = Some(FileFinder::new(files));
+    }
+
+    pub fn close_file_finder(&mut self) {
+        self.file_finder = None;
+    }
+
+    pub fn file_finder_insert_char(&mut self, character: char) {
+        if let Some(file_finder) = &mut self.file_finder {
+            file_finder.input.insert(file_finder.cursor, character);
+            file_finder.cursor += 1;
+            file_finder.update_results();
+        }
+    }
+
+    pub fn file_finder_remove_previous_char(&mut self) {
+        if let Some(file_finder) = &mut self.file_finder
+            && file_finder.cursor > 0
+        {
+            file_finder.cursor -= 1;
+            file_finder.input.remove(file_finder.cursor);
+            file_finder.update_results();
+        }
+    }
+
+    pub fn file_finder_move_left(&mut self) {
+        if let Some(file_finder) = &mut self.file_finder {
+            file_finder.cursor = file_finder.cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn file_finder_move_right(&mut self) {
+        if let Some(file_finder) = &mut self.file_finder {
+            file_finder.cursor = (file_finder.cursor + 1).min(file_finder.input.len());
+        }
+    }
+
+    pub fn file_finder_move_up(&mut self) {
+        if let Some(file_finder) = &mut self.file_finder {
+            file_finder.selected = file_finder.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn file_finder_move_down(&mut self) {
+        if let Some(file_finder) = &mut self.file_finder
+            && file_finder.selected + 1 < file_finder.results.len()
+        {
+            file_finder.selected += 1;
+        }
+    }
+
+    /// Open the selected file and close the popup
+    pub fn submit_file_finder(&mut self) {
+        let Some(file_finder) = self.file_finder.take() else {
+            return;
+        };
+        let Some(path) = file_finder.get_path(file_finder.selected) else {
+            return;
+        };
+
+        if let Err(error) = self.open_file(self.working_directory.join(path)) {
+            self.status_message = Some(format!("Could not open the file: {error}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::create_test_directory;
+    use std::fs;
+
+    #[test]
+    fn finds_project_files() {
+        let directory = create_test_directory("file-finder");
+        for path in ["src/app_state", "target/debug", ".git", "node_modules"] {
+            fs::create_dir_all(directory.join(path)).unwrap();
+        }
+        for path in [
+            "src/app_state/buffer.rs",
+            "src/app_state/search.rs",
+            "src/tabs.rs",
+            "target/debug/build.rs",
+            ".git/config",
+            "node_modules/index.js",
+            ".gitignore",
+        ] {
+            fs::write(directory.join(path), "node_modules\n").unwrap();
+        }
+
+        let mut app_state = AppState::new(directory.clone());
+        app_state.open_file_finder();
+
+        let file_finder = app_state.file_finder.as_ref().unwrap();
+        assert_eq!(file_finder.get_files_number(), 3);
+
+        for character in "ab".chars() {
+            app_state.file_finder_insert_char(character);
+        }
+        let file_finder = app_state.file_finder.as_ref().unwrap();
+        assert_eq!(
+            file_finder.get_path(0),
+            Some(Path::new("src/app_state/buffer.rs"))
+        );
+
+        app_state.file_finder_move_down();
+        app_state.file_finder_move_down();
+        assert_eq!(app_state.file_finder.as_ref().unwrap().selected, 1);
+
+        app_state.file_finder_move_up();
+        app_state.submit_file_finder();
+        assert!(app_state.file_finder.is_none());
+        assert_eq!(
+            app_state.get_active_buffer().unwrap().file_path,
+            directory.join("src/app_state/buffer.rs")
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,290 @@
+use super::editor::UIState;
+use super::text_buffer::TextBuffer;
+
+pub(super) const UTF8_BOM: char = '\u{FEFF}';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+}
+
+/// Everything we need to remember about the file on disk in order to
+/// write it back exactly the same way. Lines themselves never contain
+/// any line endings, so this information would be lost otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileFormat {
+    pub line_ending: LineEnding,
+    pub has_final_newline: bool,
+    pub has_bom: bool,
+}
+
+impl FileFormat {
+    pub fn new() -> Self {
+        FileFormat {
+            line_ending: LineEnding::Lf,
+            has_final_newline: true,
+            has_bom: false,
+        }
+    }
+
+    /// Normalize file content into text with `\n` line endings only, remembering
+    /// line endings style, whether the file ended with a newline and whether
+    /// it had a BOM.
+    ///
+    /// If the file mixes line endings, the most common one is used for the
+    /// status bar and conversions, and `parse_line_endings` keeps the rest.
+    pub fn parse(content: &str) -> (FileFormat, String) {
+        let (has_bom, content) = match content.strip_prefix(UTF8_BOM) {
+            Some(stripped) => (true, stripped),
+            None => (false, content),
+        };
+
+        let crlf_count = content.matches("\r\n").count();
+        let lf_count = content.matches('\n').count() - crlf_count;
+
+        let line_ending = if crlf_count > lf_count {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+
+        let mut text = content.replace("\r\n", "\n");
+
+        let has_final_newline = text.ends_with('\n');
+        if has_final_newline {
+            text.pop();
+        }
+
+        let file_format = FileFormat {
+            line_ending,
+            has_final_newline,
+            has_bom,
+        };
+
+        (file_format, text)
+    }
+
+    /// Line ending of every line of the parsed text, but only if the file
+    /// mixes them; saving such a file must not rewrite the other lines.
+    /// The last line without a final newline gets the most common one.
+    pub fn parse_line_endings(&self, content: &str) -> Option<Vec<LineEnding>> {
+        let content = content.strip_prefix(UTF8_BOM).unwrap_or(content);
+
+        let mut line_endings: Vec<LineEnding> = content
+            .split('\n')
+            .map(|line| match line.ends_with('\r') {
+                true => LineEnding::Crlf,
+                false => LineEnding::Lf,
+            })
+            .collect();
+        // the part after the last `\n` has no line ending of its own
+        line_endings.pop();
+
+        let is_mixed =
+            line_endings.contains(&LineEnding::Lf) && line_endings.contains(&LineEnding::Crlf);
+        if !is_mixed {
+            return None;
+        }
+
+        if !self.has_final_newline {
+            line_endings.push(self.line_ending);
+        }
+        Some(line_endings)
+    }
+
+    /// Join lines back using the stored line endings
+    pub fn serialize(&self, text_buffer: &dyn TextBuffer) -> String {
+        let text = text_buffer.to_text();
+        let mut content = String::with_capacity(text.len() + 4);
+
+        if self.has_bom {
+            content.push(UTF8_BOM);
+        }
+
+        let last_line_ending = match text_buffer.get_line_endings() {
+            Some(line_endings) => {
+                for (index, line) in text.split('\n').enumerate() {
+                    if index > 0 {
+                        content.push_str(line_endings[index - 1].as_str());
+                    }
+                    content.push_str(line);
+                }
+                line_endings.last().copied().unwrap_or(self.line_ending)
+            }
+            None => {
+                match self.line_ending {
+                    LineEnding::Lf => content.push_str(&text),
+                    LineEnding::Crlf => content.push_str(&text.replace('\n', "\r\n")),
+                }
+                self.line_ending
+            }
+        };
+
+        if self.has_final_newline {
+            content.push_str(last_line_ending.as_str());
+        }
+
+        content
+    }
+
+    /// Short description to show in the status bar, e.g. "CRLF | UTF-8 BOM | no EOL"
+    pub fn describe(&self, text_buffer: &dyn TextBuffer) -> String {
+        let mut parts = vec![match text_buffer.get_line_endings() {
+            Some(_) => "Mixed",
+            None => self.line_ending.name(),
+        }];
+
+        if self.has_bom {
+            parts.push("UTF-8 BOM");
+        } else {
+            parts.push("UTF-8");
+        }
+
+        if !self.has_final_newline {
+            parts.push("no EOL");
+        }
+
+        parts.join(" | ")
+    }
+}
+
+impl UIState {
+    /// Convert all line endings of the file; lines are stored without them,
+    /// so we only need to change how the file is written back.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        let is_mixed = self.lines.get_line_endings().is_some();
+        if self.file_format.line_ending != line_ending || is_mixed {
+            self.file_format.line_ending = line_ending;
+            self.lines.set_line_endings(None);
+            self.is_dirty = true;
+        }
+    }
+
+    pub fn toggle_line_ending(&mut self) {
+        match self.file_format.line_ending {
+            LineEnding::Lf => self.set_line_ending(LineEnding::Crlf),
+            LineEnding::Crlf => self.set_line_ending(LineEnding::Lf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::text_buffer::RopeBuffer;
+
+    #[test]
+    fn detects_lf_line_endings() {
+        let (file_format, text) = FileFormat::parse("Hello\n\nworld\n");
+
+        assert_eq!(file_format.line_ending, LineEnding::Lf);
+        assert!(file_format.has_final_newline);
+        assert!(!file_format.has_bom);
+        assert_eq!(text, "Hello\n\nworld");
+    }
+
+    #[test]
+    fn detects_crlf_and_missing_final_newline() {
+        let (file_format, text) = FileFormat::parse("Hello\r\nworld");
+
+        assert_eq!(file_format.line_ending, LineEnding::Crlf);
+        assert!(!file_format.has_final_newline);
+        assert_eq!(text, "Hello\nworld");
+    }
+
+    #[test]
+    fn detects_bom() {
+        let (file_format, text) = FileFormat::parse("\u{FEFF}Hello\n");
+
+        assert!(file_format.has_bom);
+        assert_eq!(text, "Hello");
+    }
+
+    #[test]
+    fn empty_file_has_a_single_line() {
+        let (file_format, text) = FileFormat::parse("");
+
+        assert!(!file_format.has_final_newline);
+        assert_eq!(RopeBuffer::from_text(&text).len_lines(), 1);
+    }
+
+    #[test]
+    fn converts_line_endings() {
+        let (file_format, text) = FileFormat::parse("Hello\nworld\n");
+        let mut ui_state = UIState::with_text_buffer(5, Box::new(RopeBuffer::from_text(&text)));
+        ui_state.file_format = file_format;
+
+        ui_state.set_line_ending(LineEnding::Crlf);
+
+        assert!(ui_state.is_dirty);
+        assert_eq!(ui_state.get_file_content(), "Hello\r\nworld\r\n");
+
+        ui_state.toggle_line_ending();
+
+        assert_eq!(ui_state.get_file_content(), "Hello\nworld\n");
+    }
+
+    #[test]
+    fn keeps_mixed_line_endings_of_every_line() {
+        let content = "one\r\ntwo\nthree\r\n";
+        let (file_format, text) = FileFormat::parse(content);
+        let mut text_buffer = RopeBuffer::from_text(&text);
+        text_buffer.set_line_endings(file_format.parse_line_endings(content));
+        assert_eq!(file_format.serialize(&text_buffer), content);
+
+        text_buffer.insert(1, 3, "\nnew");
+        text_buffer.remove((0, 3), (1, 0));
+        assert_eq!(
+            file_format.serialize(&text_buffer),
+            "onetwo\nnew\nthree\r\n"
+        );
+
+        let mut ui_state = UIState::with_text_buffer(5, Box::new(text_buffer));
+        ui_state.file_format = file_format;
+        assert_eq!(
+            ui_state.file_format.describe(ui_state.lines.as_ref()),
+            "Mixed | UTF-8"
+        );
+
+        ui_state.set_line_ending(LineEnding::Crlf);
+        assert_eq!(ui_state.get_file_content(), "onetwo\r\nnew\r\nthree\r\n");
+    }
+
+    #[test]
+    fn round_trips_content() {
+        let files = [
+            "Hello\nworld\n",
+            "Hello\r\nworld\r\n",
+            "Hello\r\n\r\nworld",
+            "\u{FEFF}Hello\nworld",
+            "",
+            "\n",
+            "Hello\r\nworld\nagain",
+            "\u{FEFF}a\nb\r\n",
+        ];
+
+        for content in files {
+            let (file_format, text) = FileFormat::parse(content);
+            let mut text_buffer = RopeBuffer::from_text(&text);
+            text_buffer.set_line_endings(file_format.parse_line_endings(content));
+            assert_eq!(file_format.serialize(&text_buffer), content);
+        }
+    }
+}
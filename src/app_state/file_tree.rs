@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+
+use super::app::AppState;
+use super::editor::FileTreeEntry;
+
+/// A single row of the file tree as it is shown on the screen
+pub struct VisibleFileTreeEntry {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+}
+
+impl AppState {
+    /// Flatten the file tree into rows, going into expanded directories only
+    pub fn get_visible_file_tree(&self) -> Vec<VisibleFileTreeEntry> {
+        let mut result = vec![];
+        self.collect_visible_entries(&self.working_directory, 0, &mut result);
+        result
+    }
+
+    fn collect_visible_entries(
+        &self,
+        directory: &Path,
+        depth: usize,
+        result: &mut Vec<VisibleFileTreeEntry>,
+    ) {
+        let Some(entries) = self.file_tree.get(directory) else {
+            return;
+        };
+
+        for entry in entries {
+            match entry {
+                FileTreeEntry::File(file) => result.push(VisibleFileTreeEntry {
+                    path: file.path.clone(),
+                    depth,
+                    is_dir: false,
+                    expanded: false,
+                }),
+                FileTreeEntry::Dir(dir) => {
+                    result.push(VisibleFileTreeEntry {
+                        path: dir.path.clone(),
+                        depth,
+                        is_dir: true,
+                        expanded: dir.expanded,
+                    });
+
+                    if dir.expanded {
+                        self.collect_visible_entries(&dir.path, depth + 1, result);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn file_tree_move_up(&mut self) {
+        if self.file_tree_selected > 0 {
+            self.file_tree_selected -= 1;
+        }
+    }
+
+    pub fn file_tree_move_down(&mut self) {
+        let entries_num = self.get_visible_file_tree().len();
+        if self.file_tree_selected + 1 < entries_num {
+            self.file_tree_selected += 1;
+        }
+    }
+
+    /// Expand the selected directory; does nothing for files
+    pub fn file_tree_expand(&mut self) {
+        if let Some(entry) = self.get_selected_file_tree_entry()
+            && entry.is_dir
+            && !entry.expanded
+        {
+            self.set_directory_expanded(&entry.path, true);
+        }
+    }
+
+    /// Collapse the selected directory. If it is a file or already collapsed,
+    /// we move the selection to the parent directory instead.
+    pub fn file_tree_collapse(&mut self) {
+        let Some(entry) = self.get_selected_file_tree_entry() else {
+            return;
+        };
+
+        if entry.is_dir && entry.expanded {
+            self.set_directory_expanded(&entry.path, false);
+        } else if entry.depth > 0
+            && let Some(parent) = entry.path.parent()
+        {
+            let entries = self.get_visible_file_tree();
+            if let Some(index) = entries.iter().position(|entry| entry.path == parent) {
+                self.file_tree_selected = index;
+            }
+        }
+    }
+
+    /// Toggle the selected directory or open the selected file in the editor
+    pub fn file_tree_open_selected(&mut self) {
+        let Some(entry) = self.get_selected_file_tree_entry() else {
+            return;
+        };
+
+        if entry.is_dir {
+            self.set_directory_expanded(&entry.path, !entry.expanded);
+            return;
+        }
+
+        if let Err(error) = self.open_file(entry.path) {
+            self.status_message = Some(format!("Could not open the file: {error}"));
+        }
+    }
+
+    /// Keep the selected row inside the visible area of the given height
+    pub fn handle_file_tree_scrolling(&mut self, height: usize) {
+        if height == 0 {
+            return;
+        }
+
+        if self.file_tree_selected < self.file_tree_scroll_offset {
+            self.file_tree_scroll_offset = self.file_tree_selected;
+        } else if self.file_tree_selected >= self.file_tree_scroll_offset + height {
+            self.file_tree_scroll_offset = self.file_tree_selected + 1 - height;
+        }
+    }
+
+    fn get_selected_file_tree_entry(&self) -> Option<VisibleFileTreeEntry> {
+        self.get_visible_file_tree()
+            .into_iter()
+            .nth(self.file_tree_selected)
+    }
+
+    fn set_directory_expanded(&mut self, path: &Path, expanded: bool) {
+        // children are read only once, when the directory is expanded the first time
+        if expanded && !self.file_tree.contains_key(path) {
+            self.read_directory(path.to_path_buf());
+        }
+
+        let Some(parent) = path.parent() else {
+            return;
+        };
+
+        if let Some(entries) = self.file_tree.get_mut(parent) {
+            for entry in entries {
+                if let FileTreeEntry::Dir(dir) = entry
+                    && dir.path == path
+                {
+                    dir.expanded = expanded;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::app::Focus;
+    use crate::app_state::test_utils;
+    use std::fs;
+
+    fn create_test_directory(name: &str) -> PathBuf {
+        let directory = test_utils::create_test_directory(name);
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::write(directory.join("README.md"), "Readme\n").unwrap();
+        fs::write(directory.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(directory.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+
+        directory
+    }
+
+    fn get_names(app_state: &AppState) -> Vec<String> {
+        app_state
+            .get_visible_file_tree()
+            .iter()
+            .map(|entry| {
+                let name = entry.path.file_name().unwrap().to_string_lossy();
+                format!("{}{name}", "  ".repeat(entry.depth))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sorts_directories_first() {
+        let directory = create_test_directory("tree-sort");
+        let mut app_state = AppState::new(directory.clone());
+        app_state.read_directory(directory.clone());
+
+        assert_eq!(
+            get_names(&app_state),
+            vec!["src", "Cargo.toml", "README.md"]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn expands_and_collapses_directories() {
+        let directory = create_test_directory("tree-expand");
+        let mut app_state = AppState::new(directory.clone());
+        app_state.read_directory(directory.clone());
+
+        app_state.file_tree_expand();
+
+        assert_eq!(
+            get_names(&app_state),
+            vec!["src", "  main.rs", "Cargo.toml", "README.md"]
+        );
+
+        app_state.file_tree_move_down();
+        // collapsing a file selects its parent
+        app_state.file_tree_collapse();
+
+        assert_eq!(app_state.file_tree_selected, 0);
+
+        app_state.file_tree_collapse();
+
+        assert_eq!(
+            get_names(&app_state),
+            vec!["src", "Cargo.toml", "README.md"]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn opens_selected_file() {
+        let directory = create_test_directory("tree-open");
+        let mut app_state = AppState::new(directory.clone());
+        app_state.read_directory(directory.clone());
+
+        app_state.file_tree_open_selected();
+        app_state.file_tree_move_down();
+        app_state.file_tree_open_selected();
+
+        let buffer = app_state.get_active_buffer().unwrap();
+        assert_eq!(buffer.file_path, directory.join("src").join("main.rs"));
+        assert!(app_state.focus == Focus::Editor);
+        assert_eq!(buffer.ui_state.lines.line_to_string(0), "fn main() {}");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
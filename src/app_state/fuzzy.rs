@@ -0,0 +1,127 @@
+/// Check if all characters of the pattern appear in the candidate in the same
+/// order (case-insensitive) and rate the match; `None` means no match.
+///
+/// Higher is better: consecutive characters and characters at the start of
+/// words are rewarded, and shorter candidates win ties. Word starts include
+/// path segments (after `/`) and camelCase humps, so `fb` finds `foo/bar.rs`
+/// and `FooBar.rs`. The best placement of the characters is picked, not the
+/// first one, e.g. `bar` in `b/a/r/bar.rs` matches the file name.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+
+    let bonuses: Vec<i64> = (0..candidate.len())
+        .map(|index| get_boundary_bonus(&candidate, index))
+        .collect();
+
+    // best score for the pattern characters matched so far, where the last
+    // one is at the given index of the candidate
+    let mut previous_scores: Vec<Option<i64>> = vec![Some(0); candidate.len()];
+    let mut is_first_char = true;
+
+    for pattern_char in pattern {
+        let mut scores = vec![None; candidate.len()];
+        // the best score among matches ending before the current index
+        let mut best_before: Option<i64> = None;
+
+        for index in 0..candidate.len() {
+            let previous = if is_first_char {
+                Some(0)
+            } else {
+                let consecutive = index
+                    .checked_sub(1)
+                    .and_then(|previous_index| previous_scores[previous_index])
+                    .map(|score| score + 5);
+                best_before.max(consecutive)
+            };
+
+            if let Some(previous) = previous
+                && candidate[index]
+                    .to_lowercase()
+                    .eq(std::iter::once(pattern_char))
+            {
+                scores[index] = Some(previous + 1 + bonuses[index]);
+            }
+
+            if !is_first_char {
+                best_before = best_before.max(previous_scores[index]);
+            }
+        }
+
+        previous_scores = scores;
+        is_first_char = false;
+    }
+
+    let score = if is_first_char {
+        // empty pattern matches everything
+        0
+    } else {
+        previous_scores.into_iter().flatten().max()?
+    };
+
+    Some(score * 100 - candidate.len() as i64)
+}
+
+fn get_boundary_bonus(candidate: &[char], index: usize) -> i64 {
+    let Some(previous) = index.checked_sub(1).map(|index| candidate[index]) else {
+        return 3;
+    };
+
+    if previous == '/' || previous == '\\' {
+        5
+    } else if !previous.is_alphanumeric()
+        || previous.is_lowercase() && candidate[index].is_uppercase()
+    {
+        3
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert!(fuzzy_score("gl", "goto-line").is_some());
+        assert!(fuzzy_score("GL", "goto-line").is_some());
+        assert!(fuzzy_score("", "save").is_some());
+        assert!(fuzzy_score("lg", "goto-line").is_none());
+        assert!(fuzzy_score("saves", "save").is_none());
+    }
+
+    #[test]
+    fn prefers_consecutive_and_word_start_matches() {
+        let prefix = fuzzy_score("sa", "save").unwrap();
+        let scattered = fuzzy_score("sa", "set-tab").unwrap();
+        assert!(prefix > scattered);
+
+        let word_start = fuzzy_score("nb", "next-buffer").unwrap();
+        let middle = fuzzy_score("nb", "unbind").unwrap();
+        assert!(word_start > middle);
+
+        let short = fuzzy_score("open", "open").unwrap();
+        let long = fuzzy_score("open", "open-recent").unwrap();
+        assert!(short > long);
+    }
+
+    #[test]
+    fn prefers_path_segments_and_camel_case() {
+        let segments = fuzzy_score("ab", "src/app/buffer.rs").unwrap();
+        let middle = fuzzy_score("ab", "src/tabs.rs").unwrap();
+        assert!(segments > middle);
+
+        let camel_case = fuzzy_score("fb", "src/FooBar.ts").unwrap();
+        let lowercase = fuzzy_score("fb", "src/foobar.ts").unwrap();
+        assert!(camel_case > lowercase);
+
+        // the file name wins over scattered characters in directories
+        let file_name = fuzzy_score("main", "src/app/main.rs").unwrap();
+        let scattered = fuzzy_score("main", "src/mod/app/info/nav.rs").unwrap();
+        assert!(file_name > scattered);
+        assert!(
+            fuzzy_score("bar", "b/a/r/bar.rs").unwrap() > fuzzy_score("bar", "b/a/r/x.rs").unwrap()
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ratatui::style::Color;
+use tree_sitter::{
+    InputEdit, Language, Node, ParseOptions, Parser, Point, Query, QueryCursor, StreamingIterator,
+    Tree,
+};
+
+use super::app::Theme;
+use super::language::detect_language;
+use super::text_buffer::{TextBuffer, TextEdit};
+
+/// Colored part of a single line, columns are in bytes
+pub type HighlightSpan = (Range<usize>, Color);
+
+/// Larger files are shown as plain text, parsing them would take too long
+const MAX_TEXT_SIZE: usize = 5 * 1024 * 1024;
+/// Parsing runs before every render, so it must not freeze the editor
+const PARSE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Syntax tree of a single buffer. The tree is updated incrementally:
+/// the text buffer records every edit, and before rendering we replay
+/// them on the old tree, so tree-sitter reparses only changed parts.
+pub struct Highlighter {
+    parser: Parser,
+    query: Query,
+    tree: Option<Tree>,
+    parse_timeout: Duration,
+    /// Set when parsing took too long; the file is shown as plain text then
+    is_disabled: bool,
+}
+
+impl Highlighter {
+    /// Pick the grammar by the file language; `None` if we don't support it,
+    /// or if the text (its size is in bytes) is too large
+    pub fn for_path(path: &Path, text_size: usize) -> Option<Highlighter> {
+        if text_size > MAX_TEXT_SIZE {
+            return None;
+        }
+
+        let (language, highlights_query): (Language, &str) = match detect_language(path)? {
+            "rust" => (
+                tree_sitter_rust::LANGUAGE.into(),
+                tree_sitter_rust::HIGHLIGHTS_QUERY,
+            ),
+            "toml" => (
+                tree_sitter_toml_ng::LANGUAGE.into(),
+                tree_sitter_toml_ng::HIGHLIGHTS_QUERY,
+            ),
+            "json" => (
+                tree_sitter_json::LANGUAGE.into(),
+                tree_sitter_json::HIGHLIGHTS_QUERY,
+            ),
+            "markdown" => (
+                tree_sitter_md::LANGUAGE.into(),
+                tree_sitter_md::HIGHLIGHT_QUERY_BLOCK,
+            ),
+            _ => return None,
+        };
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        let query = Query::new(&language, highlights_query).ok()?;
+
+        Some(Highlighter {
+            parser,
+            query,
+            tree: None,
+            parse_timeout: PARSE_TIMEOUT,
+            is_disabled: false,
+        })
+    }
+
+    /// Apply all edits made since the last update and reparse the text;
+    /// if it takes too long, highlighting is turned off for good
+    pub fn update(&mut self, text_buffer: &mut dyn TextBuffer) {
+        let edits = text_buffer.take_edits();
+        if self.is_disabled {
+            return;
+        }
+
+        if let Some(tree) = &mut self.tree {
+            if edits.is_empty() {
+                return;
+            }
+
+            for edit in edits {
+                tree.edit(&get_input_edit(edit));
+            }
+        }
+
+        let text_buffer: &dyn TextBuffer = text_buffer;
+        let start = Instant::now();
+        let mut stop_on_timeout = |_: &_| {
+            if start.elapsed() > self.parse_timeout {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+        self.tree = self.parser.parse_with_options(
+            &mut |byte, _| text_buffer.chunk_at_byte(byte),
+            self.tree.as_ref(),
+            Some(ParseOptions::new().progress_callback(&mut stop_on_timeout)),
+        );
+
+        if self.tree.is_none() {
+            self.parser.reset();
+            self.is_disabled = true;
+        }
+    }
+
+    /// Colored spans for every line in the range (lines start from `0`)
+    pub fn highlight_lines(
+        &self,
+        text_buffer: &dyn TextBuffer,
+        lines: Range<usize>,
+        theme: &Theme,
+    ) -> Vec<Vec<HighlightSpan>> {
+        let mut result = vec![vec![]; lines.len()];
+
+        let Some(tree) = &self.tree else {
+            return result;
+        };
+
+        let mut query_cursor = QueryCursor::new();
+        query_cursor.set_byte_range(
+            text_buffer.line_to_byte(lines.start)..text_buffer.line_to_byte(lines.end),
+        );
+
+        let text_provider = |node: Node| {
+            std::iter::once(text_buffer.get_text_by_bytes(node.start_byte(), node.end_byte()))
+        };
+        let mut captures = query_cursor.captures(&self.query, tree.root_node(), text_provider);
+
+        // the same node can be matched by several patterns, and the first one wins;
+        // nested nodes come after their parents, so they are painted on top
+        let mut last_node_range = None;
+
+        while let Some((query_match, capture_index)) = captures.next() {
+            let capture = query_match.captures()[*capture_index];
+            let node_range = capture.node.byte_range();
+
+            if last_node_range.as_ref() == Some(&node_range) {
+                continue;
+            }
+            last_node_range = Some(node_range);
+
+            let capture_name = self.query.capture_names()[capture.index as usize];
+            let Some(color) = theme.get_syntax_color(capture_name) else {
+                continue;
+            };
+
+            let start = capture.node.start_position();
+            let end = capture.node.end_position();
+
+            for line in start.row.max(lines.start)..(end.row + 1).min(lines.end) {
+                let start_column = if line == start.row { start.column } else { 0 };
+                let end_column = if line == end.row {
+                    end.column
+                } else {
+                    usize::MAX
+                };
+
+                result[line - lines.start].push((start_column..end_column, color));
+            }
+        }
+
+        result
+    }
+
+    /// Whether the character at the position (from `0`, the column is in bytes)
+    /// is code, and not a part of a string or a comment
+    pub fn is_code_at(&self, line: usize, byte_column: usize) -> bool {
+        let Some(tree) = &self.tree else {
+            return true;
+        };
+
+        let start = Point {
+            row: line,
+            column: byte_column,
+        };
+        let end = Point {
+            row: line,
+            column: byte_column + 1,
+        };
+        let mut node = tree.root_node().descendant_for_point_range(start, end);
+        while let Some(current) = node {
+            let kind = current.kind();
+            if kind.contains("string") || kind.contains("comment") || kind == "char_literal" {
+                return false;
+            }
+            node = current.parent();
+        }
+
+        true
+    }
+}
+
+fn get_input_edit(edit: TextEdit) -> InputEdit {
+    let get_point = |(row, column)| Point { row, column };
+
+    InputEdit {
+        start_byte: edit.start_byte,
+        old_end_byte: edit.old_end_byte,
+        new_end_byte: edit.new_end_byte,
+        start_position: get_point(edit.start_position),
+        old_end_position: get_point(edit.old_end_position),
+        new_end_position: get_point(edit.new_end_position),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::text_buffer::RopeBuffer;
+
+    fn get_colored_text(
+        text_buffer: &dyn TextBuffer,
+        highlighter: &Highlighter,
+        theme: &Theme,
+        color: Color,
+    ) -> Vec<String> {
+        let lines = 0..text_buffer.len_lines();
+        highlighter
+            .highlight_lines(text_buffer, lines.clone(), theme)
+            .into_iter()
+            .zip(lines)
+            .flat_map(|(spans, line)| {
+                let line_text = text_buffer.line_to_string(line);
+                spans
+                    .into_iter()
+                    .filter(|(_, span_color)| *span_color == color)
+                    .map(|(range, _)| {
+                        let end = range.end.min(line_text.len());
+                        line_text[range.start..end].to_string()
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn picks_grammar_by_extension() {
+        assert!(Highlighter::for_path(Path::new("src/main.rs"), 0).is_some());
+        assert!(Highlighter::for_path(Path::new("Cargo.toml"), 0).is_some());
+        assert!(Highlighter::for_path(Path::new("package.json"), 0).is_some());
+        assert!(Highlighter::for_path(Path::new("README.md"), 0).is_some());
+        assert!(Highlighter::for_path(Path::new("notes.txt"), 0).is_none());
+        assert!(Highlighter::for_path(Path::new("Makefile"), 0).is_none());
+    }
+
+    #[test]
+    fn gives_up_on_large_files() {
+        let path = Path::new("data.json");
+        assert!(Highlighter::for_path(path, MAX_TEXT_SIZE + 1).is_none());
+
+        let mut text_buffer = RopeBuffer::from_text(&"[1, \"two\"],\n".repeat(100_000));
+        let mut highlighter = Highlighter::for_path(path, 0).unwrap();
+        highlighter.parse_timeout = Duration::ZERO;
+        highlighter.update(&mut text_buffer);
+
+        // the text is shown as plain text, where everything is code
+        assert!(highlighter.tree.is_none());
+        assert!(highlighter.is_code_at(0, 5));
+        highlighter.update(&mut text_buffer);
+        assert!(highlighter.tree.is_none());
+    }
+
+    #[test]
+    fn highlights_and_updates_after_edits() {
+        let theme = Theme::new();
+        let keyword_color = theme.get_syntax_color("keyword").unwrap();
+
+        let mut text_buffer = RopeBuffer::from_text("fn main() {\n    let x = 1;\n}");
+        text_buffer.track_edits();
+        let mut highlighter = Highlighter::for_path(Path::new("main.rs"), 0).unwrap();
+        highlighter.update(&mut text_buffer);
+
+        assert_eq!(
+            get_colored_text(&text_buffer, &highlighter, &theme, keyword_color),
+            vec!["fn", "let"]
+        );
+
+        text_buffer.insert(1, 4, "return;\n    ");
+        text_buffer.remove((0, 0), (0, 3));
+        text_buffer.insert(0, 0, "pub fn ");
+        highlighter.update(&mut text_buffer);
+
+        assert_eq!(
+            get_colored_text(&text_buffer, &highlighter, &theme, keyword_color),
+            vec!["pub", "fn", "return", "let"]
+        );
+    }
+
+    #[test]
+    fn tells_code_from_strings_and_comments() {
+        let mut text_buffer = RopeBuffer::from_text("call(\")\", ')');\n// (\n/* [ */ x[0]");
+        let mut highlighter = Highlighter::for_path(Path::new("main.rs"), 0).unwrap();
+        highlighter.update(&mut text_buffer);
+
+        assert!(highlighter.is_code_at(0, 4));
+        assert!(!highlighter.is_code_at(0, 6));
+        assert!(!highlighter.is_code_at(0, 11));
+        assert!(highlighter.is_code_at(0, 13));
+        assert!(!highlighter.is_code_at(1, 3));
+        assert!(!highlighter.is_code_at(2, 3));
+        assert!(highlighter.is_code_at(2, 9));
+    }
+}
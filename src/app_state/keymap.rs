@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::app::{AppState, Focus};
+
+/// A single key together with its modifiers, like `ctrl+shift+z`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Terminals report the same keys differently: `ctrl+shift+z` can come as
+    /// an uppercase `Z` with or without the shift modifier, and `shift+tab` is
+    /// a separate `BackTab` key. We bring all of them to a single form.
+    pub fn from_key_event(key_event: &KeyEvent) -> KeyChord {
+        let mut modifiers = key_event.modifiers & !(KeyModifiers::META | KeyModifiers::SUPER);
+
+        let code = match key_event.code {
+            KeyCode::Char(character) if character.is_uppercase() => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Char(character.to_lowercase().next().unwrap_or(character))
+            }
+            KeyCode::BackTab => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Tab
+            }
+            code => code,
+        };
+
+        // for symbols like `:` or `?` shift is a part of the character itself
+        if let KeyCode::Char(character) = code
+            && !character.is_alphabetic()
+            && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            modifiers -= KeyModifiers::SHIFT;
+        }
+
+        KeyChord { code, modifiers }
+    }
+
+    /// Parse a chord like `ctrl+alt+pagedown`; names are case-insensitive
+    pub fn parse(value: &str) -> Result<KeyChord, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let parts: Vec<&str> = value.split('+').collect();
+        let (key, modifier_names) = match parts.split_last() {
+            // `ctrl++` binds the plus key
+            Some((&"", rest)) if value.ends_with("++") => ("+", &rest[..rest.len() - 1]),
+            Some((key, rest)) => (*key, rest),
+            None => return Err(format!("Empty key in \"{value}\"")),
+        };
+
+        for modifier_name in modifier_names {
+            modifiers |= match modifier_name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "shift" => KeyModifiers::SHIFT,
+                "alt" | "option" => KeyModifiers::ALT,
+                _ => {
+                    return Err(format!(
+                        "Unknown modifier \"{modifier_name}\" in \"{value}\""
+                    ));
+                }
+            };
+        }
+
+        let lowercase_key = key.to_lowercase();
+        let code = match lowercase_key.as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            "plus" => KeyCode::Char('+'),
+            name if name.len() > 1 && name.starts_with('f') => match name[1..].parse() {
+                Ok(number) => KeyCode::F(number),
+                Err(_) => return Err(format!("Unknown key \"{key}\" in \"{value}\"")),
+            },
+            name => {
+                let mut characters = name.chars();
+                match (characters.next(), characters.next()) {
+                    (Some(character), None) => KeyCode::Char(character),
+                    _ => return Err(format!("Unknown key \"{key}\" in \"{value}\"")),
+                }
+            }
+        };
+
+        // an uppercase letter like `ctrl+Z` means the same as `ctrl+shift+z`
+        if let KeyCode::Char(_) = code
+            && key.chars().all(char::is_uppercase)
+        {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+
+        Ok(KeyChord::from_key_event(&KeyEvent::new(code, modifiers)))
+    }
+}
+
+/// Parse a sequence of chords separated by spaces, like `ctrl+k ctrl+c`
+pub fn parse_key_sequence(value: &str) -> Result<Vec<KeyChord>, String> {
+    let sequence = value
+        .split_whitespace()
+        .map(KeyChord::parse)
+        .collect::<Result<Vec<KeyChord>, String>>()?;
+
+    if sequence.is_empty() {
+        return Err(String::from("Empty key binding"));
+    }
+
+    Ok(sequence)
+}
+
+/// Where the binding is active. Global bindings work everywhere and are
+/// checked first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Global,
+    Editor,
+    FileTree,
+}
+
+impl KeyContext {
+    pub fn parse(value: &str) -> Option<KeyContext> {
+        match value {
+            "global" => Some(KeyContext::Global),
+            "editor" => Some(KeyContext::Editor),
+            "file_tree" => Some(KeyContext::FileTree),
+            _ => None,
+        }
+    }
+}
+
+pub enum KeyLookup {
+    /// Command line to run, e.g. `save` or `goto 1`
+    Command(String),
+    /// The keys are the beginning of a longer sequence, we need to wait
+    Pending,
+    NotFound,
+}
+
+/// Maps key sequences to commands from the command registry
+pub struct Keymap {
+    bindings: HashMap<KeyContext, HashMap<Vec<KeyChord>, String>>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+
+        let default_bindings = [
+            (KeyContext::Global, "ctrl+q", "quit"),
+            (KeyContext::Global, "ctrl+s", "save"),
+            (KeyContext::Global, "ctrl+e", "toggle-focus"),
+            (KeyContext::Global, "ctrl+w", "close"),
+            (KeyContext::Global, "ctrl+t", "reopen"),
+            (KeyContext::Global, "ctrl+pagedown", "next-buffer"),
+            (KeyContext::Global, "ctrl+pageup", "previous-buffer"),
+            (KeyContext::Global, "ctrl+p", "command-bar"),
+            (KeyContext::Global, "ctrl+o", "find-file"),
+            (KeyContext::Global, "ctrl+shift+f", "project-search"),
+            // the editor needs `:` for typing, so it opens the command bar only from the file tree
+            (KeyContext::FileTree, ":", "command-bar"),
+            (KeyContext::FileTree, "up", "tree-up"),
+            (KeyContext::FileTree, "down", "tree-down"),
+            (KeyContext::FileTree, "right", "tree-expand"),
+            (KeyContext::FileTree, "left", "tree-collapse"),
+            (KeyContext::FileTree, "enter", "tree-open"),
+            (KeyContext::FileTree, "esc", "toggle-focus"),
+            (KeyContext::Editor, "alt+l", "toggle-line-ending"),
+            (KeyContext::Editor, "alt+z", "toggle-soft-wrap"),
+            (KeyContext::Editor, "ctrl+c", "copy"),
+            (KeyContext::Editor, "ctrl+f", "find"),
+            (KeyContext::Editor, "ctrl+r", "replace"),
+            (KeyContext::Editor, "f3", "find-next"),
+            (KeyContext::Editor, "shift+f3", "find-previous"),
+            (KeyContext::Editor, "ctrl+z", "undo"),
+            (KeyContext::Editor, "ctrl+shift+z", "redo"),
+            (KeyContext::Editor, "ctrl+y", "redo"),
+            (KeyContext::Editor, "left", "move-left"),
+            (KeyContext::Editor, "right", "move-right"),
+            (KeyContext::Editor, "up", "move-up"),
+            (KeyContext::Editor, "down", "move-down"),
+            (KeyContext::Editor, "home", "move-line-start"),
+            (KeyContext::Editor, "end", "move-line-end"),
+            (KeyContext::Editor, "shift+left", "select-left"),
+            (KeyContext::Editor, "shift+right", "select-right"),
+            (KeyContext::Editor, "shift+up", "select-up"),
+            (KeyContext::Editor, "shift+down", "select-down"),
+            (KeyContext::Editor, "shift+home", "select-line-start"),
+            (KeyContext::Editor, "shift+end", "select-line-end"),
+            (KeyContext::Editor, "backspace", "delete-backward"),
+            (KeyContext::Editor, "delete", "delete-forward"),
+            (KeyContext::Editor, "ctrl+g", "goto"),
+            (KeyContext::Editor, "ctrl+d", "select-next-occurrence"),
+            (KeyContext::Editor, "ctrl+shift+up", "add-cursor-above"),
+            (KeyContext::Editor, "ctrl+shift+down", "add-cursor-below"),
+            (KeyContext::Editor, "alt+shift+up", "select-block-up"),
+            (KeyContext::Editor, "alt+shift+down", "select-block-down"),
+            (KeyContext::Editor, "alt+shift+left", "select-block-left"),
+            (KeyContext::Editor, "alt+shift+right", "select-block-right"),
+            (KeyContext::Editor, "esc", "single-cursor"),
+            (KeyContext::Editor, "alt+up", "move-line-up"),
+            (KeyContext::Editor, "alt+down", "move-line-down"),
+            (KeyContext::Editor, "ctrl+shift+d", "duplicate-line"),
+            (KeyContext::Editor, "ctrl+shift+k", "delete-line"),
+            (KeyContext::Editor, "ctrl+j", "join-lines"),
+            (KeyContext::Editor, "ctrl+/", "toggle-comment"),
+            // terminals without the keyboard protocol send `ctrl+/` as `ctrl+7`
+            (KeyContext::Editor, "ctrl+7", "toggle-comment"),
+            (KeyContext::Editor, "alt+shift+a", "toggle-block-comment"),
+            (KeyContext::Editor, "alt+m", "go-to-bracket"),
+            (KeyContext::Editor, "alt+shift+m", "select-to-bracket"),
+            (KeyContext::Editor, "pageup", "move-page-up"),
+            (KeyContext::Editor, "pagedown", "move-page-down"),
+            (KeyContext::Editor, "ctrl+home", "move-file-start"),
+            (KeyContext::Editor, "ctrl+end", "move-file-end"),
+            (KeyContext::Editor, "shift+pageup", "select-page-up"),
+            (KeyContext::Editor, "shift+pagedown", "select-page-down"),
+            (KeyContext::Editor, "ctrl+shift+home", "select-file-start"),
+            (KeyContext::Editor, "ctrl+shift+end", "select-file-end"),
+            (KeyContext::Editor, "ctrl+left", "move-word-left"),
+            (KeyContext::Editor, "ctrl+right", "move-word-right"),
+            (KeyContext::Editor, "ctrl+shift+left", "select-word-left"),
+            (KeyContext::Editor, "ctrl+shift+right", "select-word-right"),
+            // most terminals send `ctrl+backspace` as `ctrl+h`, so both of
+            // them delete words, and there is `alt+backspace` too
+            (KeyContext::Editor, "ctrl+backspace", "delete-word-backward"),
+            (KeyContext::Editor, "ctrl+h", "delete-word-backward"),
+            (KeyContext::Editor, "alt+backspace", "delete-word-backward"),
+            (KeyContext::Editor, "ctrl+delete", "delete-word-forward"),
+            (KeyContext::Editor, "enter", "newline"),
+            (KeyContext::Editor, "tab", "indent"),
+            (KeyContext::Editor, "shift+tab", "unindent"),
+        ];
+
+        for (context, keys, command) in default_bindings {
+            let sequence = parse_key_sequence(keys).expect("Default key bindings are valid");
+            keymap.bind(context, sequence, command.to_string());
+        }
+
+        keymap
+    }
+
+    pub fn bind(&mut self, context: KeyContext, sequence: Vec<KeyChord>, command: String) {
+        self.bindings
+            .entry(context)
+            .or_default()
+            .insert(sequence, command);
+    }
+
+    pub fn unbind(&mut self, context: KeyContext, sequence: &[KeyChord]) {
+        if let Some(bindings) = self.bindings.get_mut(&context) {
+            bindings.remove(sequence);
+        }
+    }
+
+    pub fn lookup(&self, context: KeyContext, sequence: &[KeyChord]) -> KeyLookup {
+        let mut is_pending = false;
+
+        for context in [KeyContext::Global, context] {
+            let Some(bindings) = self.bindings.get(&context) else {
+                continue;
+            };
+
+            if let Some(command) = bindings.get(sequence) {
+                return KeyLookup::Command(command.clone());
+            }
+
+            is_pending = is_pending
+                || bindings
+                    .keys()
+                    .any(|keys| keys.len() > sequence.len() && keys.starts_with(sequence));
+        }
+
+        if is_pending {
+            KeyLookup::Pending
+        } else {
+            KeyLookup::NotFound
+        }
+    }
+}
+
+impl AppState {
+    fn get_key_context(&self) -> KeyContext {
+        match self.focus {
+            Focus::Editor => KeyContext::Editor,
+            Focus::FileTree => KeyContext::FileTree,
+            Focus::ProjectSearch => KeyContext::Global,
+        }
+    }
+
+    /// Whether the key alone runs the command; popups are closed with the
+    /// same key which opens them, whatever it is bound to
+    pub fn is_key_bound_to(&self, key_event: &KeyEvent, command: &str) -> bool {
+        let sequence = [KeyChord::from_key_event(key_event)];
+        matches!(
+            self.keymap.lookup(self.get_key_context(), &sequence),
+            KeyLookup::Command(bound_command) if bound_command == command
+        )
+    }
+
+    /// Run the command bound to the key; returns `false` if the key is not bound
+    /// to anything, so it can be used as a text input
+    pub fn handle_key_binding(&mut self, key_event: &KeyEvent) -> bool {
+        let context = self.get_key_context();
+
+        self.pending_keys.push(KeyChord::from_key_event(key_event));
+
+        match self.keymap.lookup(context, &self.pending_keys) {
+            KeyLookup::Command(command) => {
+                self.pending_keys.clear();
+                self.run_command(&command);
+                true
+            }
+            KeyLookup::Pending => true,
+            KeyLookup::NotFound => {
+                // an unfinished sequence swallows the key, like in other editors
+                let was_pending = self.pending_keys.len() > 1;
+                self.pending_keys.clear();
+                was_pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    #[test]
+    fn parses_chords() {
+        assert_eq!(
+            KeyChord::parse("ctrl+shift+z"),
+            Ok(chord(
+                KeyCode::Char('z'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT
+            ))
+        );
+        assert_eq!(
+            KeyChord::parse("Ctrl+PageDown"),
+            Ok(chord(KeyCode::PageDown, KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("shift+tab"),
+            Ok(chord(KeyCode::Tab, KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl++"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("f5"),
+            Ok(chord(KeyCode::F(5), KeyModifiers::NONE))
+        );
+        assert!(KeyChord::parse("hyper+a").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn normalizes_key_events() {
+        // legacy terminals send an uppercase letter for ctrl+shift+z
+        let legacy = KeyEvent::new(KeyCode::Char('Z'), KeyModifiers::CONTROL);
+        let modern = KeyEvent::new(
+            KeyCode::Char('z'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+
+        assert_eq!(
+            KeyChord::from_key_event(&legacy),
+            KeyChord::from_key_event(&modern)
+        );
+        assert_eq!(
+            KeyChord::from_key_event(&KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            KeyChord::parse("shift+tab").unwrap()
+        );
+        assert_eq!(
+            KeyChord::from_key_event(&KeyEvent::new(KeyCode::Char(':'), KeyModifiers::SHIFT)),
+            KeyChord::parse(":").unwrap()
+        );
+    }
+
+    #[test]
+    fn redo_is_reachable() {
+        let keymap = Keymap::new();
+        let sequence = parse_key_sequence("ctrl+shift+z").unwrap();
+
+        assert!(matches!(
+            keymap.lookup(KeyContext::Editor, &sequence),
+            KeyLookup::Command(command) if command == "redo"
+        ));
+    }
+
+    #[test]
+    fn ctrl_h_deletes_words() {
+        let keymap = Keymap::new();
+        let lookup = |keys: &str| match keymap
+            .lookup(KeyContext::Editor, &parse_key_sequence(keys).unwrap())
+        {
+            KeyLookup::Command(command) => command,
+            _ => String::new(),
+        };
+
+        assert_eq!(lookup("ctrl+h"), "delete-word-backward");
+        assert_eq!(lookup("ctrl+r"), "replace");
+        assert_eq!(lookup("ctrl+y"), "redo");
+    }
+
+    #[test]
+    fn finds_keys_which_open_popups() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        let ctrl_o = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+
+        assert!(app_state.is_key_bound_to(&ctrl_p, "command-bar"));
+        assert!(app_state.is_key_bound_to(&ctrl_o, "find-file"));
+        assert!(!app_state.is_key_bound_to(&ctrl_p, "find-file"));
+
+        app_state.keymap.bind(
+            KeyContext::Global,
+            parse_key_sequence("ctrl+p").unwrap(),
+            String::from("find-file"),
+        );
+        assert!(app_state.is_key_bound_to(&ctrl_p, "find-file"));
+    }
+
+    #[test]
+    fn handles_key_sequences() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        app_state.keymap.bind(
+            KeyContext::Global,
+            parse_key_sequence("ctrl+k ctrl+q").unwrap(),
+            String::from("quit"),
+        );
+
+        let ctrl_k = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        let letter = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+
+        assert!(app_state.handle_key_binding(&ctrl_k));
+        assert!(!app_state.should_quit);
+
+        // unknown continuation cancels the sequence
+        assert!(app_state.handle_key_binding(&letter));
+        assert!(app_state.pending_keys.is_empty());
+        assert!(!app_state.handle_key_binding(&letter));
+
+        assert!(app_state.handle_key_binding(&ctrl_k));
+        assert!(app_state.handle_key_binding(&ctrl_q));
+        assert!(app_state.should_quit);
+    }
+}
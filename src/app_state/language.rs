@@ -0,0 +1,88 @@
+use std::path::Path;
+
+/// Name of the language of the file, used for per-language settings and to
+/// pick the syntax highlighting grammar. Mostly based on the extension, but
+/// some files are recognized by their full name.
+pub fn detect_language(path: &Path) -> Option<&'static str> {
+    let file_name = path.file_name()?.to_str()?;
+
+    match file_name {
+        "Makefile" | "makefile" | "GNUmakefile" => return Some("makefile"),
+        "Dockerfile" => return Some("dockerfile"),
+        "Cargo.lock" => return Some("toml"),
+        _ => {}
+    }
+
+    let extension = path.extension()?.to_str()?;
+    let language = match extension {
+        "rs" => "rust",
+        "toml" => "toml",
+        "json" => "json",
+        "md" | "markdown" => "markdown",
+        "yaml" | "yml" => "yaml",
+        "mk" => "makefile",
+        "py" => "python",
+        "js" | "mjs" | "cjs" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "hpp" => "cpp",
+        "sh" | "bash" => "shell",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        "lua" => "lua",
+        _ => return None,
+    };
+
+    Some(language)
+}
+
+/// What starts a comment in the language; languages without line comments,
+/// like HTML, comment every line with the block tokens instead
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CommentTokens {
+    pub line: Option<&'static str>,
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+pub fn get_comment_tokens(language: Option<&str>) -> CommentTokens {
+    let (line, block) = match language {
+        Some("rust" | "javascript" | "typescript" | "go" | "c" | "cpp") => {
+            (Some("//"), Some(("/*", "*/")))
+        }
+        Some("python" | "shell" | "toml" | "yaml" | "makefile" | "dockerfile") => (Some("#"), None),
+        Some("sql") => (Some("--"), Some(("/*", "*/"))),
+        Some("lua") => (Some("--"), Some(("--[[", "]]"))),
+        Some("css") => (None, Some(("/*", "*/"))),
+        Some("html" | "markdown") => (None, Some(("<!--", "-->"))),
+        _ => (None, None),
+    };
+
+    CommentTokens { line, block }
+}
+
+/// Pairs closed automatically unless the config sets them. A single quote
+/// starts Rust lifetimes and is an apostrophe in prose, so it is closed
+/// only in languages where it always starts a string or a character.
+pub fn get_default_auto_pairs(language: Option<&str>) -> &'static str {
+    match language {
+        Some("rust" | "markdown") | None => "()[]{}\"\"",
+        Some(_) => "()[]{}\"\"''",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_languages() {
+        assert_eq!(detect_language(Path::new("src/main.rs")), Some("rust"));
+        assert_eq!(detect_language(Path::new("ci/config.yml")), Some("yaml"));
+        assert_eq!(detect_language(Path::new("Makefile")), Some("makefile"));
+        assert_eq!(detect_language(Path::new("Cargo.lock")), Some("toml"));
+        assert_eq!(detect_language(Path::new("notes.txt")), None);
+        assert_eq!(detect_language(Path::new("LICENSE")), None);
+    }
+}
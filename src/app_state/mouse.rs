@@ -0,0 +1,349 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+use super::app::{AppState, Focus};
+use super::editor::UIState;
+
+/// Clicks at the same spot within this time count as double and triple clicks
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// Lines scrolled by one step of the mouse wheel
+const SCROLL_LINES: usize = 3;
+
+/// The last click, needed to detect double and triple clicks
+pub struct Click {
+    time: Instant,
+    column: u16,
+    row: u16,
+    /// `1` for a single click, `2` for a double click and `3` for a triple one
+    count: usize,
+}
+
+impl UIState {
+    /// Map a point on the screen to the position in the text; points outside
+    /// of the text are clamped to the closest line and column. A point above
+    /// the first visible line gives the line before it, so dragging the mouse
+    /// there scrolls up.
+    pub fn get_position_at(&self, x: usize, y: usize) -> (usize, usize) {
+        let lines_number = self.lines.len_lines().max(1);
+        let visible_rows = self.get_visible_rows();
+
+        // 1 for the padding at the top
+        let (line, row) = match y.checked_sub(self.editor_offset_y + 1) {
+            Some(index) if index < visible_rows.len() => {
+                let visible_row = visible_rows[index];
+                (visible_row.line_index + 1, visible_row.row)
+            }
+            // below the text, it is the line after the last visible one
+            Some(index) => {
+                let next_line = match visible_rows.last() {
+                    Some(last_row) if self.soft_wrap => last_row.line_index + 2,
+                    _ => self.editor_scroll_offset + index + 1,
+                };
+                (next_line.min(lines_number), 0)
+            }
+            None => (self.editor_scroll_offset.max(1), 0),
+        };
+
+        // the line number is in front of the text
+        let x = x.saturating_sub(self.editor_offset_x + self.prefix_len + 1);
+        let index = self
+            .get_line_layout(line - 1)
+            .get_index_at(row, self.horizontal_scroll_offset + x);
+
+        (line, index + 1)
+    }
+
+    /// Move the cursor to the point; with `shift` the selection is extended
+    /// to it, like with the keyboard
+    pub fn click_at(&mut self, x: usize, y: usize, modifiers: &KeyModifiers) {
+        self.clear_extra_cursors();
+        self.start_selection(modifiers);
+        self.move_cursor_to(self.get_position_at(x, y));
+        self.adjust_selection();
+    }
+
+    /// Extend the selection while the mouse button is held down
+    pub fn drag_to(&mut self, x: usize, y: usize) {
+        self.start_selection(&KeyModifiers::SHIFT);
+        self.move_cursor_to(self.get_position_at(x, y));
+        self.adjust_selection();
+    }
+
+    /// Select the word under the point, or a run of spaces or punctuation
+    pub fn select_word_at(&mut self, x: usize, y: usize) {
+        self.clear_extra_cursors();
+        self.select_word(self.get_position_at(x, y));
+    }
+
+    /// Select the whole line under the point, together with its line break
+    pub fn select_line_at(&mut self, x: usize, y: usize) {
+        self.clear_extra_cursors();
+        let (line, _) = self.get_position_at(x, y);
+
+        let end = if line < self.lines.len_lines() {
+            (line + 1, 1)
+        } else {
+            (line, self.get_line_len(line - 1) + 1)
+        };
+
+        self.set_selection((line, 1), end);
+        self.move_cursor_to(end);
+    }
+
+    /// Scroll the view without moving the cursor, unless it would go off the
+    /// screen; then it is moved to the closest visible line
+    pub fn scroll_view(&mut self, lines: isize) {
+        let max_offset = self.lines.len_lines().saturating_sub(self.editor_lines_num);
+        self.editor_scroll_offset = self
+            .editor_scroll_offset
+            .saturating_add_signed(lines)
+            .min(max_offset);
+
+        let first_line = self.editor_scroll_offset + 1;
+        let last_line = self
+            .get_visible_rows()
+            .last()
+            .map_or(first_line, |row| row.line_index + 1);
+        if self.cursor_line < first_line || self.cursor_line > last_line {
+            self.cursor_line = self.cursor_line.clamp(first_line, last_line);
+            self.cursor_column = self
+                .cursor_column
+                .min(self.get_line_len(self.cursor_line - 1) + 1);
+        }
+    }
+
+    fn move_cursor_to(&mut self, (line, column): (usize, usize)) {
+        self.vertical_offset_target = 0;
+        self.cursor_line = line;
+        self.cursor_column = column;
+        self.handle_cursor_scrolling();
+    }
+}
+
+impl AppState {
+    /// Clicks and the wheel work on the editor and the file tree, which are
+    /// found by the areas they were rendered into last time
+    pub fn handle_mouse_event(&mut self, mouse_event: &MouseEvent) {
+        // popups and prompts are answered with the keyboard
+        if self.prompt.is_some() || self.command_bar.is_some() || self.file_finder.is_some() {
+            return;
+        }
+
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        let x = mouse_event.column as usize;
+        let y = mouse_event.row as usize;
+
+        // the selection keeps following the mouse outside of the editor,
+        // which also scrolls it
+        let is_editor_drag = mouse_event.kind == MouseEventKind::Drag(MouseButton::Left)
+            && self.focus == Focus::Editor;
+
+        if self.editor_area.contains(position) || is_editor_drag {
+            self.handle_editor_mouse_event(mouse_event, x, y);
+        } else if self.file_tree_area.contains(position) && self.project_search.is_none() {
+            self.handle_file_tree_mouse_event(mouse_event, y);
+        }
+    }
+
+    fn handle_editor_mouse_event(&mut self, mouse_event: &MouseEvent, x: usize, y: usize) {
+        let click_count = match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.register_click(mouse_event),
+            _ => 0,
+        };
+
+        let Some(buffer) = self.buffers.get_mut(self.active_buffer) else {
+            return;
+        };
+        let ui_state = &mut buffer.ui_state;
+
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.focus = Focus::Editor;
+
+                match click_count {
+                    _ if mouse_event.modifiers.contains(KeyModifiers::ALT) => {
+                        ui_state.toggle_cursor_at(ui_state.get_position_at(x, y))
+                    }
+                    2 => ui_state.select_word_at(x, y),
+                    3 => ui_state.select_line_at(x, y),
+                    _ => ui_state.click_at(x, y, &mouse_event.modifiers),
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left)
+                if mouse_event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                ui_state.drag_block_to(x, y)
+            }
+            MouseEventKind::Drag(MouseButton::Left) => ui_state.drag_to(x, y),
+            MouseEventKind::ScrollUp => ui_state.scroll_view(-(SCROLL_LINES as isize)),
+            MouseEventKind::ScrollDown => ui_state.scroll_view(SCROLL_LINES as isize),
+            _ => {}
+        }
+    }
+
+    /// A click selects the entry and opens it, like `Enter` does
+    fn handle_file_tree_mouse_event(&mut self, mouse_event: &MouseEvent, y: usize) {
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                // 1 for the padding at the top
+                let Some(row) = y.checked_sub(self.file_tree_area.y as usize + 1) else {
+                    return;
+                };
+                let index = self.file_tree_scroll_offset + row;
+                if index >= self.get_visible_file_tree().len() {
+                    return;
+                }
+
+                self.focus = Focus::FileTree;
+                self.file_tree_selected = index;
+                self.file_tree_open_selected();
+            }
+            MouseEventKind::ScrollUp => {
+                for _ in 0..SCROLL_LINES {
+                    self.file_tree_move_up();
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                for _ in 0..SCROLL_LINES {
+                    self.file_tree_move_down();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Count clicks at the same spot; the fourth one starts over
+    fn register_click(&mut self, mouse_event: &MouseEvent) -> usize {
+        let now = Instant::now();
+
+        let count = match &self.last_click {
+            Some(click)
+                if click.column == mouse_event.column
+                    && click.row == mouse_event.row
+                    && now.duration_since(click.time) < MULTI_CLICK_INTERVAL =>
+            {
+                click.count % 3 + 1
+            }
+            _ => 1,
+        };
+
+        self.last_click = Some(Click {
+            time: now,
+            column: mouse_event.column,
+            row: mouse_event.row,
+            count,
+        });
+
+        count
+    }
+
+    /// Areas are updated on every render
+    pub fn set_layout_areas(&mut self, file_tree_area: Rect, editor_area: Rect) {
+        self.file_tree_area = file_tree_area;
+        self.editor_area = editor_area;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::lines;
+
+    /// The editor starts at (50, 1), so the text starts at (53, 2) with
+    /// a single-digit line number prefix
+    fn create_ui_state(text: &str) -> UIState {
+        let mut ui_state = UIState::new(1, lines(text));
+        ui_state.set_editor_offset(50, 1, 5);
+        ui_state
+    }
+
+    #[test]
+    fn maps_screen_points_to_positions() {
+        let ui_state = create_ui_state("first\nsecond line\n\nlast");
+
+        assert_eq!(ui_state.get_position_at(54, 2), (1, 1));
+        assert_eq!(ui_state.get_position_at(56, 3), (2, 3));
+        // before the text, after the line end and below the last line
+        assert_eq!(ui_state.get_position_at(51, 3), (2, 1));
+        assert_eq!(ui_state.get_position_at(100, 2), (1, 6));
+        assert_eq!(ui_state.get_position_at(56, 20), (4, 3));
+    }
+
+    #[test]
+    fn clicks_and_drags_select_text() {
+        let mut ui_state = create_ui_state("first\nsecond line");
+
+        ui_state.click_at(55, 2, &KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 2));
+
+        ui_state.drag_to(57, 3);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 2), (2, 4)));
+
+        ui_state.click_at(54, 2, &KeyModifiers::NONE);
+        assert!(ui_state.selection.is_none());
+
+        ui_state.click_at(56, 2, &KeyModifiers::SHIFT);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 1), (1, 3)));
+    }
+
+    #[test]
+    fn selects_words_and_lines() {
+        let mut ui_state = create_ui_state("let some_value = 1;\nnext");
+
+        ui_state.select_word_at(60, 2);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 5), (1, 15)));
+        assert_eq!(ui_state.cursor_column, 15);
+
+        ui_state.select_line_at(60, 2);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((1, 1), (2, 1)));
+
+        ui_state.select_line_at(60, 3);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((2, 1), (2, 5)));
+    }
+
+    #[test]
+    fn scrolls_and_keeps_cursor_visible() {
+        let text: Vec<String> = (1..=20).map(|line| line.to_string()).collect();
+        let mut ui_state = create_ui_state(&text.join("\n"));
+
+        ui_state.scroll_view(3);
+        assert_eq!(ui_state.editor_scroll_offset, 3);
+        assert_eq!(ui_state.cursor_line, 4);
+
+        ui_state.scroll_view(100);
+        assert_eq!(ui_state.editor_scroll_offset, 17);
+
+        ui_state.scroll_view(-100);
+        assert_eq!(ui_state.editor_scroll_offset, 0);
+        assert_eq!(ui_state.cursor_line, 3);
+    }
+
+    #[test]
+    fn counts_multiple_clicks() {
+        let mut app_state = AppState::new(std::path::PathBuf::from("."));
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 10,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        assert_eq!(app_state.register_click(&click), 1);
+        assert_eq!(app_state.register_click(&click), 2);
+        assert_eq!(app_state.register_click(&click), 3);
+        assert_eq!(app_state.register_click(&click), 1);
+
+        let other_click = MouseEvent {
+            column: 11,
+            ..click
+        };
+        assert_eq!(app_state.register_click(&other_click), 1);
+    }
+}
@@ -0,0 +1,597 @@
+use std::cmp::Reverse;
+
+use super::editor::UIState;
+use super::selection::Selection;
+use super::undo_redo::UndoRedo;
+
+/// An additional cursor; the primary one lives in `UIState` itself, and
+/// everything which works with a single cursor works with it
+#[derive(Clone)]
+pub struct Cursor {
+    pub line: usize,
+    pub column: usize,
+    pub selection: Option<Selection>,
+    vertical_offset_target: usize,
+}
+
+impl Cursor {
+    /// Start and end of the selection, or the cursor position twice
+    pub fn get_range(&self) -> ((usize, usize), (usize, usize)) {
+        let position = (self.line, self.column);
+        match &self.selection {
+            Some(selection) => (
+                selection.start.min(selection.end),
+                selection.start.max(selection.end),
+            ),
+            None => (position, position),
+        }
+    }
+}
+
+/// A cursor with positions converted to offsets in the text, so they can be
+/// shifted by edits made before them
+struct CursorOffsets {
+    cursor: usize,
+    selection: Option<(usize, usize)>,
+    vertical_offset_target: usize,
+}
+
+impl UIState {
+    pub fn has_multiple_cursors(&self) -> bool {
+        !self.extra_cursors.is_empty()
+    }
+
+    pub fn clear_extra_cursors(&mut self) {
+        self.extra_cursors.clear();
+        self.block_selection = None;
+    }
+
+    pub(super) fn take_primary_cursor(&mut self) -> Cursor {
+        Cursor {
+            line: self.cursor_line,
+            column: self.cursor_column,
+            selection: self.selection.take(),
+            vertical_offset_target: self.vertical_offset_target,
+        }
+    }
+
+    pub(super) fn set_primary_cursor(&mut self, cursor: Cursor) {
+        self.cursor_line = cursor.line;
+        self.cursor_column = cursor.column;
+        self.selection = cursor.selection;
+        self.vertical_offset_target = cursor.vertical_offset_target;
+    }
+
+    /// All cursors, the primary one first
+    pub fn get_cursor_positions(&self) -> Vec<(usize, usize)> {
+        let mut positions = vec![(self.cursor_line, self.cursor_column)];
+        positions.extend(
+            self.extra_cursors
+                .iter()
+                .map(|cursor| (cursor.line, cursor.column)),
+        );
+        positions
+    }
+
+    /// Put the cursors back, e.g. on undo; selections are dropped
+    pub fn set_cursor_positions(&mut self, positions: &[(usize, usize)]) {
+        let Some((&(line, column), rest)) = positions.split_first() else {
+            return;
+        };
+
+        self.selection = None;
+        self.vertical_offset_target = 0;
+        (self.cursor_line, self.cursor_column) = self.clamp_position((line, column));
+        self.extra_cursors = rest
+            .iter()
+            .map(|position| {
+                let (line, column) = self.clamp_position(*position);
+                Cursor {
+                    line,
+                    column,
+                    selection: None,
+                    vertical_offset_target: 0,
+                }
+            })
+            .collect();
+
+        self.merge_cursors();
+        self.handle_cursor_scrolling();
+    }
+
+    fn clamp_position(&self, (line, column): (usize, usize)) -> (usize, usize) {
+        let line = line.clamp(1, self.lines.len_lines());
+        (line, column.clamp(1, self.get_line_len(line - 1) + 1))
+    }
+
+    fn to_offset(&self, (line, column): (usize, usize)) -> usize {
+        self.lines.position_to_offset(line - 1, column - 1)
+    }
+
+    fn to_position(&self, offset: usize) -> (usize, usize) {
+        let (line, column) = self.lines.offset_to_position(offset);
+        (line + 1, column + 1)
+    }
+
+    /// Run the action at every cursor, as if it was the only one. Cursors are
+    /// processed from the end of the file, so the text before the current one
+    /// is not changed yet; the ones after it are moved by the amount of added
+    /// or removed characters. All edits are undone in a single step.
+    pub fn for_each_cursor(
+        &mut self,
+        undo_redo: &mut UndoRedo,
+        mut action: impl FnMut(&mut UIState, &mut UndoRedo),
+    ) {
+        if self.extra_cursors.is_empty() {
+            action(self, undo_redo);
+            self.block_selection = None;
+            return;
+        }
+
+        let cursors_before = self.get_cursor_positions();
+        let scroll_offsets = (self.editor_scroll_offset, self.horizontal_scroll_offset);
+
+        let mut cursors = vec![self.take_primary_cursor()];
+        cursors.append(&mut self.extra_cursors);
+        let mut order: Vec<usize> = (0..cursors.len()).collect();
+        order.sort_by_key(|index| Reverse(cursors[*index].get_range()));
+
+        let mut group = UndoRedo::new();
+        let mut done: Vec<(usize, CursorOffsets)> = vec![];
+
+        for index in order {
+            self.set_primary_cursor(cursors[index].clone());
+            let len_before = self.lines.len_chars() as isize;
+
+            action(self, &mut group);
+            group.commit_buffer();
+
+            let delta = self.lines.len_chars() as isize - len_before;
+            let cursor = self.get_cursor_offsets();
+            // cursors inside of removed text end up where it was
+            let edit_start = cursor.selection.map_or(cursor.cursor, |(start, end)| {
+                cursor.cursor.min(start).min(end)
+            });
+            let shift = |offset: usize| offset.saturating_add_signed(delta).max(edit_start);
+
+            for (_, other) in &mut done {
+                other.cursor = shift(other.cursor);
+                other.selection = other
+                    .selection
+                    .map(|(start, end)| (shift(start), shift(end)));
+            }
+
+            done.push((index, cursor));
+        }
+
+        // the primary cursor had the index `0`
+        done.sort_by_key(|(index, _)| *index);
+        let mut cursors: Vec<Cursor> = done
+            .into_iter()
+            .map(|(_, offsets)| self.cursor_from_offsets(offsets))
+            .collect();
+        self.extra_cursors = cursors.split_off(1);
+        if let Some(primary) = cursors.pop() {
+            self.set_primary_cursor(primary);
+        }
+        self.merge_cursors();
+        self.block_selection = None;
+
+        (self.editor_scroll_offset, self.horizontal_scroll_offset) = scroll_offsets;
+        self.handle_cursor_scrolling();
+
+        undo_redo.add_group(group, cursors_before, self.get_cursor_positions());
+    }
+
+    fn get_cursor_offsets(&self) -> CursorOffsets {
+        CursorOffsets {
+            cursor: self.to_offset((self.cursor_line, self.cursor_column)),
+            selection: self.selection.as_ref().map(|selection| {
+                (
+                    self.to_offset(selection.start),
+                    self.to_offset(selection.end),
+                )
+            }),
+            vertical_offset_target: self.vertical_offset_target,
+        }
+    }
+
+    fn cursor_from_offsets(&self, offsets: CursorOffsets) -> Cursor {
+        let (line, column) = self.to_position(offsets.cursor);
+        let selection =
+            offsets
+                .selection
+                .filter(|(start, end)| start != end)
+                .map(|(start, end)| {
+                    let (start_line, start_column) = self.to_position(start);
+                    let mut selection = Selection::new(start_line, start_column);
+                    let (end_line, end_column) = self.to_position(end);
+                    selection.set_end(end_line, end_column);
+                    selection
+                });
+
+        Cursor {
+            line,
+            column,
+            selection,
+            vertical_offset_target: offsets.vertical_offset_target,
+        }
+    }
+
+    /// Cursors at the same place and overlapping selections become one;
+    /// if one of them was the primary cursor, the result is primary too
+    fn merge_cursors(&mut self) {
+        if self.extra_cursors.is_empty() {
+            return;
+        }
+
+        let mut cursors = vec![(self.take_primary_cursor(), true)];
+        cursors.extend(self.extra_cursors.drain(..).map(|cursor| (cursor, false)));
+        cursors.sort_by_key(|(cursor, _)| cursor.get_range());
+
+        let mut merged: Vec<(Cursor, bool)> = vec![];
+        for (cursor, is_primary) in cursors {
+            let (start, end) = cursor.get_range();
+
+            if let Some((last, last_is_primary)) = merged.last_mut() {
+                let (last_start, last_end) = last.get_range();
+
+                if start < last_end || start == last_start {
+                    let end = end.max(last_end);
+                    *last = Cursor {
+                        line: end.0,
+                        column: end.1,
+                        selection: (last_start != end).then(|| {
+                            let mut selection = Selection::new(last_start.0, last_start.1);
+                            selection.set_end(end.0, end.1);
+                            selection
+                        }),
+                        vertical_offset_target: last.vertical_offset_target,
+                    };
+                    *last_is_primary = *last_is_primary || is_primary;
+                    continue;
+                }
+            }
+
+            merged.push((cursor, is_primary));
+        }
+
+        let primary_index = merged
+            .iter()
+            .position(|(_, is_primary)| *is_primary)
+            .unwrap_or_default();
+        let (primary, _) = merged.remove(primary_index);
+        self.set_primary_cursor(primary);
+        self.extra_cursors = merged.into_iter().map(|(cursor, _)| cursor).collect();
+    }
+
+    /// The new cursor becomes the primary one, so the view follows it
+    fn push_primary_cursor(&mut self, (line, column): (usize, usize)) {
+        let cursor = self.take_primary_cursor();
+        self.extra_cursors.push(cursor);
+
+        self.cursor_line = line;
+        self.cursor_column = column;
+        self.vertical_offset_target = 0;
+    }
+
+    /// Add a cursor at the position, or remove the one which is already there
+    pub fn toggle_cursor_at(&mut self, position: (usize, usize)) {
+        self.block_selection = None;
+        let position = self.clamp_position(position);
+
+        if let Some(index) = self
+            .extra_cursors
+            .iter()
+            .position(|cursor| (cursor.line, cursor.column) == position)
+        {
+            self.extra_cursors.remove(index);
+            return;
+        }
+
+        if (self.cursor_line, self.cursor_column) == position {
+            if let Some(cursor) = self.extra_cursors.pop() {
+                self.set_primary_cursor(cursor);
+            }
+            return;
+        }
+
+        self.push_primary_cursor(position);
+        self.merge_cursors();
+        self.handle_cursor_scrolling();
+    }
+
+    /// Add a cursor on the row above or below the primary one, at the same
+    /// place on the screen
+    pub fn add_cursor_vertically(&mut self, is_up: bool) {
+        self.block_selection = None;
+        let primary = self.take_primary_cursor();
+        let position = (primary.line, primary.column);
+        self.set_primary_cursor(primary.clone());
+
+        self.cursor_move_visual_row(is_up);
+        let new_position = (self.cursor_line, self.cursor_column);
+        let vertical_offset_target = self.vertical_offset_target;
+        self.set_primary_cursor(primary);
+
+        if new_position == position {
+            return;
+        }
+
+        self.push_primary_cursor(new_position);
+        self.vertical_offset_target = vertical_offset_target;
+        self.merge_cursors();
+        self.handle_cursor_scrolling();
+    }
+
+    /// Select the word under the cursor; if something is selected already,
+    /// select its next occurrence with a new cursor. Only text within a
+    /// single line can be searched for.
+    pub fn select_next_occurrence(&mut self) {
+        self.block_selection = None;
+        let Some(selection) = &self.selection else {
+            self.select_word((self.cursor_line, self.get_word_column_at_cursor()));
+            return;
+        };
+
+        let (start, end) = (
+            selection.start.min(selection.end),
+            selection.start.max(selection.end),
+        );
+        if start.0 != end.0 {
+            return;
+        }
+        let pattern = self.lines.get_line(start.0 - 1)[start.1 - 1..end.1 - 1].to_vec();
+
+        let Some((line, column)) = self.find_next_occurrence(&pattern, end) else {
+            return;
+        };
+
+        let end_column = column + pattern.len();
+        self.push_primary_cursor((line, end_column));
+        self.set_selection((line, column), (line, end_column));
+        self.merge_cursors();
+        self.handle_cursor_scrolling();
+    }
+
+    /// Next place with the text after the position, wrapping around the end
+    /// of the file; places already selected by a cursor are skipped
+    fn find_next_occurrence(
+        &self,
+        pattern: &[char],
+        from: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        // an empty pattern would be found at every position
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let lines_number = self.lines.len_lines();
+        let selected: Vec<((usize, usize), (usize, usize))> = self
+            .extra_cursors
+            .iter()
+            .map(Cursor::get_range)
+            .chain(self.selection.as_ref().map(|selection| {
+                (
+                    selection.start.min(selection.end),
+                    selection.start.max(selection.end),
+                )
+            }))
+            .collect();
+
+        // the line of the cursor is checked twice: after it, and before it
+        // once the search wraps around
+        for step in 0..=lines_number {
+            let line_index = (from.0 - 1 + step) % lines_number;
+            let line = self.lines.get_line(line_index);
+            let first_column = if step == 0 { from.1 - 1 } else { 0 };
+
+            if line.len() < pattern.len() {
+                continue;
+            }
+
+            for index in first_column..=line.len() - pattern.len() {
+                let range = (
+                    (line_index + 1, index + 1),
+                    (line_index + 1, index + pattern.len() + 1),
+                );
+                if line[index..index + pattern.len()] == *pattern && !selected.contains(&range) {
+                    return Some(range.0);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Text of every selection, in the order they are in the file; lines of
+    /// a block selection are all included, even the ones with nothing selected
+    pub fn get_selected_texts(&self) -> Vec<String> {
+        let is_block = self.block_selection.is_some();
+        let mut ranges: Vec<((usize, usize), (usize, usize))> = self
+            .extra_cursors
+            .iter()
+            .filter(|cursor| cursor.selection.is_some() || is_block)
+            .map(Cursor::get_range)
+            .collect();
+        if let Some(selection) = &self.selection {
+            ranges.push((
+                selection.start.min(selection.end),
+                selection.start.max(selection.end),
+            ));
+        } else if is_block {
+            let position = (self.cursor_line, self.cursor_column);
+            ranges.push((position, position));
+        }
+        ranges.sort();
+
+        ranges
+            .into_iter()
+            .map(|(start, end)| {
+                self.lines
+                    .get_text((start.0 - 1, start.1 - 1), (end.0 - 1, end.1 - 1))
+            })
+            .collect()
+    }
+
+    /// With as many lines as cursors, every cursor gets its own line;
+    /// otherwise the whole text is pasted at every cursor. A copied block
+    /// is pasted as a column below a single cursor.
+    pub fn paste_at_cursors(&mut self, data: String, undo_redo: &mut UndoRedo) {
+        let normalized = data.replace("\r\n", "\n");
+        let mut lines: Vec<String> = normalized.split('\n').map(String::from).collect();
+
+        if !self.has_multiple_cursors()
+            && lines.len() > 1
+            && self.block_clipboard.as_ref() == Some(&normalized)
+        {
+            self.start_block_column(lines.len());
+            // lines past the end of the file go to the last one
+            let cursors_number = self.extra_cursors.len() + 1;
+            if cursors_number < lines.len() {
+                let rest = lines.split_off(cursors_number - 1).join("\n");
+                lines.push(rest);
+            }
+        }
+
+        if !self.has_multiple_cursors() || lines.len() != self.extra_cursors.len() + 1 {
+            self.for_each_cursor(undo_redo, |ui_state, undo_redo| {
+                ui_state.handle_paste(data.clone(), undo_redo)
+            });
+            return;
+        }
+
+        // cursors are processed from the end of the file
+        self.for_each_cursor(undo_redo, |ui_state, undo_redo| {
+            let line = lines.pop().unwrap_or_default();
+            ui_state.pad_to_block(undo_redo);
+            ui_state.handle_paste(line, undo_redo);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::{create_ui_state, get_text};
+    use crossterm::event::KeyModifiers;
+
+    #[test]
+    fn edits_at_every_cursor() {
+        let mut ui_state = create_ui_state("one\ntwo\nthree");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.add_cursor_vertically(false);
+        ui_state.add_cursor_vertically(false);
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 1), (1, 1), (2, 1)]);
+
+        for character in "- ".chars() {
+            ui_state.for_each_cursor(&mut undo_redo, |ui_state, undo_redo| {
+                ui_state.insert_character(character, undo_redo)
+            });
+        }
+        assert_eq!(get_text(&ui_state), "- one\n- two\n- three");
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 3), (1, 3), (2, 3)]);
+
+        ui_state.for_each_cursor(&mut undo_redo, |ui_state, undo_redo| {
+            ui_state.cursor_move_line_end(&KeyModifiers::NONE);
+            ui_state.remove_previous_character(undo_redo);
+        });
+        assert_eq!(get_text(&ui_state), "- on\n- tw\n- thre");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "- one\n- two\n- three");
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 3), (1, 3), (2, 3)]);
+
+        // typing both characters is a single step
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "one\ntwo\nthree");
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 1), (1, 1), (2, 1)]);
+
+        undo_redo.redo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "- one\n- two\n- three");
+    }
+
+    #[test]
+    fn edits_several_cursors_on_the_same_line() {
+        let mut ui_state = create_ui_state("a, b, c");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.toggle_cursor_at((1, 3));
+        ui_state.toggle_cursor_at((1, 6));
+        ui_state.for_each_cursor(&mut undo_redo, |ui_state, undo_redo| {
+            ui_state.add_new_line(undo_redo);
+        });
+
+        assert_eq!(get_text(&ui_state), "\na,\n b,\n c");
+        assert_eq!(ui_state.get_cursor_positions(), [(4, 1), (2, 1), (3, 1)]);
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(get_text(&ui_state), "a, b, c");
+    }
+
+    #[test]
+    fn merges_cursors() {
+        let mut ui_state = create_ui_state("abc");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.toggle_cursor_at((1, 2));
+        ui_state.toggle_cursor_at((1, 3));
+        ui_state.for_each_cursor(&mut undo_redo, |ui_state, _| {
+            ui_state.cursor_move_line_start(&KeyModifiers::NONE);
+        });
+
+        assert!(!ui_state.has_multiple_cursors());
+        assert_eq!(ui_state.get_cursor_positions(), [(1, 1)]);
+    }
+
+    #[test]
+    fn selects_next_occurrences() {
+        let mut ui_state = create_ui_state("let value = value + 1;\nvalue");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.cursor_column = 6;
+
+        ui_state.select_next_occurrence();
+        ui_state.select_next_occurrence();
+        ui_state.select_next_occurrence();
+        // wraps around to the first one, which is selected already
+        ui_state.select_next_occurrence();
+        assert_eq!(ui_state.get_cursor_positions(), [(2, 6), (1, 10), (1, 18)]);
+        assert_eq!(ui_state.get_selected_texts(), ["value"; 3]);
+
+        for character in "x".chars() {
+            ui_state.for_each_cursor(&mut undo_redo, |ui_state, undo_redo| {
+                ui_state.insert_character(character, undo_redo)
+            });
+        }
+        assert_eq!(get_text(&ui_state), "let x = x + 1;\nx");
+    }
+
+    #[test]
+    fn selects_the_word_before_the_cursor() {
+        let mut ui_state = create_ui_state("value = value");
+        ui_state.cursor_column = 6;
+        ui_state.select_next_occurrence();
+        assert_eq!(ui_state.get_selected_texts(), ["value"]);
+
+        ui_state.cursor_move_line_end(&KeyModifiers::NONE);
+        ui_state.select_next_occurrence();
+        assert_eq!(ui_state.get_selected_texts(), ["value"]);
+
+        // nothing is selected, so there is nothing to look for
+        ui_state.set_selection((1, 3), (1, 3));
+        ui_state.select_next_occurrence();
+        assert!(!ui_state.has_multiple_cursors());
+    }
+
+    #[test]
+    fn pastes_a_line_per_cursor() {
+        let mut ui_state = create_ui_state("a\nb");
+        let mut undo_redo = UndoRedo::new();
+
+        ui_state.add_cursor_vertically(false);
+        ui_state.paste_at_cursors(String::from("1\n2"), &mut undo_redo);
+
+        assert_eq!(get_text(&ui_state), "1a\n2b");
+    }
+}
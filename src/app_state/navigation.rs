@@ -1,3 +1,4 @@
+use super::display_width::get_cluster_boundaries;
 use super::editor::UIState;
 use crossterm::event::KeyModifiers;
 
@@ -21,9 +22,10 @@ impl UIState {
 
                     self.handle_cursor_scrolling();
                 }
+            } else if modifiers.contains(KeyModifiers::CONTROL) {
+                self.cursor_column = self.get_previous_word_column();
             } else {
-                let new_value = self.cursor_column - 1;
-                self.cursor_column = new_value;
+                self.cursor_column = self.get_previous_cursor_column();
             }
 
             self.adjust_selection();
@@ -38,7 +40,7 @@ impl UIState {
 
             let line_len = self.get_line_len(self.cursor_line - 1);
             if self.cursor_column > line_len {
-                if self.cursor_line >= self.lines.len() {
+                if self.cursor_line >= self.lines.len_lines() {
                     // we are on the last line, do nothing
                     return;
                 } else {
@@ -48,8 +50,10 @@ impl UIState {
 
                     self.handle_cursor_scrolling();
                 }
+            } else if modifiers.contains(KeyModifiers::CONTROL) {
+                self.cursor_column = self.get_next_word_column();
             } else {
-                self.cursor_column += 1;
+                self.cursor_column = self.get_next_cursor_column();
             }
 
             self.adjust_selection();
@@ -60,17 +64,7 @@ impl UIState {
         if self.should_show_cursor {
             self.start_selection(modifiers);
 
-            if self.cursor_line == 1 {
-                if self.vertical_offset_target == 0 {
-                    self.vertical_offset_target = self.cursor_column;
-                }
-                self.cursor_column = 1;
-            } else {
-                self.cursor_line -= 1;
-                self.adjust_cursor_column_after_vertical_nav();
-
-                self.handle_cursor_scrolling();
-            }
+            self.cursor_move_visual_row(true);
 
             self.adjust_selection();
         }
@@ -80,19 +74,7 @@ impl UIState {
         if self.should_show_cursor {
             self.start_selection(modifiers);
 
-            if self.cursor_line == self.lines.len() {
-                if self.vertical_offset_target == 0 {
-                    self.vertical_offset_target = self.cursor_column;
-                }
-
-                let line_len = self.get_line_len(self.cursor_line - 1);
-                self.cursor_column = line_len + 1;
-            } else {
-                self.cursor_line += 1;
-                self.adjust_cursor_column_after_vertical_nav();
-
-                self.handle_cursor_scrolling();
-            }
+            self.cursor_move_visual_row(false);
 
             self.adjust_selection();
         }
@@ -122,36 +104,101 @@ impl UIState {
         }
     }
 
-    pub fn handle_cursor_scrolling(&mut self) {
-        if self.cursor_line < self.editor_scroll_offset + 1 {
-            self.editor_scroll_offset = self.cursor_line - 1;
-        } else if self.cursor_line > self.editor_scroll_offset + self.editor_lines_num {
-            self.editor_scroll_offset = self.cursor_line - self.editor_lines_num;
-        }
+    /// Move the cursor to the line and column (starting with `1`); numbers
+    /// outside of the file go to the first or the last line, and columns
+    /// are clamped to the line length
+    pub fn go_to_line(&mut self, line: usize, column: usize) {
+        self.clear_extra_cursors();
+        self.selection = None;
+        self.vertical_offset_target = 0;
+        self.cursor_line = line.clamp(1, self.lines.len_lines());
+
+        // the column can point into the middle of a combined character
+        let boundaries = get_cluster_boundaries(&self.lines.get_line(self.cursor_line - 1));
+        self.cursor_column = boundaries
+            .iter()
+            .rev()
+            .find(|boundary| **boundary < column.max(1))
+            .map_or(1, |boundary| boundary + 1);
+
+        self.handle_cursor_scrolling();
     }
 
-    fn adjust_cursor_column_after_vertical_nav(&mut self) {
-        // if it is not 0, it means we were pressing up/down before
-        if self.vertical_offset_target == 0 {
-            self.vertical_offset_target = self.cursor_column;
+    pub fn cursor_move_page_up(&mut self, modifiers: &KeyModifiers) {
+        self.cursor_move_page(true, modifiers);
+    }
+
+    pub fn cursor_move_page_down(&mut self, modifiers: &KeyModifiers) {
+        self.cursor_move_page(false, modifiers);
+    }
+
+    /// Move the cursor by the number of visible rows, and scroll the view
+    /// by the same number of lines, so the cursor stays at the same place
+    /// on the screen unless it hits the beginning or the end of the file
+    fn cursor_move_page(&mut self, is_up: bool, modifiers: &KeyModifiers) {
+        if !self.should_show_cursor {
+            return;
         }
 
-        let line_len = self.get_line_len(self.cursor_line - 1);
+        self.start_selection(modifiers);
+
+        let (line_before, scroll_offset_before) = (self.cursor_line, self.editor_scroll_offset);
+        for _ in 0..self.editor_lines_num.max(1) {
+            self.cursor_move_visual_row(is_up);
+        }
 
-        if self.vertical_offset_target < line_len + 1 {
-            self.cursor_column = self.vertical_offset_target;
+        let lines_moved = self.cursor_line.abs_diff(line_before);
+        let max_offset = self.lines.len_lines().saturating_sub(self.editor_lines_num);
+        self.editor_scroll_offset = if is_up {
+            scroll_offset_before.saturating_sub(lines_moved)
         } else {
-            self.cursor_column = line_len + 1;
+            (scroll_offset_before + lines_moved).min(max_offset)
+        };
+        self.handle_cursor_scrolling();
+
+        self.adjust_selection();
+    }
+
+    pub fn cursor_move_file_start(&mut self, modifiers: &KeyModifiers) {
+        if self.should_show_cursor {
+            self.start_selection(modifiers);
+
+            self.vertical_offset_target = 0;
+            self.cursor_line = 1;
+            self.cursor_column = 1;
+            self.handle_cursor_scrolling();
+
+            self.adjust_selection();
+        }
+    }
+
+    pub fn cursor_move_file_end(&mut self, modifiers: &KeyModifiers) {
+        if self.should_show_cursor {
+            self.start_selection(modifiers);
+
+            self.vertical_offset_target = 0;
+            self.cursor_line = self.lines.len_lines().max(1);
+            self.cursor_column = self.get_line_len(self.cursor_line - 1) + 1;
+            self.handle_cursor_scrolling();
+
+            self.adjust_selection();
+        }
+    }
+
+    pub fn handle_cursor_scrolling(&mut self) {
+        if self.cursor_line < self.editor_scroll_offset + 1 {
+            self.editor_scroll_offset = self.cursor_line - 1;
+        } else if self.cursor_line > self.editor_scroll_offset + self.editor_lines_num {
+            self.editor_scroll_offset = self.cursor_line - self.editor_lines_num;
         }
+
+        self.handle_wrapped_cursor_scrolling();
     }
 
     pub(super) fn get_line_len(&self, index: usize) -> usize {
         // this will break if index is higher than 65535, which is not impossible
         // TODO: switch to `usize` everywhere, and only use `u16` for actual terminal
-        match self.lines.get(index) {
-            Some(line) => line.len(),
-            None => 0,
-        }
+        self.lines.line_len(index)
     }
 }
 
@@ -159,6 +206,76 @@ impl UIState {
 mod tests {
     use super::*;
 
+    #[test]
+    fn goes_to_line() {
+        let lines = vec![vec!['a'], vec!['b'], vec!['c'], vec!['d'], vec!['e']];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 4);
+
+        ui_state.go_to_line(4, 1);
+
+        assert_eq!(ui_state.cursor_line, 4);
+        assert_eq!(ui_state.editor_scroll_offset, 2);
+
+        ui_state.go_to_line(100, 1);
+        assert_eq!(ui_state.cursor_line, 5);
+
+        ui_state.go_to_line(0, 1);
+        assert_eq!(ui_state.cursor_line, 1);
+        assert_eq!(ui_state.editor_scroll_offset, 0);
+    }
+
+    #[test]
+    fn goes_to_column() {
+        let lines = vec!["first".chars().collect(), "e\u{301}x".chars().collect()];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 4);
+
+        ui_state.go_to_line(1, 4);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 4));
+
+        ui_state.go_to_line(1, 100);
+        assert_eq!(ui_state.cursor_column, 6);
+
+        // in the middle of `e` with an accent
+        ui_state.go_to_line(2, 2);
+        assert_eq!(ui_state.cursor_column, 1);
+    }
+
+    #[test]
+    fn moves_by_pages() {
+        let lines = (0..10)
+            .map(|index| vec![char::from(b'a' + index)])
+            .collect();
+        let mut ui_state = UIState::new(5, lines);
+        // 4 visible lines
+        ui_state.set_editor_offset(30, 0, 6);
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        ui_state.cursor_move_page_down(&KeyModifiers::NONE);
+        assert_eq!(ui_state.cursor_line, 6);
+        assert_eq!(ui_state.editor_scroll_offset, 4);
+
+        ui_state.cursor_move_page_down(&KeyModifiers::SHIFT);
+        assert_eq!(ui_state.cursor_line, 10);
+        assert_eq!(ui_state.editor_scroll_offset, 6);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((6, 1), (10, 1)));
+
+        ui_state.cursor_move_page_up(&KeyModifiers::NONE);
+        assert_eq!(ui_state.cursor_line, 6);
+        assert_eq!(ui_state.editor_scroll_offset, 2);
+        assert!(ui_state.selection.is_none());
+
+        ui_state.cursor_move_file_end(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (10, 2));
+        ui_state.cursor_move_file_start(&KeyModifiers::SHIFT);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 1));
+        assert_eq!(ui_state.editor_scroll_offset, 0);
+        let selection = ui_state.selection.as_ref().unwrap();
+        assert_eq!((selection.start, selection.end), ((10, 2), (1, 1)));
+    }
+
     #[test]
     fn cannot_move_negative() {
         let lines = vec![
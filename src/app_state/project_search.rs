@@ -0,0 +1,555 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use ignore::{WalkBuilder, WalkState};
+use regex::Regex;
+
+use super::app::{AppState, Focus};
+use super::file_format::UTF8_BOM;
+use super::search::{SearchMatch, SearchQuery, find_in_line};
+
+/// After that many hits we stop searching, nobody is going to scroll through them
+const MAX_HITS: usize = 10_000;
+/// Files with a zero byte at the beginning are treated as binary, like git does
+const BINARY_CHECK_LEN: usize = 8000;
+/// Minified files can have very long lines, we don't need all of it for a preview
+const MAX_PREVIEW_LEN: usize = 300;
+
+/// A single match; `line` is counted from `1` and `columns` from `0`
+pub struct ProjectSearchHit {
+    pub line: usize,
+    pub columns: Range<usize>,
+    /// Text of the line, possibly cut at the end
+    pub preview: String,
+}
+
+/// All hits in a single file, files are shown as groups
+pub struct ProjectSearchFile {
+    pub path: PathBuf,
+    pub hits: Vec<ProjectSearchHit>,
+}
+
+enum ProjectSearchMessage {
+    File(ProjectSearchFile),
+    Done,
+}
+
+/// Rows of the results list: a header for every file, followed by its hits
+pub enum ProjectSearchRow<'a> {
+    File(&'a ProjectSearchFile),
+    /// The hit together with its index among all hits
+    Hit(&'a ProjectSearchHit, usize),
+}
+
+/// The search panel, shown instead of the file tree while it is opened
+pub struct ProjectSearch {
+    pub query: SearchQuery,
+    pub cursor: usize,
+    /// Results arrive in the order the files were searched, not sorted
+    pub files: Vec<ProjectSearchFile>,
+    /// Index among the hits of all files
+    pub selected: usize,
+    pub scroll_offset: usize,
+    /// Set when the query is edited, so `Enter` starts a new search instead
+    /// of opening the selected hit
+    pub is_query_changed: bool,
+    pub is_running: bool,
+    receiver: Option<Receiver<ProjectSearchMessage>>,
+    /// Shared with the background threads, so we can stop them early
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl ProjectSearch {
+    fn new(query: SearchQuery) -> Self {
+        ProjectSearch {
+            cursor: query.pattern.len(),
+            query,
+            files: vec![],
+            selected: 0,
+            scroll_offset: 0,
+            is_query_changed: true,
+            is_running: false,
+            receiver: None,
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn get_hits_number(&self) -> usize {
+        self.files.iter().map(|file| file.hits.len()).sum()
+    }
+
+    pub fn get_rows(&self) -> Vec<ProjectSearchRow<'_>> {
+        let mut rows = vec![];
+        let mut hit_index = 0;
+
+        for file in &self.files {
+            rows.push(ProjectSearchRow::File(file));
+
+            for hit in &file.hits {
+                rows.push(ProjectSearchRow::Hit(hit, hit_index));
+                hit_index += 1;
+            }
+        }
+
+        rows
+    }
+
+    fn get_selected_hit(&self) -> Option<(&Path, &ProjectSearchHit)> {
+        self.files
+            .iter()
+            .flat_map(|file| file.hits.iter().map(|hit| (file.path.as_path(), hit)))
+            .nth(self.selected)
+    }
+
+    /// Keep the selected hit visible; its row index is larger than the hit
+    /// index because of the file headers
+    pub fn handle_scrolling(&mut self, height: usize) {
+        if height == 0 {
+            return;
+        }
+
+        let rows = self.get_rows();
+        let selected_row = rows
+            .iter()
+            .position(
+                |row| matches!(row, ProjectSearchRow::Hit(_, index) if *index == self.selected),
+            )
+            .unwrap_or_default();
+
+        // the header is shown too when we go up to the first hit of a file
+        let first_row = match selected_row.checked_sub(1).map(|row| &rows[row]) {
+            Some(ProjectSearchRow::File(_)) => selected_row - 1,
+            _ => selected_row,
+        };
+
+        if first_row < self.scroll_offset {
+            self.scroll_offset = first_row;
+        } else if selected_row >= self.scroll_offset + height {
+            self.scroll_offset = selected_row + 1 - height;
+        }
+    }
+
+    fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ProjectSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl AppState {
+    /// Open the search panel, or focus it if it is already opened. The
+    /// pattern of the last search in the file is used as the initial one.
+    pub fn open_project_search(&mut self) {
+        if self.project_search.is_none() {
+            self.project_search = Some(ProjectSearch::new(self.search.clone()));
+        }
+
+        self.focus = Focus::ProjectSearch;
+    }
+
+    pub fn close_project_search(&mut self) {
+        // dropping the panel stops the running search
+        self.project_search = None;
+        self.focus = Focus::FileTree;
+    }
+
+    pub fn project_search_insert_char(&mut self, character: char) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search
+                .query
+                .pattern
+                .insert(project_search.cursor, character);
+            project_search.cursor += 1;
+            project_search.is_query_changed = true;
+        }
+    }
+
+    pub fn project_search_remove_previous_char(&mut self) {
+        if let Some(project_search) = &mut self.project_search
+            && project_search.cursor > 0
+        {
+            project_search.cursor -= 1;
+            project_search.query.pattern.remove(project_search.cursor);
+            project_search.is_query_changed = true;
+        }
+    }
+
+    pub fn project_search_move_left(&mut self) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search.cursor = project_search.cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn project_search_move_right(&mut self) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search.cursor =
+                (project_search.cursor + 1).min(project_search.query.pattern.len());
+        }
+    }
+
+    pub fn project_search_toggle_case_sensitive(&mut self) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search.query.case_sensitive = !project_search.query.case_sensitive;
+            project_search.is_query_changed = true;
+        }
+    }
+
+    pub fn project_search_toggle_regex(&mut self) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search.query.use_regex = !project_search.query.use_regex;
+            project_search.is_query_changed = true;
+        }
+    }
+
+    pub fn project_search_move_up(&mut self) {
+        if let Some(project_search) = &mut self.project_search {
+            project_search.selected = project_search.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn project_search_move_down(&mut self) {
+        if let Some(project_search) = &mut self.project_search
+            && project_search.selected + 1 < project_search.get_hits_number()
+        {
+            project_search.selected += 1;
+        }
+    }
+
+    /// Start a new search if the query was changed, otherwise open the hit
+    pub fn project_search_submit(&mut self) {
+        let Some(project_search) = &self.project_search else {
+            return;
+        };
+
+        if project_search.is_query_changed || project_search.files.is_empty() {
+            self.start_project_search();
+        } else {
+            self.project_search_open_selected();
+        }
+    }
+
+    /// Walk the working directory in background threads; results are picked
+    /// up by `receive_project_search_results`
+    pub fn start_project_search(&mut self) {
+        let Some(project_search) = &mut self.project_search else {
+            return;
+        };
+
+        if project_search.query.pattern.is_empty() {
+            return;
+        }
+
+        let regex = match project_search.query.to_regex() {
+            Ok(regex) => regex,
+            Err(error) => {
+                self.status_message = Some(error);
+                return;
+            }
+        };
+
+        project_search.cancel();
+
+        let (sender, receiver) = mpsc::channel();
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+
+        project_search.files = vec![];
+        project_search.selected = 0;
+        project_search.scroll_offset = 0;
+        project_search.is_query_changed = false;
+        project_search.is_running = true;
+        project_search.receiver = Some(receiver);
+        project_search.is_cancelled = Arc::clone(&is_cancelled);
+
+        let root = self.working_directory.clone();
+        thread::spawn(move || search_directory(&root, &regex, &sender, &is_cancelled));
+
+        self.status_message = Some(String::from("Searching..."));
+    }
+
+    /// Take everything found so far, without waiting; returns `true` if
+    /// anything changed and the screen needs to be redrawn
+    pub fn receive_project_search_results(&mut self) -> bool {
+        let Some(project_search) = &mut self.project_search else {
+            return false;
+        };
+        let Some(receiver) = &project_search.receiver else {
+            return false;
+        };
+
+        let mut is_changed = false;
+
+        loop {
+            match receiver.try_recv() {
+                Ok(ProjectSearchMessage::File(file)) => {
+                    project_search.files.push(file);
+                    is_changed = true;
+                }
+                Ok(ProjectSearchMessage::Done) | Err(TryRecvError::Disconnected) => {
+                    project_search.is_running = false;
+                    project_search.receiver = None;
+
+                    let hits_number = project_search.get_hits_number();
+                    let files_number = project_search.files.len();
+                    let limit_note = if hits_number >= MAX_HITS {
+                        " (stopped at the limit)"
+                    } else {
+                        ""
+                    };
+                    self.status_message = Some(format!(
+                        "Found {hits_number} matches in {files_number} files{limit_note}"
+                    ));
+
+                    return true;
+                }
+                Err(TryRecvError::Empty) => return is_changed,
+            }
+        }
+    }
+
+    pub fn is_project_search_running(&self) -> bool {
+        self.project_search
+            .as_ref()
+            .is_some_and(|project_search| project_search.is_running)
+    }
+
+    /// Open the file of the selected hit with the match selected
+    pub fn project_search_open_selected(&mut self) {
+        let Some((path, hit)) = self
+            .project_search
+            .as_ref()
+            .and_then(ProjectSearch::get_selected_hit)
+        else {
+            return;
+        };
+
+        let path = path.to_path_buf();
+        let found = SearchMatch {
+            line: hit.line,
+            columns: hit.columns.clone(),
+        };
+
+        if let Err(error) = self.open_file(path) {
+            self.status_message = Some(format!("Could not open the file: {error}"));
+            return;
+        }
+
+        // the file could be changed since we searched it
+        if let Some(buffer) = self.get_active_buffer_mut()
+            && found.line <= buffer.ui_state.lines.len_lines()
+        {
+            let line_len = buffer.ui_state.lines.line_len(found.line - 1);
+            let found = SearchMatch {
+                columns: found.columns.start.min(line_len)..found.columns.end.min(line_len),
+                ..found
+            };
+            buffer.ui_state.select_match(&found);
+        }
+    }
+}
+
+/// Runs in a background thread: every file is searched in parallel and sent
+/// as soon as it is done. `.gitignore` files and hidden files are respected.
+fn search_directory(
+    root: &Path,
+    regex: &Regex,
+    sender: &Sender<ProjectSearchMessage>,
+    is_cancelled: &Arc<AtomicBool>,
+) {
+    let hits_number = Arc::new(AtomicUsize::new(0));
+
+    WalkBuilder::new(root)
+        .require_git(false)
+        .build_parallel()
+        .run(|| {
+            let regex = regex.clone();
+            let sender = sender.clone();
+            let is_cancelled = Arc::clone(is_cancelled);
+            let hits_number = Arc::clone(&hits_number);
+
+            Box::new(move |entry| {
+                if is_cancelled.load(Ordering::Relaxed)
+                    || hits_number.load(Ordering::Relaxed) >= MAX_HITS
+                {
+                    return WalkState::Quit;
+                }
+
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+                {
+                    return WalkState::Continue;
+                }
+
+                if let Some(file) = search_file(entry.path(), &regex) {
+                    hits_number.fetch_add(file.hits.len(), Ordering::Relaxed);
+
+                    // the panel was closed, nobody is listening anymore
+                    if sender.send(ProjectSearchMessage::File(file)).is_err() {
+                        return WalkState::Quit;
+                    }
+                }
+
+                WalkState::Continue
+            })
+        });
+
+    let _ = sender.send(ProjectSearchMessage::Done);
+}
+
+/// `None` if there are no hits, or the file is binary or can't be read
+fn search_file(path: &Path, regex: &Regex) -> Option<ProjectSearchFile> {
+    let bytes = fs::read(path).ok()?;
+    if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+        return None;
+    }
+    let text = String::from_utf8(bytes).ok()?;
+    // the editor does not show the BOM, so columns of the first line would
+    // be off by one otherwise
+    let text = text.strip_prefix(UTF8_BOM).unwrap_or(&text);
+
+    let mut hits = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let preview: String = chars.iter().take(MAX_PREVIEW_LEN).collect();
+
+        for columns in find_in_line(&chars, regex) {
+            hits.push(ProjectSearchHit {
+                line: index + 1,
+                columns,
+                preview: preview.clone(),
+            });
+        }
+    }
+
+    if hits.is_empty() {
+        return None;
+    }
+
+    Some(ProjectSearchFile {
+        path: path.to_path_buf(),
+        hits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils;
+    use std::time::{Duration, Instant};
+
+    fn create_test_directory() -> PathBuf {
+        let directory = test_utils::create_test_directory("project-search");
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::create_dir_all(directory.join("target")).unwrap();
+
+        fs::write(directory.join(".gitignore"), "target/\n").unwrap();
+        fs::write(
+            directory.join("src/main.rs"),
+            "fn main() {\n    todo!();\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("src/lib.rs"),
+            "// TODO: one\n// todo: two todo\n",
+        )
+        .unwrap();
+        fs::write(directory.join("target/build.rs"), "todo!()\n").unwrap();
+        fs::write(directory.join("image.png"), b"todo\0\x89PNG").unwrap();
+
+        directory
+    }
+
+    fn wait_for_results(app_state: &mut AppState) {
+        let start = Instant::now();
+        while app_state.is_project_search_running() {
+            app_state.receive_project_search_results();
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn searches_files_respecting_gitignore() {
+        let directory = create_test_directory();
+        let mut app_state = AppState::new(directory.clone());
+
+        app_state.open_project_search();
+        for character in "todo".chars() {
+            app_state.project_search_insert_char(character);
+        }
+        app_state.project_search_submit();
+        wait_for_results(&mut app_state);
+
+        let project_search = app_state.project_search.as_mut().unwrap();
+        project_search.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let paths: Vec<_> = project_search
+            .files
+            .iter()
+            .map(|file| file.path.strip_prefix(&directory).unwrap())
+            .collect();
+        assert_eq!(paths, [Path::new("src/lib.rs"), Path::new("src/main.rs")]);
+        assert_eq!(project_search.get_hits_number(), 4);
+
+        let hit = &project_search.files[1].hits[0];
+        assert_eq!((hit.line, hit.columns.clone()), (2, 4..8));
+        assert_eq!(hit.preview, "    todo!();");
+
+        // the query is not changed, so the selected hit is opened
+        project_search.selected = 3;
+        app_state.project_search_submit();
+
+        let ui_state = &app_state.get_active_buffer().unwrap().ui_state;
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (2, 9));
+        assert!(app_state.focus == Focus::Editor);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn skips_bom_in_columns() {
+        let directory = test_utils::create_test_directory("search-bom");
+        let path = directory.join("bom.txt");
+        fs::write(&path, "\u{FEFF}a todo\n").unwrap();
+
+        let regex = Regex::new("todo").unwrap();
+        let file = search_file(&path, &regex).unwrap();
+        assert_eq!(file.hits[0].columns, 2..6);
+        assert_eq!(file.hits[0].preview, "a todo");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn reports_invalid_regex() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+
+        app_state.open_project_search();
+        app_state.project_search_toggle_regex();
+        app_state.project_search_insert_char('(');
+        app_state.project_search_submit();
+
+        assert!(!app_state.is_project_search_running());
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Invalid regular expression")
+        );
+    }
+}
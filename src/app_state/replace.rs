@@ -0,0 +1,314 @@
+use std::borrow::Cow;
+
+use regex::{NoExpand, Regex};
+
+use super::app::AppState;
+use super::editor::UIState;
+use super::search::{SearchDirection, find_in_line};
+use super::undo_redo::{LineReplacement, LinesChange, UndoAction, UndoRedo};
+
+/// Replace all matches in the line; with regular expressions, `$1` or
+/// `${name}` in the replacement are substituted by capture groups
+fn replace_in_line<'a>(
+    line: &'a str,
+    regex: &Regex,
+    replacement: &str,
+    use_regex: bool,
+) -> Cow<'a, str> {
+    if use_regex {
+        regex.replace_all(line, replacement)
+    } else {
+        regex.replace_all(line, NoExpand(replacement))
+    }
+}
+
+impl UIState {
+    /// Replace the whole line, used for replacements and their undo/redo
+    pub fn set_line_text(&mut self, line: usize, text: &str) {
+        let line_len = self.get_line_len(line - 1);
+        // other cursors could end up outside of the line
+        self.clear_extra_cursors();
+
+        self.delete_range((line, 1), (line, line_len + 1));
+        self.lines.insert(line - 1, 0, text);
+        self.is_dirty = true;
+    }
+
+    /// Lines which would change after replacing all matches, as the preview
+    pub fn count_lines_to_replace(
+        &self,
+        regex: &Regex,
+        replacement: &str,
+        use_regex: bool,
+    ) -> usize {
+        (0..self.lines.len_lines())
+            .filter(|index| {
+                let line: String = self.lines.line_chars(*index).collect();
+                replace_in_line(&line, regex, replacement, use_regex) != line
+            })
+            .count()
+    }
+
+    /// Replace all matches at once, so a single undo reverts everything.
+    /// Returns the number of changed lines.
+    fn replace_all(
+        &mut self,
+        regex: &Regex,
+        replacement: &str,
+        use_regex: bool,
+        undo_redo: &mut UndoRedo,
+    ) -> usize {
+        let cursor_before = (self.cursor_line, self.cursor_column);
+        let selection_before = self.get_selection_ends();
+        let mut replaced_lines = vec![];
+
+        for line in 1..=self.lines.len_lines() {
+            let old_text: String = self.lines.line_chars(line - 1).collect();
+            let new_text = replace_in_line(&old_text, regex, replacement, use_regex);
+
+            if new_text != old_text {
+                let new_text = new_text.into_owned();
+                self.set_line_text(line, &new_text);
+                replaced_lines.push(LineReplacement {
+                    line,
+                    old_text,
+                    new_text,
+                });
+            }
+        }
+
+        if replaced_lines.is_empty() {
+            return 0;
+        }
+
+        // the line might be shorter now
+        self.selection = None;
+        self.cursor_column = self
+            .cursor_column
+            .min(self.get_line_len(self.cursor_line - 1) + 1);
+
+        let replaced_lines_number = replaced_lines.len();
+        undo_redo.add_undo_action(UndoAction::Replace(LinesChange {
+            lines: replaced_lines,
+            cursor_before,
+            cursor_after: (self.cursor_line, self.cursor_column),
+            selection_before,
+            selection_after: None,
+        }));
+
+        replaced_lines_number
+    }
+
+    /// Replace the selected text if it is exactly a match. The cursor is put
+    /// after the inserted text, and `false` means there was nothing to replace.
+    fn replace_selected_match(
+        &mut self,
+        regex: &Regex,
+        replacement: &str,
+        use_regex: bool,
+        undo_redo: &mut UndoRedo,
+    ) -> bool {
+        let Some(selection) = &self.selection else {
+            return false;
+        };
+        let (line, start_column) = selection.start.min(selection.end);
+        let (end_line, end_column) = selection.start.max(selection.end);
+        if line != end_line || line > self.lines.len_lines() {
+            return false;
+        }
+
+        let columns = start_column - 1..end_column - 1;
+        let chars = self.lines.get_line(line - 1);
+        if !find_in_line(&chars, regex).contains(&columns) {
+            return false;
+        }
+
+        // replacing only the match itself would break patterns like `^foo`,
+        // so we run the regex on the whole line and pick the same match
+        let old_text = String::from_iter(&chars);
+        let start_byte = chars[..columns.start].iter().map(|c| c.len_utf8()).sum();
+        let Some(captures) = regex.captures_iter(&old_text).find(|captures| {
+            captures
+                .get(0)
+                .is_some_and(|found| found.start() == start_byte)
+        }) else {
+            return false;
+        };
+        let found = captures.get(0).expect("Group 0 is always present");
+
+        let mut inserted = String::new();
+        if use_regex {
+            captures.expand(replacement, &mut inserted);
+        } else {
+            inserted.push_str(replacement);
+        }
+
+        let new_text = format!(
+            "{}{inserted}{}",
+            &old_text[..found.start()],
+            &old_text[found.end()..]
+        );
+
+        let cursor_before = (self.cursor_line, self.cursor_column);
+        let selection_before = self.get_selection_ends();
+        self.set_line_text(line, &new_text);
+        self.selection = None;
+        self.cursor_line = line;
+        self.cursor_column = columns.start + inserted.chars().count() + 1;
+
+        undo_redo.add_undo_action(UndoAction::Replace(LinesChange {
+            lines: vec![LineReplacement {
+                line,
+                old_text,
+                new_text,
+            }],
+            cursor_before,
+            cursor_after: (self.cursor_line, self.cursor_column),
+            selection_before,
+            selection_after: None,
+        }));
+
+        true
+    }
+}
+
+impl AppState {
+    pub fn get_replacement(&self) -> String {
+        String::from_iter(&self.replacement)
+    }
+
+    /// Regex of the current search, or the error shown to the user
+    fn get_search_regex(&self) -> Result<Regex, String> {
+        if self.search.pattern.is_empty() {
+            return Err(String::from("Nothing to search for"));
+        }
+
+        self.search.to_regex()
+    }
+
+    /// Replace the selected match and go to the next one; if the selection
+    /// is not a match, we only go to the next one, so it can be checked first
+    pub fn replace_next(&mut self) {
+        let regex = match self.get_search_regex() {
+            Ok(regex) => regex,
+            Err(error) => {
+                self.status_message = Some(error);
+                return;
+            }
+        };
+        let replacement = self.get_replacement();
+        let use_regex = self.search.use_regex;
+
+        if let Some(buffer) = self.buffers.get_mut(self.active_buffer) {
+            buffer.ui_state.replace_selected_match(
+                &regex,
+                &replacement,
+                use_regex,
+                &mut buffer.undo_redo,
+            );
+        }
+
+        self.find_next(SearchDirection::Forward);
+    }
+
+    pub fn replace_all(&mut self) {
+        let regex = match self.get_search_regex() {
+            Ok(regex) => regex,
+            Err(error) => {
+                self.status_message = Some(error);
+                return;
+            }
+        };
+        let replacement = self.get_replacement();
+        let use_regex = self.search.use_regex;
+
+        let Some(buffer) = self.buffers.get_mut(self.active_buffer) else {
+            return;
+        };
+        let replaced_lines =
+            buffer
+                .ui_state
+                .replace_all(&regex, &replacement, use_regex, &mut buffer.undo_redo);
+
+        self.status_message = Some(match replaced_lines {
+            0 => String::from("Nothing to replace"),
+            1 => String::from("Replaced matches on 1 line"),
+            lines => format!("Replaced matches on {lines} lines"),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::search::SearchQuery;
+    use crate::app_state::test_utils::lines;
+
+    fn regex_query(pattern: &str) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.chars().collect(),
+            case_sensitive: true,
+            use_regex: true,
+        }
+    }
+
+    #[test]
+    fn replaces_all_with_capture_groups_in_one_undo_step() {
+        let mut undo_redo = UndoRedo::new();
+        let mut ui_state = UIState::new(5, lines("let a = 1;\nfoo\nlet bb = 22;"));
+        ui_state.set_editor_offset(30, 0, 50);
+        let regex = regex_query(r"let (\w+) = (\d+)").to_regex().unwrap();
+
+        assert_eq!(
+            ui_state.count_lines_to_replace(&regex, "const $1: i32 = $2", true),
+            2
+        );
+
+        let replaced = ui_state.replace_all(&regex, "const $1: i32 = $2", true, &mut undo_redo);
+
+        assert_eq!(replaced, 2);
+        assert_eq!(ui_state.lines.line_to_string(0), "const a: i32 = 1;");
+        assert_eq!(ui_state.lines.line_to_string(2), "const bb: i32 = 22;");
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(0), "let a = 1;");
+        assert_eq!(ui_state.lines.line_to_string(1), "foo");
+        assert_eq!(ui_state.lines.line_to_string(2), "let bb = 22;");
+
+        undo_redo.redo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(2), "const bb: i32 = 22;");
+    }
+
+    #[test]
+    fn replaces_plain_text_literally() {
+        let mut undo_redo = UndoRedo::new();
+        let mut ui_state = UIState::new(5, lines("a.b axb"));
+        let query = SearchQuery {
+            use_regex: false,
+            ..regex_query("a.b")
+        };
+
+        ui_state.replace_all(&query.to_regex().unwrap(), "$0", false, &mut undo_redo);
+
+        assert_eq!(ui_state.lines.line_to_string(0), "$0 axb");
+    }
+
+    #[test]
+    fn replaces_only_the_selected_match() {
+        let mut undo_redo = UndoRedo::new();
+        let mut ui_state = UIState::new(5, lines("foo foo\nfoo"));
+        let regex = regex_query("f(o+)").to_regex().unwrap();
+
+        ui_state.set_selection((1, 5), (1, 8));
+        assert!(ui_state.replace_selected_match(&regex, "b$1", true, &mut undo_redo));
+        assert_eq!(ui_state.lines.line_to_string(0), "foo boo");
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 8));
+
+        // the selection is not a match anymore
+        ui_state.set_selection((1, 1), (1, 3));
+        assert!(!ui_state.replace_selected_match(&regex, "b$1", true, &mut undo_redo));
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.line_to_string(0), "foo foo");
+    }
+}
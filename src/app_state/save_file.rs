@@ -0,0 +1,200 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::app::{AppState, Config};
+use super::editor::UIState;
+use super::undo_redo::UndoRedo;
+
+impl AppState {
+    /// Write current lines back to the opened file. The result is reported
+    /// in the status bar, so the editor keeps working even if saving failed.
+    pub fn save_file(&mut self) {
+        let Some(buffer) = self.get_active_buffer() else {
+            self.status_message = Some("There is no opened file to save".into());
+            return;
+        };
+        let config = self.get_buffer_config(buffer);
+
+        let Some(buffer) = self.buffers.get_mut(self.active_buffer) else {
+            return;
+        };
+
+        buffer
+            .ui_state
+            .apply_save_config(&config, &mut buffer.undo_redo);
+        let content = buffer.ui_state.get_file_content();
+
+        match write_file_atomically(&buffer.file_path, &content) {
+            Ok(_) => {
+                buffer.ui_state.is_dirty = false;
+                self.status_message = Some(format!("Saved {}", get_file_name(&buffer.file_path)));
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not save the file: {error}"));
+            }
+        }
+    }
+}
+
+impl UIState {
+    /// Serialize all lines into a single string which can be written to disk,
+    /// preserving the original file format
+    pub fn get_file_content(&self) -> String {
+        self.file_format.serialize(self.lines.as_ref())
+    }
+
+    /// Line ending, final newline and trailing whitespace can be enforced by
+    /// the config, e.g. from `.editorconfig`; otherwise the file keeps its own
+    /// format. Whitespace is trimmed in the buffer itself, so it can be undone.
+    pub fn apply_save_config(&mut self, config: &Config, undo_redo: &mut UndoRedo) {
+        if let Some(line_ending) = config.end_of_line {
+            self.file_format.line_ending = line_ending;
+            self.lines.set_line_endings(None);
+        }
+        if let Some(insert_final_newline) = config.insert_final_newline {
+            self.file_format.has_final_newline = insert_final_newline;
+        }
+        if config.trim_trailing_whitespace {
+            self.trim_trailing_whitespace(undo_redo);
+        }
+    }
+}
+
+pub fn get_file_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+/// We never write into the original file directly: if the editor crashes or
+/// the disk is full in the middle of writing, the file would be truncated.
+/// Instead, we write everything into a temporary file in the same directory
+/// (so that it is on the same filesystem) and atomically rename it.
+fn write_file_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let temp_path = get_temp_file_path(path);
+
+    let result =
+        write_temp_file(path, &temp_path, content).and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        // the temporary file might be left in case of a failure, it is not
+        // important if we can't remove it
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+fn write_temp_file(original_path: &Path, temp_path: &Path, content: &str) -> io::Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+
+    // keep the same permissions, otherwise executable scripts would lose their flag
+    if let Ok(metadata) = fs::metadata(original_path) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+
+    Ok(())
+}
+
+fn get_temp_file_path(path: &Path) -> PathBuf {
+    let file_name = get_file_name(path);
+    let temp_name = format!(".{file_name}.love-{}.tmp", std::process::id());
+
+    path.with_file_name(temp_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::file_format::LineEnding;
+    use crate::app_state::test_utils::create_test_directory;
+    use crate::app_state::test_utils::create_ui_state;
+    use crossterm::event::KeyModifiers;
+
+    #[test]
+    fn serializes_lines_with_newlines() {
+        let lines = vec![vec!['H', 'e', 'l', 'l', 'o'], vec![], vec!['E', 'n', 'd']];
+        let ui_state = UIState::new(5, lines);
+
+        assert_eq!(ui_state.get_file_content(), "Hello\n\nEnd\n");
+    }
+
+    #[test]
+    fn edits_mark_buffer_as_dirty() {
+        let mut undo_redo = UndoRedo::new();
+        let lines = vec![vec!['H', 'e', 'l', 'l', 'o'], vec![], vec!['E', 'n', 'd']];
+        let mut ui_state = UIState::new(5, lines);
+        ui_state.set_editor_offset(30, 0, 50);
+
+        assert!(!ui_state.is_dirty);
+
+        ui_state.cursor_move_right(&KeyModifiers::NONE);
+
+        assert!(!ui_state.is_dirty);
+
+        ui_state.insert_character('y', &mut undo_redo);
+
+        assert!(ui_state.is_dirty);
+    }
+
+    #[test]
+    fn applies_save_config() {
+        let lines = vec![vec!['a', ' ', ' '], vec!['\t'], vec!['b']];
+        let mut ui_state = UIState::new(5, lines);
+        let config = Config {
+            end_of_line: Some(LineEnding::Crlf),
+            insert_final_newline: Some(false),
+            trim_trailing_whitespace: true,
+            ..Config::new()
+        };
+
+        let mut undo_redo = UndoRedo::new();
+        ui_state.go_to_line(1, 4);
+
+        ui_state.apply_save_config(&config, &mut undo_redo);
+
+        assert_eq!(ui_state.get_file_content(), "a\r\n\r\nb");
+        // the editor shows what is written to the disk
+        assert_eq!(ui_state.lines.line_to_string(0), "a");
+        assert_eq!(ui_state.cursor_column, 2);
+
+        undo_redo.undo_action(&mut ui_state);
+        assert_eq!(ui_state.lines.to_text(), "a  \n\t\nb");
+    }
+
+    #[test]
+    fn keeps_all_cursors_when_trimming() {
+        let mut ui_state = create_ui_state("a  \nb  \nc");
+        let mut undo_redo = UndoRedo::new();
+        ui_state.cursor_column = 4;
+        ui_state.add_cursor_vertically(false);
+        ui_state.add_cursor_vertically(false);
+        let config = Config {
+            trim_trailing_whitespace: true,
+            ..Config::new()
+        };
+
+        ui_state.apply_save_config(&config, &mut undo_redo);
+
+        assert_eq!(ui_state.lines.to_text(), "a\nb\nc");
+        assert_eq!(ui_state.get_cursor_positions(), [(3, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn writes_file_atomically() {
+        let directory = create_test_directory("save");
+        let path = directory.join("file.txt");
+        fs::write(&path, "old content\n").unwrap();
+
+        write_file_atomically(&path, "new content\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content\n");
+        assert!(!get_temp_file_path(&path).exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
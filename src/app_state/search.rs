@@ -0,0 +1,627 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use super::app::AppState;
+use super::editor::UIState;
+
+/// What we are looking for; it is kept after the search bar is closed, so
+/// that `find-next` and `find-previous` still work
+#[derive(Clone, Default)]
+pub struct SearchQuery {
+    pub pattern: Vec<char>,
+    pub case_sensitive: bool,
+    /// Treat the pattern as a regular expression instead of plain text
+    pub use_regex: bool,
+}
+
+impl SearchQuery {
+    /// Plain text is escaped, so both kinds of search work the same way.
+    /// Matches never span multiple lines.
+    pub fn to_regex(&self) -> Result<Regex, String> {
+        let pattern = String::from_iter(&self.pattern);
+        let source = if self.use_regex {
+            pattern
+        } else {
+            regex::escape(&pattern)
+        };
+
+        RegexBuilder::new(&source)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|error| match error {
+                regex::Error::Syntax(_) => String::from("Invalid regular expression"),
+                error => error.to_string(),
+            })
+    }
+}
+
+/// State of the search bar while it is opened
+pub struct SearchBar {
+    /// Position in the focused input
+    pub cursor: usize,
+    /// Cursor position when the search started. While typing, we look for
+    /// the first match after it, so the match grows together with the input.
+    origin: (usize, usize),
+    /// Show the replacement input as well
+    pub replacing: bool,
+    pub focused_input: SearchInput,
+    /// `None` while the pattern is empty
+    pub summary: Option<SearchSummary>,
+}
+
+/// Compiled pattern and the numbers shown in the search bar. Counting goes
+/// over the whole file, so it is done again only when the search or the text
+/// changes, and not on every render.
+pub struct SearchSummary {
+    key: SearchSummaryKey,
+    pub regex: Result<Regex, String>,
+    pub matches_number: usize,
+    /// Only counted while replacing
+    pub lines_to_replace: Option<usize>,
+}
+
+#[derive(PartialEq, Eq)]
+struct SearchSummaryKey {
+    pattern: Vec<char>,
+    case_sensitive: bool,
+    use_regex: bool,
+    replacement: Option<Vec<char>>,
+    buffer: usize,
+    version: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SearchInput {
+    Pattern,
+    Replacement,
+}
+
+/// A single occurrence, `line` is counted from `1` and `columns` from `0`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub columns: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+/// Non-overlapping occurrences in the line, in character columns. Empty
+/// matches (like `^`) can't be selected or highlighted, so they are skipped.
+pub fn find_in_line(line: &[char], regex: &Regex) -> Vec<Range<usize>> {
+    let text = String::from_iter(line);
+    let to_char_column = |byte: usize| text[..byte].chars().count();
+
+    regex
+        .find_iter(&text)
+        .filter(|found| !found.is_empty())
+        .map(|found| to_char_column(found.start())..to_char_column(found.end()))
+        .collect()
+}
+
+impl UIState {
+    /// Closest match starting from the position (`1`-based, like the cursor),
+    /// wrapping around the end of the file. The second value tells whether we
+    /// had to wrap around. Going forward, a match starting exactly at the
+    /// position counts; going backward, it does not.
+    pub fn find_match(
+        &self,
+        query: &SearchQuery,
+        (line, column): (usize, usize),
+        direction: SearchDirection,
+    ) -> Option<(SearchMatch, bool)> {
+        let lines_number = self.lines.len_lines();
+        if query.pattern.is_empty() || lines_number == 0 {
+            return None;
+        }
+        let regex = query.to_regex().ok()?;
+
+        let line = line.clamp(1, lines_number);
+        let column = column.saturating_sub(1);
+
+        let find_in = |line_num: usize, columns: Range<usize>| {
+            let matches = find_in_line(&self.lines.get_line(line_num - 1), &regex)
+                .into_iter()
+                .filter(|found| columns.contains(&found.start));
+
+            let found = match direction {
+                SearchDirection::Forward => matches.min_by_key(|found| found.start),
+                SearchDirection::Backward => matches.max_by_key(|found| found.start),
+            };
+
+            found.map(|columns| SearchMatch {
+                line: line_num,
+                columns,
+            })
+        };
+
+        // the line with the position is checked twice: first the part in the
+        // search direction, and after wrapping around, the rest of it
+        match direction {
+            SearchDirection::Forward => find_in(line, column..usize::MAX)
+                .map(|found| (found, false))
+                .or_else(|| {
+                    (line + 1..=lines_number)
+                        .find_map(|line_num| find_in(line_num, 0..usize::MAX))
+                        .map(|found| (found, false))
+                })
+                .or_else(|| {
+                    (1..line)
+                        .find_map(|line_num| find_in(line_num, 0..usize::MAX))
+                        .or_else(|| find_in(line, 0..column))
+                        .map(|found| (found, true))
+                }),
+            SearchDirection::Backward => find_in(line, 0..column)
+                .map(|found| (found, false))
+                .or_else(|| {
+                    (1..line)
+                        .rev()
+                        .find_map(|line_num| find_in(line_num, 0..usize::MAX))
+                        .map(|found| (found, false))
+                })
+                .or_else(|| {
+                    (line + 1..=lines_number)
+                        .rev()
+                        .find_map(|line_num| find_in(line_num, 0..usize::MAX))
+                        .or_else(|| find_in(line, column..usize::MAX))
+                        .map(|found| (found, true))
+                }),
+        }
+    }
+
+    pub fn count_matches(&self, regex: &Regex) -> usize {
+        (0..self.lines.len_lines())
+            .map(|index| find_in_line(&self.lines.get_line(index), regex).len())
+            .sum()
+    }
+
+    /// Select the match with the cursor at its end, like a regular selection
+    pub fn select_match(&mut self, found: &SearchMatch) {
+        self.clear_extra_cursors();
+        self.vertical_offset_target = 0;
+        self.cursor_line = found.line;
+        self.cursor_column = found.columns.end + 1;
+        self.set_selection(
+            (found.line, found.columns.start + 1),
+            (found.line, found.columns.end + 1),
+        );
+        self.handle_cursor_scrolling();
+    }
+
+    /// Text of the selection if it is within a single line
+    pub(super) fn get_single_line_selection(&self) -> Option<Vec<char>> {
+        let selection = self.selection.as_ref()?;
+        let (start_line, start_column) = selection.start.min(selection.end);
+        let (end_line, end_column) = selection.start.max(selection.end);
+
+        if start_line != end_line || start_line > self.lines.len_lines() {
+            return None;
+        }
+
+        let line = self.lines.get_line(start_line - 1);
+        let end_column = (end_column - 1).min(line.len());
+        line.get(start_column - 1..end_column).map(<[char]>::to_vec)
+    }
+}
+
+impl AppState {
+    /// Start typing a new search; the text selected on a single line is used
+    /// as the initial pattern, otherwise the previous one is kept
+    pub fn open_search_bar(&mut self, replacing: bool) {
+        let Some(buffer) = self.get_active_buffer() else {
+            self.status_message = Some("There is no opened file to search in".into());
+            return;
+        };
+
+        let ui_state = &buffer.ui_state;
+        let mut origin = (ui_state.cursor_line, ui_state.cursor_column);
+
+        if let Some(selected_text) = ui_state.get_single_line_selection()
+            && !selected_text.is_empty()
+        {
+            if let Some(selection) = &ui_state.selection {
+                origin = selection.start.min(selection.end);
+            }
+            self.search.pattern = selected_text;
+        }
+
+        self.search_bar = Some(SearchBar {
+            cursor: self.search.pattern.len(),
+            origin,
+            replacing,
+            focused_input: SearchInput::Pattern,
+            summary: None,
+        });
+        self.update_search();
+    }
+
+    pub fn close_search_bar(&mut self) {
+        self.search_bar = None;
+    }
+
+    /// The search bar and the input it is typed into
+    fn get_search_bar_input(&mut self) -> Option<(&mut SearchBar, &mut Vec<char>)> {
+        let search_bar = self.search_bar.as_mut()?;
+        let input = match search_bar.focused_input {
+            SearchInput::Pattern => &mut self.search.pattern,
+            SearchInput::Replacement => &mut self.replacement,
+        };
+
+        Some((search_bar, input))
+    }
+
+    /// Only changes of the pattern move the cursor to a new match
+    fn after_search_bar_input_change(&mut self) {
+        if self
+            .search_bar
+            .as_ref()
+            .is_some_and(|search_bar| search_bar.focused_input == SearchInput::Pattern)
+        {
+            self.update_search();
+        }
+    }
+
+    pub fn search_bar_insert_char(&mut self, character: char) {
+        if let Some((search_bar, input)) = self.get_search_bar_input() {
+            input.insert(search_bar.cursor, character);
+            search_bar.cursor += 1;
+            self.after_search_bar_input_change();
+        }
+    }
+
+    pub fn search_bar_remove_previous_char(&mut self) {
+        if let Some((search_bar, input)) = self.get_search_bar_input()
+            && search_bar.cursor > 0
+        {
+            search_bar.cursor -= 1;
+            input.remove(search_bar.cursor);
+            self.after_search_bar_input_change();
+        }
+    }
+
+    pub fn search_bar_move_left(&mut self) {
+        if let Some((search_bar, _)) = self.get_search_bar_input() {
+            search_bar.cursor = search_bar.cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn search_bar_move_right(&mut self) {
+        if let Some((search_bar, input)) = self.get_search_bar_input() {
+            search_bar.cursor = (search_bar.cursor + 1).min(input.len());
+        }
+    }
+
+    /// Switch between the pattern and the replacement
+    pub fn search_bar_switch_input(&mut self) {
+        let Some(search_bar) = &mut self.search_bar else {
+            return;
+        };
+        if !search_bar.replacing {
+            return;
+        }
+
+        let (focused_input, cursor) = match search_bar.focused_input {
+            SearchInput::Pattern => (SearchInput::Replacement, self.replacement.len()),
+            SearchInput::Replacement => (SearchInput::Pattern, self.search.pattern.len()),
+        };
+        search_bar.focused_input = focused_input;
+        search_bar.cursor = cursor;
+    }
+
+    pub fn toggle_search_case_sensitive(&mut self) {
+        self.search.case_sensitive = !self.search.case_sensitive;
+        if self.search_bar.is_some() {
+            self.update_search();
+        }
+    }
+
+    pub fn toggle_search_regex(&mut self) {
+        self.search.use_regex = !self.search.use_regex;
+        if self.search_bar.is_some() {
+            self.update_search();
+        }
+    }
+
+    /// Look for the first match after the position where the search started;
+    /// if there is none, the cursor goes back there
+    fn update_search(&mut self) {
+        let Some(origin) = self.search_bar.as_ref().map(|search_bar| search_bar.origin) else {
+            return;
+        };
+        let Some(buffer) = self.buffers.get_mut(self.active_buffer) else {
+            return;
+        };
+        let ui_state = &mut buffer.ui_state;
+
+        match ui_state.find_match(&self.search, origin, SearchDirection::Forward) {
+            Some((found, _)) => {
+                ui_state.select_match(&found);
+                self.status_message = None;
+            }
+            None => {
+                ui_state.selection = None;
+                (ui_state.cursor_line, ui_state.cursor_column) = origin;
+                ui_state.handle_cursor_scrolling();
+
+                self.status_message = match self.search.to_regex() {
+                    _ if self.search.pattern.is_empty() => None,
+                    Ok(_) => Some(format!("No matches for \"{}\"", self.get_search_pattern())),
+                    Err(error) => Some(error),
+                };
+            }
+        }
+    }
+
+    /// Jump to the next or the previous match of the last search
+    pub fn find_next(&mut self, direction: SearchDirection) {
+        if self.search.pattern.is_empty() {
+            self.status_message = Some("Nothing to search for".into());
+            return;
+        }
+        let Some(buffer) = self.buffers.get_mut(self.active_buffer) else {
+            return;
+        };
+        let ui_state = &mut buffer.ui_state;
+
+        // the current match is selected, so we need to skip it
+        let position = match &ui_state.selection {
+            Some(selection) => selection.start.min(selection.end),
+            None => (ui_state.cursor_line, ui_state.cursor_column),
+        };
+        let position = match direction {
+            SearchDirection::Forward if ui_state.selection.is_some() => {
+                (position.0, position.1 + 1)
+            }
+            _ => position,
+        };
+
+        match ui_state.find_match(&self.search, position, direction) {
+            Some((found, wrapped)) => {
+                ui_state.select_match(&found);
+                self.status_message = wrapped.then(|| String::from("Search wrapped around"));
+            }
+            None => {
+                self.status_message = match self.search.to_regex() {
+                    Ok(_) => Some(format!("No matches for \"{}\"", self.get_search_pattern())),
+                    Err(error) => Some(error),
+                };
+            }
+        }
+    }
+
+    pub fn get_search_pattern(&self) -> String {
+        String::from_iter(&self.search.pattern)
+    }
+
+    /// Count matches for the search bar again if anything they depend on
+    /// has changed since the last time; called before every render
+    pub fn update_search_summary(&mut self) {
+        let Some(search_bar) = &mut self.search_bar else {
+            return;
+        };
+        let Some(buffer) = self.buffers.get(self.active_buffer) else {
+            search_bar.summary = None;
+            return;
+        };
+        if self.search.pattern.is_empty() {
+            search_bar.summary = None;
+            return;
+        }
+
+        let key = SearchSummaryKey {
+            pattern: self.search.pattern.clone(),
+            case_sensitive: self.search.case_sensitive,
+            use_regex: self.search.use_regex,
+            replacement: search_bar.replacing.then(|| self.replacement.clone()),
+            buffer: self.active_buffer,
+            version: buffer.ui_state.lines.version(),
+        };
+        if search_bar
+            .summary
+            .as_ref()
+            .is_some_and(|summary| summary.key == key)
+        {
+            return;
+        }
+
+        let regex = self.search.to_regex();
+        let ui_state = &buffer.ui_state;
+        let (matches_number, lines_to_replace) = match &regex {
+            Ok(regex) => (
+                ui_state.count_matches(regex),
+                key.replacement.as_ref().map(|replacement| {
+                    let replacement = String::from_iter(replacement);
+                    ui_state.count_lines_to_replace(regex, &replacement, key.use_regex)
+                }),
+            ),
+            Err(_) => (0, None),
+        };
+
+        search_bar.summary = Some(SearchSummary {
+            key,
+            regex,
+            matches_number,
+            lines_to_replace,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::buffer::Buffer;
+    use crate::app_state::test_utils::lines;
+    use crate::app_state::undo_redo::UndoRedo;
+    use std::path::PathBuf;
+
+    fn query(pattern: &str, case_sensitive: bool) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.chars().collect(),
+            case_sensitive,
+            use_regex: false,
+        }
+    }
+
+    fn find(line: &str, query: &SearchQuery) -> Vec<Range<usize>> {
+        let line: Vec<char> = line.chars().collect();
+        find_in_line(&line, &query.to_regex().unwrap())
+    }
+
+    #[test]
+    fn finds_matches_in_line() {
+        assert_eq!(
+            find("Foo foo fOO", &query("foo", false)),
+            vec![0..3, 4..7, 8..11]
+        );
+        assert_eq!(find("Foo foo fOO", &query("foo", true)), vec![4..7]);
+        assert!(find("Foo foo fOO", &query("aa", false)).is_empty());
+        assert_eq!(find("aaaa", &query("aa", true)), vec![0..2, 2..4]);
+
+        // plain text is not treated as a regular expression
+        assert_eq!(find("a.b axb", &query("a.b", true)), vec![0..3]);
+
+        let regex_query = SearchQuery {
+            use_regex: true,
+            ..query("é+|x*", true)
+        };
+        assert_eq!(find("ééx é", &regex_query), vec![0..2, 2..3, 4..5]);
+        assert!(query("(", false).to_regex().is_ok());
+        assert!(
+            SearchQuery {
+                use_regex: true,
+                ..query("(", false)
+            }
+            .to_regex()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn finds_next_and_previous_matches_with_wrapping() {
+        let ui_state = UIState::new(5, lines("one two\nthree\ntwo one"));
+        let two = query("two", true);
+
+        let (found, wrapped) = ui_state
+            .find_match(&two, (1, 1), SearchDirection::Forward)
+            .unwrap();
+        assert_eq!(
+            found,
+            SearchMatch {
+                line: 1,
+                columns: 4..7
+            }
+        );
+        assert!(!wrapped);
+
+        let (found, wrapped) = ui_state
+            .find_match(&two, (1, 6), SearchDirection::Forward)
+            .unwrap();
+        assert_eq!(
+            found,
+            SearchMatch {
+                line: 3,
+                columns: 0..3
+            }
+        );
+        assert!(!wrapped);
+
+        let (found, wrapped) = ui_state
+            .find_match(&two, (3, 2), SearchDirection::Forward)
+            .unwrap();
+        assert_eq!(found.line, 1);
+        assert!(wrapped);
+
+        let (found, wrapped) = ui_state
+            .find_match(&two, (1, 5), SearchDirection::Backward)
+            .unwrap();
+        assert_eq!(found.line, 3);
+        assert!(wrapped);
+
+        assert!(
+            ui_state
+                .find_match(&query("four", false), (1, 1), SearchDirection::Forward)
+                .is_none()
+        );
+        assert_eq!(
+            ui_state.count_matches(&query("ONE", false).to_regex().unwrap()),
+            2
+        );
+    }
+
+    #[test]
+    fn searches_incrementally_and_selects_matches() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        let mut buffer_ui_state = UIState::new(5, lines("abc\nab\nabc"));
+        buffer_ui_state.cursor_line = 2;
+        app_state.buffers.push(Buffer {
+            file_path: PathBuf::from("test.txt"),
+            ui_state: buffer_ui_state,
+            undo_redo: UndoRedo::new(),
+            highlighter: None,
+            editorconfig: Default::default(),
+        });
+
+        app_state.open_search_bar(false);
+        app_state.search_bar_insert_char('a');
+        app_state.search_bar_insert_char('b');
+        assert_eq!(app_state.buffers[0].ui_state.cursor_line, 2);
+
+        app_state.search_bar_insert_char('c');
+        let ui_state = &app_state.buffers[0].ui_state;
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (3, 4));
+        assert_eq!(
+            ui_state.get_single_line_selection(),
+            Some(vec!['a', 'b', 'c'])
+        );
+
+        app_state.find_next(SearchDirection::Forward);
+        assert_eq!(app_state.buffers[0].ui_state.cursor_line, 1);
+        assert_eq!(
+            app_state.status_message.as_deref(),
+            Some("Search wrapped around")
+        );
+
+        app_state.search_bar_insert_char('d');
+        let ui_state = &app_state.buffers[0].ui_state;
+        assert!(ui_state.selection.is_none());
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (2, 1));
+    }
+
+    #[test]
+    fn counts_matches_again_only_after_changes() {
+        let mut app_state = AppState::new(PathBuf::from("."));
+        app_state.buffers.push(Buffer {
+            file_path: PathBuf::from("test.txt"),
+            ui_state: UIState::new(5, lines("ab\nab")),
+            undo_redo: UndoRedo::new(),
+            highlighter: None,
+            editorconfig: Default::default(),
+        });
+
+        app_state.open_search_bar(true);
+        app_state.update_search_summary();
+        assert!(app_state.search_bar.as_ref().unwrap().summary.is_none());
+
+        app_state.search_bar_insert_char('a');
+        app_state.update_search_summary();
+        let summary = app_state.search_bar.as_ref().unwrap().summary.as_ref();
+        assert_eq!(summary.unwrap().matches_number, 2);
+        assert_eq!(summary.unwrap().lines_to_replace, Some(2));
+
+        // a stale number means it was not counted again
+        let summary = app_state.search_bar.as_mut().unwrap().summary.as_mut();
+        summary.unwrap().matches_number = 10;
+        app_state.update_search_summary();
+        let summary = app_state.search_bar.as_ref().unwrap().summary.as_ref();
+        assert_eq!(summary.unwrap().matches_number, 10);
+
+        app_state.buffers[0].ui_state.lines.insert(0, 0, "a");
+        app_state.update_search_summary();
+        let summary = app_state.search_bar.as_ref().unwrap().summary.as_ref();
+        assert_eq!(summary.unwrap().matches_number, 3);
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub enum SelectionType {
     Line,
     To(usize),
@@ -7,6 +8,7 @@ pub enum SelectionType {
     Range(usize, usize),
 }
 
+#[derive(Clone)]
 pub struct Selection {
     pub start: (usize, usize),
     pub end: (usize, usize),
@@ -0,0 +1,31 @@
+//! Helpers shared by the tests of the app state modules
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use super::editor::UIState;
+
+pub fn lines(text: &str) -> Vec<Vec<char>> {
+    text.lines().map(|line| line.chars().collect()).collect()
+}
+
+/// The editor is at the top left corner and 20 lines high
+pub fn create_ui_state(text: &str) -> UIState {
+    let mut ui_state = UIState::new(1, lines(text));
+    ui_state.set_editor_offset(0, 0, 20);
+    ui_state
+}
+
+pub fn get_text(ui_state: &UIState) -> String {
+    ui_state.lines.to_text()
+}
+
+/// An empty directory which is unique for the test and the process
+pub fn create_test_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("love-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    directory
+}
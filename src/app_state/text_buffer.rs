@@ -0,0 +1,448 @@
+use ropey::Rope;
+
+use super::file_format::LineEnding;
+
+/// Storage for the text of a single file. All editing actions, undo/redo and
+/// rendering go through this trait, so the underlying data structure can be
+/// swapped without touching them.
+///
+/// Lines and columns are counted from `0` here, and lines never include line
+/// endings. There is always at least one (possibly empty) line.
+pub trait TextBuffer {
+    fn len_lines(&self) -> usize;
+
+    /// Amount of characters in the whole text, line endings count as one
+    fn len_chars(&self) -> usize;
+
+    /// Amount of characters in the line, or `0` if the line does not exist
+    fn line_len(&self, line: usize) -> usize;
+
+    /// Iterate over characters of the line, without the line ending
+    fn line_chars(&self, line: usize) -> Box<dyn Iterator<Item = char> + '_>;
+
+    /// Insert text at the position; the text can contain `\n` characters
+    fn insert(&mut self, line: usize, column: usize, text: &str);
+
+    /// Remove text between two positions and return it; `start` must not be after `end`
+    fn remove(&mut self, start: (usize, usize), end: (usize, usize)) -> String;
+
+    /// Text between two positions, lines are joined with `\n`
+    fn get_text(&self, start: (usize, usize), end: (usize, usize)) -> String;
+
+    /// The entire content, lines are joined with `\n`
+    fn to_text(&self) -> String;
+
+    /// Byte offset of the line start; `len_lines()` maps to the end of the text
+    fn line_to_byte(&self, line: usize) -> usize;
+
+    /// Characters from the start of the text to the position, line endings
+    /// count as one; positions outside of the text are clamped
+    fn position_to_offset(&self, line: usize, column: usize) -> usize;
+
+    /// Opposite of `position_to_offset`
+    fn offset_to_position(&self, offset: usize) -> (usize, usize);
+
+    /// Bytes from the offset until the end of the internal chunk containing it,
+    /// empty at the end of the text. Used to feed the parser without copying
+    fn chunk_at_byte(&self, byte: usize) -> &[u8];
+
+    /// Text between two byte offsets
+    fn get_text_by_bytes(&self, start: usize, end: usize) -> String;
+
+    /// Start recording all changes, so they can be replayed by the highlighter
+    fn track_edits(&mut self);
+
+    /// All changes since the last call, in the order they were made
+    fn take_edits(&mut self) -> Vec<TextEdit>;
+
+    /// Grows with every change, so results computed from the text can be
+    /// cached until it changes
+    fn version(&self) -> usize;
+
+    /// Remember the line ending of every line, for files which mix them;
+    /// they move together with the lines when the text is edited
+    fn set_line_endings(&mut self, line_endings: Option<Vec<LineEnding>>);
+
+    /// `None` unless the file mixes line endings
+    fn get_line_endings(&self) -> Option<&[LineEnding]>;
+
+    fn get_line(&self, line: usize) -> Vec<char> {
+        self.line_chars(line).collect()
+    }
+
+    #[cfg(test)]
+    fn line_to_string(&self, line: usize) -> String {
+        self.line_chars(line).collect()
+    }
+}
+
+/// A single change of the text, in the same form as tree-sitter expects it.
+/// Positions are `(line, byte column)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start_byte: usize,
+    pub old_end_byte: usize,
+    pub new_end_byte: usize,
+    pub start_position: (usize, usize),
+    pub old_end_position: (usize, usize),
+    pub new_end_position: (usize, usize),
+}
+
+/// Rope keeps the text in a balanced tree of small chunks, so inserting and
+/// removing is `O(log n)` regardless of the file size, and lines can be
+/// looked up without scanning the whole file.
+pub struct RopeBuffer {
+    rope: Rope,
+    /// `None` unless somebody is interested in them, so they don't pile up
+    edits: Option<Vec<TextEdit>>,
+    line_endings: Option<Vec<LineEnding>>,
+    version: usize,
+}
+
+impl RopeBuffer {
+    pub fn from_text(text: &str) -> Self {
+        RopeBuffer {
+            rope: Rope::from_str(text),
+            edits: None,
+            line_endings: None,
+            version: 0,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn from_lines(lines: &[Vec<char>]) -> Self {
+        let text = lines
+            .iter()
+            .map(|line| line.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        Self::from_text(&text)
+    }
+
+    /// Convert line and column into a character index, clamping both of them
+    fn get_char_index(&self, line: usize, column: usize) -> usize {
+        let last_line = self.rope.len_lines() - 1;
+        if line > last_line {
+            return self.rope.len_chars();
+        }
+
+        self.rope.line_to_char(line) + column.min(self.line_len(line))
+    }
+
+    fn get_byte_position(&self, byte: usize) -> (usize, usize) {
+        let line = self.rope.byte_to_line(byte);
+        (line, byte - self.rope.line_to_byte(line))
+    }
+}
+
+impl TextBuffer for RopeBuffer {
+    fn len_lines(&self) -> usize {
+        // we use only `\n` as line separator, so the rope never has
+        // a line ending at the end of the last line
+        self.rope.len_lines()
+    }
+
+    fn len_chars(&self) -> usize {
+        self.rope.len_chars()
+    }
+
+    fn line_len(&self, line: usize) -> usize {
+        let lines_num = self.rope.len_lines();
+        if line >= lines_num {
+            return 0;
+        }
+
+        let len = self.rope.line(line).len_chars();
+        if line + 1 < lines_num { len - 1 } else { len }
+    }
+
+    fn line_chars(&self, line: usize) -> Box<dyn Iterator<Item = char> + '_> {
+        if line >= self.rope.len_lines() {
+            return Box::new(std::iter::empty());
+        }
+
+        let line_len = self.line_len(line);
+        Box::new(self.rope.line(line).chars().take(line_len))
+    }
+
+    fn insert(&mut self, line: usize, column: usize, text: &str) {
+        let index = self.get_char_index(line, column);
+
+        if self.edits.is_some() {
+            let start_byte = self.rope.char_to_byte(index);
+            let start_position = self.get_byte_position(start_byte);
+            let new_end_position = match text.rfind('\n') {
+                Some(last_newline) => (
+                    start_position.0 + text.matches('\n').count(),
+                    text.len() - last_newline - 1,
+                ),
+                None => (start_position.0, start_position.1 + text.len()),
+            };
+
+            if let Some(edits) = &mut self.edits {
+                edits.push(TextEdit {
+                    start_byte,
+                    old_end_byte: start_byte,
+                    new_end_byte: start_byte + text.len(),
+                    start_position,
+                    old_end_position: start_position,
+                    new_end_position,
+                });
+            }
+        }
+
+        // the line is split, and all its parts keep its line ending
+        if let Some(line_endings) = &mut self.line_endings {
+            let line = self.rope.char_to_line(index);
+            let line_ending = line_endings[line];
+            let new_lines = text.matches('\n').count();
+            line_endings.splice(line..line, std::iter::repeat_n(line_ending, new_lines));
+        }
+
+        self.rope.insert(index, text);
+        self.version += 1;
+    }
+
+    fn remove(&mut self, start: (usize, usize), end: (usize, usize)) -> String {
+        let start_index = self.get_char_index(start.0, start.1);
+        let end_index = self.get_char_index(end.0, end.1);
+
+        if start_index >= end_index {
+            return String::new();
+        }
+
+        let removed = self.rope.slice(start_index..end_index).to_string();
+
+        if self.edits.is_some() {
+            let start_byte = self.rope.char_to_byte(start_index);
+            let old_end_byte = self.rope.char_to_byte(end_index);
+            let start_position = self.get_byte_position(start_byte);
+            let old_end_position = self.get_byte_position(old_end_byte);
+
+            if let Some(edits) = &mut self.edits {
+                edits.push(TextEdit {
+                    start_byte,
+                    old_end_byte,
+                    new_end_byte: start_byte,
+                    start_position,
+                    old_end_position,
+                    new_end_position: start_position,
+                });
+            }
+        }
+
+        // joined lines get the line ending of the last one
+        if let Some(line_endings) = &mut self.line_endings {
+            let line = self.rope.char_to_line(start_index);
+            line_endings.drain(line..line + removed.matches('\n').count());
+        }
+
+        self.rope.remove(start_index..end_index);
+        self.version += 1;
+
+        removed
+    }
+
+    fn get_text(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        let start_index = self.get_char_index(start.0, start.1);
+        let end_index = self.get_char_index(end.0, end.1);
+
+        if start_index >= end_index {
+            return String::new();
+        }
+
+        self.rope.slice(start_index..end_index).to_string()
+    }
+
+    fn to_text(&self) -> String {
+        self.rope.to_string()
+    }
+
+    fn line_to_byte(&self, line: usize) -> usize {
+        if line >= self.rope.len_lines() {
+            return self.rope.len_bytes();
+        }
+
+        self.rope.line_to_byte(line)
+    }
+
+    fn position_to_offset(&self, line: usize, column: usize) -> usize {
+        self.get_char_index(line, column)
+    }
+
+    fn offset_to_position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.rope.len_chars());
+        let line = self.rope.char_to_line(offset);
+        (line, offset - self.rope.line_to_char(line))
+    }
+
+    fn chunk_at_byte(&self, byte: usize) -> &[u8] {
+        if byte >= self.rope.len_bytes() {
+            return &[];
+        }
+
+        let (chunk, chunk_start, _, _) = self.rope.chunk_at_byte(byte);
+        &chunk.as_bytes()[byte - chunk_start..]
+    }
+
+    fn get_text_by_bytes(&self, start: usize, end: usize) -> String {
+        let len_bytes = self.rope.len_bytes();
+        let start = self.rope.byte_to_char(start.min(len_bytes));
+        let end = self.rope.byte_to_char(end.min(len_bytes));
+
+        if start >= end {
+            return String::new();
+        }
+
+        self.rope.slice(start..end).to_string()
+    }
+
+    fn track_edits(&mut self) {
+        if self.edits.is_none() {
+            self.edits = Some(vec![]);
+        }
+    }
+
+    fn take_edits(&mut self) -> Vec<TextEdit> {
+        match &mut self.edits {
+            Some(edits) => std::mem::take(edits),
+            None => vec![],
+        }
+    }
+
+    fn version(&self) -> usize {
+        self.version
+    }
+
+    fn set_line_endings(&mut self, line_endings: Option<Vec<LineEnding>>) {
+        self.line_endings = line_endings;
+    }
+
+    fn get_line_endings(&self) -> Option<&[LineEnding]> {
+        self.line_endings.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn counts_lines_and_lengths() {
+        let buffer = RopeBuffer::from_text("Hello\n\nworld!");
+
+        assert_eq!(buffer.len_lines(), 3);
+        assert_eq!(buffer.line_len(0), 5);
+        assert_eq!(buffer.line_len(1), 0);
+        assert_eq!(buffer.line_len(2), 6);
+        assert_eq!(buffer.line_len(3), 0);
+        assert_eq!(buffer.line_to_string(2), "world!");
+
+        let empty_buffer = RopeBuffer::from_text("");
+        assert_eq!(empty_buffer.len_lines(), 1);
+    }
+
+    #[test]
+    fn converts_positions_to_offsets() {
+        let buffer = RopeBuffer::from_text("Hello\n\nwörld!");
+
+        assert_eq!(buffer.len_chars(), 13);
+        assert_eq!(buffer.position_to_offset(2, 2), 9);
+        assert_eq!(buffer.offset_to_position(9), (2, 2));
+        assert_eq!(buffer.offset_to_position(6), (1, 0));
+        // clamped to the line length and the end of the text
+        assert_eq!(buffer.position_to_offset(0, 100), 5);
+        assert_eq!(buffer.offset_to_position(100), (2, 6));
+    }
+
+    #[test]
+    fn inserts_and_removes_across_lines() {
+        let mut buffer = RopeBuffer::from_text("Hello\nworld!");
+
+        buffer.insert(0, 5, " dear\nnew");
+
+        assert_eq!(buffer.to_text(), "Hello dear\nnew\nworld!");
+
+        let version = buffer.version();
+        let removed = buffer.remove((0, 5), (2, 0));
+        assert_eq!(buffer.version(), version + 1);
+
+        assert_eq!(removed, " dear\nnew\n");
+        assert_eq!(buffer.to_text(), "Helloworld!");
+        assert_eq!(buffer.get_text((0, 1), (0, 4)), "ell");
+    }
+
+    #[test]
+    fn records_edits_with_byte_positions() {
+        let mut buffer = RopeBuffer::from_text("fn main() {\n    é\n}");
+        buffer.insert(0, 0, "pub ");
+        assert!(buffer.take_edits().is_empty());
+
+        buffer.track_edits();
+        buffer.insert(1, 5, "\nab");
+        buffer.remove((0, 0), (0, 4));
+
+        assert_eq!(
+            buffer.take_edits(),
+            vec![
+                TextEdit {
+                    start_byte: 22,
+                    old_end_byte: 22,
+                    new_end_byte: 25,
+                    start_position: (1, 6),
+                    old_end_position: (1, 6),
+                    new_end_position: (2, 2),
+                },
+                TextEdit {
+                    start_byte: 0,
+                    old_end_byte: 4,
+                    new_end_byte: 0,
+                    start_position: (0, 0),
+                    old_end_position: (0, 4),
+                    new_end_position: (0, 0),
+                },
+            ]
+        );
+        assert!(buffer.take_edits().is_empty());
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture` to see the numbers
+    #[test]
+    #[ignore]
+    fn benchmark_large_file() {
+        let line = "let value = some_function(argument_one, argument_two); // comment\n";
+        // roughly 200 MB of text
+        let lines_num = 200 * 1024 * 1024 / line.len();
+        let text = line.repeat(lines_num);
+
+        let timer = Instant::now();
+        let mut buffer = RopeBuffer::from_text(&text);
+        println!("loading {lines_num} lines took {:?}", timer.elapsed());
+
+        let iterations = 10_000;
+
+        let timer = Instant::now();
+        for i in 0..iterations {
+            buffer.insert(lines_num / 2 + i, 10, "x");
+            buffer.insert(lines_num / 2 + i, 20, "\n");
+        }
+        let insert_time = timer.elapsed();
+        println!("{} insertions took {insert_time:?}", iterations * 2);
+
+        // emulate rendering of a screen with 50 lines, scrolled to the middle
+        let timer = Instant::now();
+        for i in 0..iterations {
+            let visible_chars: usize = (0..50)
+                .map(|line| buffer.line_chars(lines_num / 2 + i + line).count())
+                .sum();
+            assert!(visible_chars > 0);
+        }
+        let render_time = timer.elapsed();
+        println!("{iterations} screen renders took {render_time:?}");
+
+        assert!(insert_time.as_millis() < 1000);
+        assert!(render_time.as_millis() < 5000);
+    }
+}
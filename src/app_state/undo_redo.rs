@@ -38,11 +38,51 @@ struct PasteAction {
     selection: Option<UndoSelection>,
 }
 
+/// New text of whole lines starting from `line` (from `1`); both texts can
+/// span several lines, and their number can be different
+pub struct LineReplacement {
+    pub line: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// Lines replaced with other ones at once, e.g. when all matches are replaced
+/// or lines are moved; the selection comes back together with the cursor
+pub struct LinesChange {
+    /// In the order they were made
+    pub lines: Vec<LineReplacement>,
+    pub cursor_before: (usize, usize),
+    pub cursor_after: (usize, usize),
+    pub selection_before: Option<((usize, usize), (usize, usize))>,
+    pub selection_after: Option<((usize, usize), (usize, usize))>,
+}
+
+/// The same edit made at several cursors at once
+struct GroupAction {
+    /// In the order they were made; positions in every action are valid
+    /// only after all actions before it
+    actions: Vec<Action>,
+    cursors_before: Vec<(usize, usize)>,
+    cursors_after: Vec<(usize, usize)>,
+    last_action_timestamp: SystemTime,
+}
+
+impl GroupAction {
+    /// Typing at several cursors is undone by words, like typing at one
+    fn is_typing(&self) -> bool {
+        self.actions
+            .iter()
+            .all(|action| matches!(action, Action::Add(_)))
+    }
+}
+
 enum Action {
     Add(AddAction),
     RemoveBack(RemoveBackAction),
     RemoveForward(RemoveForwardAction),
     Paste(PasteAction),
+    Replace(LinesChange),
+    Group(GroupAction),
 }
 
 pub enum UndoAction {
@@ -54,8 +94,13 @@ pub enum UndoAction {
         (usize, usize),
         Option<UndoSelection>,
     ),
+    /// all replaced lines are undone at once
+    Replace(LinesChange),
     RemoveCharacter(char, (usize, usize), (usize, usize), RemoveBufferType),
     RemoveSelection(UndoSelection, (usize, usize), RemoveBufferType),
+    /// removed text, the cursor before and the other end of the removed text;
+    /// unlike characters, it is never merged with other removals
+    RemoveText(String, (usize, usize), (usize, usize), RemoveBufferType),
 }
 
 struct AddCharacterBuffer {
@@ -221,6 +266,10 @@ impl UndoRedo {
                     selection,
                 }))
             }
+            UndoAction::Replace(change) => {
+                self.commit_buffer();
+                self.undo_actions.push(Action::Replace(change))
+            }
             UndoAction::RemoveCharacter(
                 ch,
                 (start_line, start_col),
@@ -270,10 +319,69 @@ impl UndoRedo {
                     Some(undo_selection),
                 )));
             }
+            UndoAction::RemoveText(data, start, end, remove_type) => {
+                self.commit_buffer();
+                self.undo_actions.push(match remove_type {
+                    RemoveBufferType::Backspace => Action::RemoveBack(RemoveBackAction {
+                        data,
+                        start,
+                        end,
+                        selection: None,
+                    }),
+                    RemoveBufferType::Delete => Action::RemoveForward(RemoveForwardAction {
+                        data,
+                        start,
+                        end,
+                        selection: None,
+                    }),
+                });
+            }
         }
     }
 
-    fn commit_buffer(&mut self) {
+    /// Record everything done at several cursors as a single action; `group`
+    /// is a separate history the edits were recorded into
+    pub(super) fn add_group(
+        &mut self,
+        mut group: UndoRedo,
+        cursors_before: Vec<(usize, usize)>,
+        cursors_after: Vec<(usize, usize)>,
+    ) {
+        group.commit_buffer();
+        if group.undo_actions.is_empty() {
+            // only the cursors moved
+            return;
+        }
+
+        self.redo_actions.clear();
+        self.commit_buffer();
+
+        let group_action = GroupAction {
+            actions: group.undo_actions,
+            cursors_before,
+            cursors_after,
+            last_action_timestamp: SystemTime::now(),
+        };
+
+        if let Some(Action::Group(last_group)) = self.undo_actions.last_mut()
+            && last_group.is_typing()
+            && group_action.is_typing()
+            && last_group.cursors_after == group_action.cursors_before
+            && last_group
+                .last_action_timestamp
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed.as_millis() <= MAX_BUFFER_DEBOUNCE_TIME_MS)
+        {
+            last_group.actions.extend(group_action.actions);
+            last_group.cursors_after = group_action.cursors_after;
+            last_group.last_action_timestamp = group_action.last_action_timestamp;
+            return;
+        }
+
+        self.undo_actions.push(Action::Group(group_action));
+    }
+
+    pub(super) fn commit_buffer(&mut self) {
         if let Some(buffer) = self.buffer.take() {
             match buffer {
                 Buffer::AddCharacter(add_character_buffer) => {
@@ -324,7 +432,26 @@ impl UndoRedo {
             return;
         };
 
-        match &action {
+        // positions of other cursors are not recorded for single actions
+        editor_state.clear_extra_cursors();
+        self.undo(&action, editor_state);
+
+        self.redo_actions.push(action);
+    }
+
+    pub fn redo_action(&mut self, editor_state: &mut UIState) {
+        let Some(action) = self.redo_actions.pop() else {
+            return;
+        };
+
+        editor_state.clear_extra_cursors();
+        self.redo(&action, editor_state);
+
+        self.undo_actions.push(action);
+    }
+
+    fn undo(&self, action: &Action, editor_state: &mut UIState) {
+        match action {
             Action::Add(add_action) => {
                 // we don't need to use actual characters, as we delete them
                 // using only start and end positions
@@ -355,12 +482,18 @@ impl UndoRedo {
                 self.insert_selection_back(&paste_action.selection, editor_state);
             }
             Action::RemoveBack(remove_back_action) => {
+                // the cursor could be moved since, or belong to another cursor
+                editor_state.cursor_line = remove_back_action.end.0;
+                editor_state.cursor_column = remove_back_action.end.1;
+
                 // we should prepend all deleted whitespaces
                 editor_state.insert_text(remove_back_action.data.clone(), false);
 
                 self.insert_selection_back(&remove_back_action.selection, editor_state);
             }
             Action::RemoveForward(remove_forward_action) => {
+                editor_state.cursor_line = remove_forward_action.start.0;
+                editor_state.cursor_column = remove_forward_action.start.1;
                 editor_state.insert_text(remove_forward_action.data.clone(), false);
                 // we need to move the cursor to the beginning to replicate the state
                 // before the user pressed delete
@@ -369,17 +502,29 @@ impl UndoRedo {
 
                 self.insert_selection_back(&remove_forward_action.selection, editor_state);
             }
+            Action::Replace(change) => {
+                for replacement in change.lines.iter().rev() {
+                    let lines_number = replacement.new_text.split('\n').count();
+                    editor_state.set_lines_text(
+                        replacement.line,
+                        lines_number,
+                        &replacement.old_text,
+                    );
+                }
+                editor_state
+                    .set_cursor_and_selection(change.cursor_before, change.selection_before);
+            }
+            Action::Group(group_action) => {
+                for action in group_action.actions.iter().rev() {
+                    self.undo(action, editor_state);
+                }
+                editor_state.set_cursor_positions(&group_action.cursors_before);
+            }
         }
-
-        self.redo_actions.push(action);
     }
 
-    pub fn redo_action(&mut self, editor_state: &mut UIState) {
-        let Some(action) = self.redo_actions.pop() else {
-            return;
-        };
-
-        match &action {
+    fn redo(&self, action: &Action, editor_state: &mut UIState) {
+        match action {
             Action::Add(add_action) => {
                 self.remove_selection(&add_action.selection, editor_state);
 
@@ -417,9 +562,24 @@ impl UndoRedo {
                 self.remove_selection(&remove_forward_action.selection, editor_state);
                 editor_state.delete_range(remove_forward_action.start, remove_forward_action.end);
             }
+            Action::Replace(change) => {
+                for replacement in &change.lines {
+                    let lines_number = replacement.old_text.split('\n').count();
+                    editor_state.set_lines_text(
+                        replacement.line,
+                        lines_number,
+                        &replacement.new_text,
+                    );
+                }
+                editor_state.set_cursor_and_selection(change.cursor_after, change.selection_after);
+            }
+            Action::Group(group_action) => {
+                for action in &group_action.actions {
+                    self.redo(action, editor_state);
+                }
+                editor_state.set_cursor_positions(&group_action.cursors_after);
+            }
         }
-
-        self.undo_actions.push(action);
     }
 
     fn insert_selection_back(
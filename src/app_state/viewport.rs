@@ -0,0 +1,280 @@
+use super::display_width::{LineLayout, get_cluster_boundaries};
+use super::editor::UIState;
+
+/// A single row on the screen: the line (counting from `0`) and which part
+/// of it is shown, `0` unless the line is wrapped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VisualRow {
+    pub line_index: usize,
+    pub row: usize,
+}
+
+impl UIState {
+    /// Called on every render, since the terminal can be resized
+    pub fn set_editor_width(&mut self, width: usize) {
+        // 1 for the padding on the left, 1 on the right
+        self.editor_text_width = width.saturating_sub(2 + self.prefix_len).max(1);
+    }
+
+    pub fn get_line_layout(&self, line_index: usize) -> LineLayout {
+        let wrap_width = self.soft_wrap.then_some(self.editor_text_width);
+        LineLayout::new(&self.lines.get_line(line_index), self.tab_width, wrap_width)
+    }
+
+    /// Rows taken by the line on the screen; only wrapped lines take more than one
+    pub fn get_line_rows(&self, line_index: usize) -> usize {
+        if !self.soft_wrap {
+            return 1;
+        }
+
+        self.get_line_layout(line_index).rows
+    }
+
+    /// Row within the line and the cell within that row (from `0`) for the
+    /// column in the line (from `1`). The cursor after the last character of
+    /// a full row stays on that row, so such lines don't get an empty row.
+    pub fn get_visual_position(&self, line: usize, column: usize) -> (usize, usize) {
+        self.get_line_layout(line - 1).get_position(column - 1)
+    }
+
+    /// Rows visible on the screen, from the top
+    pub fn get_visible_rows(&self) -> Vec<VisualRow> {
+        let mut rows = vec![];
+
+        for line_index in self.editor_scroll_offset..self.lines.len_lines() {
+            for row in 0..self.get_line_rows(line_index) {
+                if rows.len() == self.editor_lines_num {
+                    return rows;
+                }
+
+                rows.push(VisualRow { line_index, row });
+            }
+        }
+
+        rows
+    }
+
+    /// Keep the cursor inside the pane horizontally, together with the whole
+    /// character under it; with soft wrap there is nothing to scroll
+    pub fn handle_horizontal_scrolling(&mut self) {
+        if self.soft_wrap {
+            self.horizontal_scroll_offset = 0;
+            return;
+        }
+
+        let layout = self.get_line_layout(self.cursor_line - 1);
+        let (_, x) = layout.get_position(self.cursor_column - 1);
+        let width = layout
+            .widths
+            .get(self.cursor_column - 1)
+            .copied()
+            .unwrap_or(1)
+            .max(1);
+
+        if x < self.horizontal_scroll_offset {
+            self.horizontal_scroll_offset = x;
+        } else if x + width > self.horizontal_scroll_offset + self.editor_text_width {
+            self.horizontal_scroll_offset = (x + width).saturating_sub(self.editor_text_width);
+        }
+    }
+
+    /// With soft wrap, scrolling by lines is not enough: the rows of all
+    /// lines above the cursor have to fit as well
+    pub(super) fn handle_wrapped_cursor_scrolling(&mut self) {
+        if !self.soft_wrap {
+            return;
+        }
+
+        let (cursor_row, _) = self.get_visual_position(self.cursor_line, self.cursor_column);
+
+        loop {
+            let rows_above: usize = (self.editor_scroll_offset..self.cursor_line - 1)
+                .map(|line_index| self.get_line_rows(line_index))
+                .sum();
+
+            if rows_above + cursor_row < self.editor_lines_num
+                || self.editor_scroll_offset + 1 >= self.cursor_line
+            {
+                return;
+            }
+
+            self.editor_scroll_offset += 1;
+        }
+    }
+
+    /// Place of the cursor on the screen, relative to the text area;
+    /// `None` if it is scrolled out of view
+    pub fn get_cursor_screen_position(&self) -> Option<(usize, usize)> {
+        let (row, x) = self.get_visual_position(self.cursor_line, self.cursor_column);
+        let y = self.get_visible_rows().iter().position(|visible_row| {
+            *visible_row
+                == VisualRow {
+                    line_index: self.cursor_line - 1,
+                    row,
+                }
+        })?;
+
+        let x = x.checked_sub(self.horizontal_scroll_offset)?;
+
+        Some((x, y))
+    }
+
+    /// Up and down go through the rows of wrapped lines, and keep the same
+    /// place on the screen rather than the same character index
+    pub(super) fn cursor_move_visual_row(&mut self, is_up: bool) {
+        let (row, x) = self.get_visual_position(self.cursor_line, self.cursor_column);
+
+        // if it is not 0, it means we were pressing up/down before
+        if self.vertical_offset_target == 0 {
+            self.vertical_offset_target = x + 1;
+        }
+
+        let last_row = self.get_line_rows(self.cursor_line - 1) - 1;
+        let (line, row) = match is_up {
+            true if row > 0 => (self.cursor_line, row - 1),
+            true if self.cursor_line > 1 => (
+                self.cursor_line - 1,
+                self.get_line_rows(self.cursor_line - 2) - 1,
+            ),
+            true => {
+                self.cursor_column = 1;
+                return;
+            }
+            false if row < last_row => (self.cursor_line, row + 1),
+            false if self.cursor_line < self.lines.len_lines() => (self.cursor_line + 1, 0),
+            false => {
+                self.cursor_column = self.get_line_len(self.cursor_line - 1) + 1;
+                return;
+            }
+        };
+
+        let layout = self.get_line_layout(line - 1);
+        self.cursor_line = line;
+        self.cursor_column = layout.get_index_at(row, self.vertical_offset_target - 1) + 1;
+        self.handle_cursor_scrolling();
+    }
+
+    /// Column of the previous cursor stop; combined characters, like an
+    /// emoji with modifiers, are skipped as a whole
+    pub(super) fn get_previous_cursor_column(&self) -> usize {
+        let boundaries = get_cluster_boundaries(&self.lines.get_line(self.cursor_line - 1));
+
+        boundaries
+            .iter()
+            .rev()
+            .find(|boundary| **boundary + 1 < self.cursor_column)
+            .map_or(1, |boundary| boundary + 1)
+    }
+
+    pub(super) fn get_next_cursor_column(&self) -> usize {
+        let boundaries = get_cluster_boundaries(&self.lines.get_line(self.cursor_line - 1));
+
+        boundaries
+            .iter()
+            .find(|boundary| **boundary + 1 > self.cursor_column)
+            .map_or(self.cursor_column, |boundary| boundary + 1)
+    }
+
+    pub fn toggle_soft_wrap(&mut self) {
+        self.soft_wrap = !self.soft_wrap;
+        self.handle_cursor_scrolling();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_utils::lines;
+    use crossterm::event::KeyModifiers;
+
+    /// 10 characters fit in a row: 15 - 2 for the padding - 3 for the prefix
+    fn create_ui_state(text: &str, soft_wrap: bool) -> UIState {
+        let mut ui_state = UIState::new(1, lines(text));
+        ui_state.set_editor_offset(0, 0, 5);
+        ui_state.set_editor_width(15);
+        ui_state.soft_wrap = soft_wrap;
+        ui_state
+    }
+
+    #[test]
+    fn scrolls_horizontally_with_the_cursor() {
+        let mut ui_state = create_ui_state("0123456789abcdefghij\nshort", false);
+
+        ui_state.cursor_column = 15;
+        ui_state.handle_horizontal_scrolling();
+        assert_eq!(ui_state.horizontal_scroll_offset, 5);
+        assert_eq!(ui_state.get_cursor_screen_position(), Some((9, 0)));
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        ui_state.handle_horizontal_scrolling();
+        assert_eq!(ui_state.horizontal_scroll_offset, 5);
+        assert_eq!(ui_state.get_cursor_screen_position(), Some((0, 1)));
+
+        ui_state.cursor_move_line_start(&KeyModifiers::NONE);
+        ui_state.handle_horizontal_scrolling();
+        assert_eq!(ui_state.horizontal_scroll_offset, 0);
+    }
+
+    #[test]
+    fn wraps_long_lines() {
+        let ui_state = create_ui_state("0123456789abcdefghij\nshort\n0123456789abc", true);
+
+        assert_eq!(ui_state.get_line_rows(0), 2);
+        assert_eq!(ui_state.get_line_rows(1), 1);
+        assert_eq!(ui_state.get_visual_position(1, 11), (1, 0));
+        // after the last character of a full row
+        assert_eq!(ui_state.get_visual_position(1, 21), (1, 10));
+
+        let visible_rows: Vec<(usize, usize)> = ui_state
+            .get_visible_rows()
+            .iter()
+            .map(|row| (row.line_index, row.row))
+            .collect();
+        assert_eq!(visible_rows, [(0, 0), (0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn uses_display_width_of_tabs_and_wide_characters() {
+        let mut ui_state = create_ui_state("\tab\n日本語ab\n e\u{301}x", false);
+        ui_state.cursor_column = 2;
+        assert_eq!(ui_state.get_cursor_screen_position(), Some((4, 0)));
+
+        // the same place on the screen, which is the third character here
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (2, 3));
+        assert_eq!(ui_state.get_cursor_screen_position(), Some((4, 1)));
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        ui_state.cursor_move_line_start(&KeyModifiers::NONE);
+        ui_state.cursor_move_right(&KeyModifiers::NONE);
+        ui_state.cursor_move_right(&KeyModifiers::NONE);
+        assert_eq!(ui_state.cursor_column, 4);
+        ui_state.cursor_move_left(&KeyModifiers::NONE);
+        assert_eq!(ui_state.cursor_column, 2);
+    }
+
+    #[test]
+    fn moves_by_visual_rows() {
+        let mut ui_state = create_ui_state("0123456789abcdefghij\nshort\n0123456789abc", true);
+        ui_state.cursor_column = 4;
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 14));
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (2, 4));
+
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        ui_state.cursor_move_down(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (3, 14));
+        // the last line does not fit, so the view is scrolled by a line
+        assert_eq!(ui_state.editor_scroll_offset, 1);
+        assert_eq!(ui_state.get_cursor_screen_position(), Some((3, 2)));
+
+        ui_state.cursor_move_up(&KeyModifiers::NONE);
+        ui_state.cursor_move_up(&KeyModifiers::NONE);
+        ui_state.cursor_move_up(&KeyModifiers::NONE);
+        assert_eq!((ui_state.cursor_line, ui_state.cursor_column), (1, 14));
+        assert_eq!(ui_state.editor_scroll_offset, 0);
+    }
+}
@@ -0,0 +1,267 @@
+use super::display_width::get_cluster_boundaries;
+use super::editor::UIState;
+
+/// What counts as a word when jumping over words or selecting them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordRules {
+    /// Counted as word characters besides letters, digits and `_`,
+    /// e.g. `-` for CSS or `$` for JavaScript
+    pub extra_chars: Vec<char>,
+    /// Stop inside words too: at camelCase humps and after underscores
+    pub subwords: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CharKind {
+    Word,
+    Whitespace,
+    Other,
+}
+
+impl WordRules {
+    pub fn get_char_kind(&self, character: char) -> CharKind {
+        if character.is_alphanumeric() || character == '_' || self.extra_chars.contains(&character)
+        {
+            CharKind::Word
+        } else if character.is_whitespace() {
+            CharKind::Whitespace
+        } else {
+            CharKind::Other
+        }
+    }
+
+    /// In the sub-word mode, word characters which are not letters or digits
+    /// separate parts of the word, like spaces separate words
+    fn is_separator(&self, character: char) -> bool {
+        self.subwords
+            && self.get_char_kind(character) == CharKind::Word
+            && !character.is_alphanumeric()
+    }
+
+    fn is_part_char(&self, character: char) -> bool {
+        self.get_char_kind(character) == CharKind::Word && !self.is_separator(character)
+    }
+
+    /// Index (from `0`) where the word after the index ends; whitespace
+    /// before it is skipped, and a run of punctuation counts as a word
+    pub fn find_next_word_end(&self, line: &[char], index: usize) -> usize {
+        let mut index = index;
+        while index < line.len() && line[index].is_whitespace() {
+            index += 1;
+        }
+        while index < line.len() && self.is_separator(line[index]) {
+            index += 1;
+        }
+
+        let Some(&character) = line.get(index) else {
+            return index;
+        };
+
+        if self.subwords && self.is_part_char(character) {
+            while index < line.len() && self.is_part_char(line[index]) {
+                index += 1;
+                if is_case_boundary(line, index) {
+                    break;
+                }
+            }
+        } else {
+            let kind = self.get_char_kind(character);
+            while index < line.len() && self.get_char_kind(line[index]) == kind {
+                index += 1;
+            }
+        }
+
+        index
+    }
+
+    /// Index (from `0`) where the word before the index starts
+    pub fn find_previous_word_start(&self, line: &[char], index: usize) -> usize {
+        let mut index = index.min(line.len());
+        while index > 0 && line[index - 1].is_whitespace() {
+            index -= 1;
+        }
+        while index > 0 && self.is_separator(line[index - 1]) {
+            index -= 1;
+        }
+
+        if index == 0 {
+            return 0;
+        }
+
+        if self.subwords && self.is_part_char(line[index - 1]) {
+            while index > 0 && self.is_part_char(line[index - 1]) {
+                index -= 1;
+                if is_case_boundary(line, index) {
+                    break;
+                }
+            }
+        } else {
+            let kind = self.get_char_kind(line[index - 1]);
+            while index > 0 && self.get_char_kind(line[index - 1]) == kind {
+                index -= 1;
+            }
+        }
+
+        index
+    }
+}
+
+/// `fooBar` has a boundary before `B`, and `HTTPServer` before `S`
+fn is_case_boundary(line: &[char], index: usize) -> bool {
+    let (Some(previous), Some(current)) = (
+        index.checked_sub(1).and_then(|index| line.get(index)),
+        line.get(index),
+    ) else {
+        return false;
+    };
+
+    let is_hump = (previous.is_lowercase() || previous.is_numeric()) && current.is_uppercase();
+    let is_acronym_end = previous.is_uppercase()
+        && current.is_uppercase()
+        && line.get(index + 1).is_some_and(|next| next.is_lowercase());
+
+    is_hump || is_acronym_end
+}
+
+impl UIState {
+    /// Column where the word before the cursor starts; the cursor has to
+    /// be after the first column
+    pub(super) fn get_previous_word_column(&self) -> usize {
+        let line = self.lines.get_line(self.cursor_line - 1);
+        let index = self
+            .word_rules
+            .find_previous_word_start(&line, self.cursor_column - 1);
+
+        // combining marks are not letters, but they still can't be split
+        let boundaries = get_cluster_boundaries(&line);
+        boundaries
+            .iter()
+            .rev()
+            .find(|boundary| **boundary <= index)
+            .map_or(1, |boundary| boundary + 1)
+    }
+
+    /// Column of the word at the cursor; right after a word, like in `value|`,
+    /// it is the word before the cursor
+    pub(super) fn get_word_column_at_cursor(&self) -> usize {
+        let line = self.lines.get_line(self.cursor_line - 1);
+        let index = self.cursor_column - 1;
+        let is_word = |index: usize| {
+            line.get(index).is_some_and(|character| {
+                self.word_rules.get_char_kind(*character) == CharKind::Word
+            })
+        };
+
+        if !is_word(index) && index > 0 && is_word(index - 1) {
+            self.cursor_column - 1
+        } else {
+            self.cursor_column
+        }
+    }
+
+    /// Select the word at the position, or a run of spaces or punctuation
+    pub(super) fn select_word(&mut self, (line, column): (usize, usize)) {
+        let chars = self.lines.get_line(line - 1);
+        self.vertical_offset_target = 0;
+        self.cursor_line = line;
+
+        let Some(&character) = chars.get(column - 1) else {
+            self.cursor_column = column;
+            self.selection = None;
+            self.handle_cursor_scrolling();
+            return;
+        };
+
+        let kind = self.word_rules.get_char_kind(character);
+        let is_same_kind = |character: &&char| self.word_rules.get_char_kind(**character) == kind;
+        let start = chars[..column - 1]
+            .iter()
+            .rev()
+            .take_while(is_same_kind)
+            .count();
+        let end = chars[column - 1..].iter().take_while(is_same_kind).count();
+
+        let start_column = column - start;
+        let end_column = column + end;
+        self.set_selection((line, start_column), (line, end_column));
+        self.cursor_column = end_column;
+        self.handle_cursor_scrolling();
+    }
+
+    pub(super) fn get_next_word_column(&self) -> usize {
+        let line = self.lines.get_line(self.cursor_line - 1);
+        let index = self
+            .word_rules
+            .find_next_word_end(&line, self.cursor_column - 1);
+
+        let boundaries = get_cluster_boundaries(&line);
+        boundaries
+            .iter()
+            .find(|boundary| **boundary >= index)
+            .map_or(line.len() + 1, |boundary| boundary + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn get_stops(rules: &WordRules, text: &str) -> (Vec<usize>, Vec<usize>) {
+        let line = chars(text);
+
+        let mut forward = vec![];
+        let mut index = 0;
+        while index < line.len() {
+            index = rules.find_next_word_end(&line, index);
+            forward.push(index);
+        }
+
+        let mut backward = vec![];
+        while index > 0 {
+            index = rules.find_previous_word_start(&line, index);
+            backward.push(index);
+        }
+
+        (forward, backward)
+    }
+
+    #[test]
+    fn jumps_over_words_and_punctuation() {
+        let rules = WordRules::default();
+        let (forward, backward) = get_stops(&rules, "let value = foo_bar(1);  ");
+
+        assert_eq!(forward, [3, 9, 11, 19, 20, 21, 23, 25]);
+        assert_eq!(backward, [21, 20, 19, 12, 10, 4, 0]);
+    }
+
+    #[test]
+    fn uses_extra_word_characters() {
+        let rules = WordRules {
+            extra_chars: vec!['-'],
+            subwords: false,
+        };
+        let (forward, _) = get_stops(&rules, "margin-top: 0");
+
+        assert_eq!(forward, [10, 11, 13]);
+    }
+
+    #[test]
+    fn stops_at_sub_words() {
+        let rules = WordRules {
+            extra_chars: vec![],
+            subwords: true,
+        };
+
+        let (forward, backward) = get_stops(&rules, "parseHTTPServer");
+        assert_eq!(forward, [5, 9, 15]);
+        assert_eq!(backward, [9, 5, 0]);
+
+        let (forward, backward) = get_stops(&rules, "snake_case_name2x");
+        assert_eq!(forward, [5, 10, 17]);
+        assert_eq!(backward, [11, 6, 0]);
+    }
+}
@@ -6,34 +6,162 @@ use ratatui::{
     widgets::{Block, Padding, Paragraph},
 };
 
-use crate::app_state::AppState;
+use std::ops::Range;
+
+use crate::app_state::{
+    AppState, BracketMatch, HighlightSpan, LineLayout, Selection, find_in_line, get_display_text,
+};
+
+const SEARCH_MATCH_COLOR: Color = Color::Rgb(110, 80, 20);
+const BRACKET_MATCH_COLOR: Color = Color::Rgb(70, 70, 90);
+const BRACKET_MISMATCH_COLOR: Color = Color::Rgb(150, 40, 40);
+/// Shown instead of the line number on the continuation rows of wrapped lines
+const WRAP_INDICATOR: char = '↪';
 
 pub fn render_editor(frame: &mut Frame, area: Rect, app_state: &mut AppState) {
-    app_state
-        .ui_state
-        .set_editor_offset(area.x as usize, area.y as usize, area.height as usize);
+    let Some(config) = app_state
+        .get_active_buffer()
+        .map(|buffer| app_state.get_buffer_config(buffer))
+    else {
+        render_empty_editor(frame, area, app_state);
+        return;
+    };
+    let Some(buffer) = app_state.buffers.get_mut(app_state.active_buffer) else {
+        return;
+    };
+    let ui_state = &mut buffer.ui_state;
 
-    let selection_exists = app_state.ui_state.has_any_selection();
+    // the options can be changed at any time with the `set` command
+    ui_state.tab_width = config.get_tab_width();
+    ui_state.word_rules = config.get_word_rules();
+    ui_state.auto_pairs = config.get_auto_pairs();
 
-    let lines_number = app_state.ui_state.lines.len();
-    let text: Vec<Line> = app_state
-        .ui_state
-        .lines
-        .iter()
-        .skip(app_state.ui_state.editor_scroll_offset)
-        .take(app_state.ui_state.editor_lines_num)
-        .enumerate()
-        .map(|(i, line)| {
-            generate_code_line(
-                line,
-                i + 1 + app_state.ui_state.editor_scroll_offset,
-                lines_number,
-                selection_exists,
-                app_state,
+    ui_state.set_editor_offset(area.x as usize, area.y as usize, area.height as usize);
+    ui_state.set_editor_width(area.width as usize);
+    ui_state.handle_horizontal_scrolling();
+
+    // only visible lines are read from the buffer, so rendering does not
+    // depend on the file size
+    let lines_number = ui_state.lines.len_lines();
+    let visible_rows = ui_state.get_visible_rows();
+    let visible_lines = match (visible_rows.first(), visible_rows.last()) {
+        (Some(first), Some(last)) => first.line_index..last.line_index + 1,
+        _ => 0..0,
+    };
+
+    let highlights = match &mut buffer.highlighter {
+        Some(highlighter) => {
+            highlighter.update(ui_state.lines.as_mut());
+            highlighter.highlight_lines(
+                ui_state.lines.as_ref(),
+                visible_lines.clone(),
+                &app_state.theme,
             )
-        })
+        }
+        None => vec![],
+    };
+
+    // the bracket at the cursor and its partner, or the bracket alone in red
+    // if nothing matches it; nothing is shown if the partner may be further
+    // than the search goes
+    let highlighter = buffer.highlighter.as_ref();
+    let is_code = |line: usize, byte_column: usize| {
+        highlighter.is_none_or(|highlighter| highlighter.is_code_at(line, byte_column))
+    };
+    let bracket_match = ui_state.get_bracket_match(&is_code);
+
+    let selections: Vec<&Selection> = ui_state
+        .selection
+        .iter()
+        .chain(
+            ui_state
+                .extra_cursors
+                .iter()
+                .filter_map(|cursor| cursor.selection.as_ref()),
+        )
         .collect();
 
+    // matches are highlighted only while the search bar is opened
+    let search_regex = app_state
+        .search_bar
+        .as_ref()
+        .and_then(|search_bar| search_bar.summary.as_ref())
+        .and_then(|summary| summary.regex.as_ref().ok());
+
+    let mut text: Vec<Line> = vec![];
+
+    for (i, index) in visible_lines.clone().enumerate() {
+        let line = ui_state.lines.get_line(index);
+        let search_matches = match search_regex {
+            Some(regex) => find_in_line(&line, regex),
+            None => vec![],
+        };
+        // the terminal shows only the primary cursor, others are drawn
+        let extra_cursor_columns: Vec<usize> = ui_state
+            .extra_cursors
+            .iter()
+            .filter(|cursor| cursor.line == index + 1)
+            .map(|cursor| cursor.column - 1)
+            .collect();
+        let brackets = get_bracket_styles(bracket_match.as_ref(), index + 1);
+        let styles = get_line_styles(
+            &line,
+            index + 1,
+            &selections,
+            &extra_cursor_columns,
+            &brackets,
+            highlights.get(i).map_or(&[], |spans| spans.as_slice()),
+            &search_matches,
+        );
+
+        let layout = ui_state.get_line_layout(index);
+        let visible_cells = ui_state.horizontal_scroll_offset
+            ..ui_state.horizontal_scroll_offset + ui_state.editor_text_width;
+        for visible_row in visible_rows.iter().filter(|row| row.line_index == index) {
+            let mut spans = vec![generate_line_prefix(
+                index + 1,
+                lines_number,
+                visible_row.row > 0,
+            )];
+            spans.append(&mut generate_row_spans(
+                &line,
+                &styles,
+                &layout,
+                visible_row.row,
+                visible_cells.clone(),
+            ));
+
+            // a cursor after the last character has no character to draw it on
+            let (end_row, end_x) = layout.get_position(line.len());
+            let mut drawn_end = end_x.max(visible_cells.start);
+            if extra_cursor_columns.contains(&line.len())
+                && end_row == visible_row.row
+                && visible_cells.contains(&end_x)
+            {
+                spans.push(Span::styled(" ", Style::new().reversed()));
+                drawn_end += 1;
+            }
+
+            // the block is a rectangle even where lines are too short for it;
+            // its cells are counted on unwrapped lines
+            if layout.rows == 1
+                && let Some(block_cells) = ui_state.get_block_cells_past_line_end(index + 1)
+            {
+                let start = block_cells.start.max(drawn_end);
+                let end = block_cells.end.min(visible_cells.end);
+                if start < end {
+                    spans.push(Span::raw(" ".repeat(start - drawn_end)));
+                    spans.push(Span::styled(
+                        " ".repeat(end - start),
+                        Style::new().bg(Color::Blue),
+                    ));
+                }
+            }
+
+            text.push(Line::from(spans));
+        }
+    }
+
     let block = Block::default()
         .style(Style::default().bg(app_state.theme.bg_color))
         .padding(Padding::uniform(1));
@@ -45,119 +173,194 @@ pub fn render_editor(frame: &mut Frame, area: Rect, app_state: &mut AppState) {
     frame.render_widget(text_widget, area);
 }
 
-fn generate_code_line(
-    line: &[char],
+fn render_empty_editor(frame: &mut Frame, area: Rect, app_state: &AppState) {
+    let block = Block::default()
+        .style(Style::default().bg(app_state.theme.bg_color))
+        .padding(Padding::uniform(1));
+    let text_widget = Paragraph::new("Select a file in the file tree and press Enter to open it")
+        .block(block)
+        .style(Style::new().dark_gray())
+        .alignment(Alignment::Center);
+
+    frame.render_widget(text_widget, area);
+}
+
+/// The line number, or the wrap indicator for the continuation rows of
+/// a wrapped line; both have the same width
+fn generate_line_prefix(
     current_line: usize,
     lines_number: usize,
-    selection_exists: bool,
-    app_state: &AppState,
-) -> Line<'static> {
-    let current_line_width = current_line.to_string().len();
+    is_wrapped: bool,
+) -> Span<'static> {
     let lines_number_width = lines_number.to_string().len();
 
-    let padding_times = lines_number_width - current_line_width;
-    let padding_str = " ".repeat(padding_times);
-
-    let mut result = vec![Span::styled(
-        format!("|{padding_str}{current_line} "),
-        Style::new().dark_gray(),
-    )];
-    let mut code_line_spans =
-        generate_styled_code_line(line, current_line, selection_exists, app_state);
-
-    result.append(&mut code_line_spans);
+    let prefix = if is_wrapped {
+        format!("|{}{WRAP_INDICATOR} ", " ".repeat(lines_number_width - 1))
+    } else {
+        format!("|{current_line:>lines_number_width$} ")
+    };
 
-    Line::from(result)
+    Span::styled(prefix, Style::new().dark_gray())
 }
 
-fn generate_styled_code_line(
+/// Every character gets the color from syntax highlighting and the background
+/// from search matches, matched brackets and selections
+fn get_line_styles(
     line: &[char],
     current_line_num: usize,
-    selection_exists: bool,
-    app_state: &AppState,
-) -> Vec<Span<'static>> {
-    if !selection_exists {
-        return generate_line_with_no_selection(line);
+    selections: &[&Selection],
+    extra_cursor_columns: &[usize],
+    brackets: &[(usize, Color)],
+    highlights: &[HighlightSpan],
+    search_matches: &[Range<usize>],
+) -> Vec<Style> {
+    let mut styles = vec![Style::new(); line.len()];
+
+    if !highlights.is_empty() {
+        // highlight columns are in bytes, so we need to map them to characters
+        let mut byte_column = 0;
+        for (character, style) in line.iter().zip(styles.iter_mut()) {
+            for (range, color) in highlights {
+                if range.contains(&byte_column) {
+                    *style = style.fg(*color);
+                }
+            }
+
+            byte_column += character.len_utf8();
+        }
     }
 
-    if app_state.ui_state.is_entire_line_selected(current_line_num) {
-        return generate_line_with_full_selection(line);
+    for (column, color) in brackets {
+        if let Some(style) = styles.get_mut(*column) {
+            *style = style.bg(*color).bold();
+        }
     }
 
-    if let Some((min_col, max_col)) = app_state.ui_state.get_selection_range(current_line_num) {
-        generate_line_with_selection(line, min_col, max_col)
-    } else {
-        generate_line_with_no_selection(line)
+    // the current match is selected, so the selection is painted over it
+    for columns in search_matches {
+        for style in &mut styles[columns.clone()] {
+            *style = style.bg(SEARCH_MATCH_COLOR);
+        }
     }
-}
 
-fn generate_line_with_no_selection(line: &[char]) -> Vec<Span<'static>> {
-    vec![Span::raw(line.iter().collect::<String>())]
+    for selection in selections {
+        if let Some(selected_columns) =
+            get_selected_columns(line.len(), current_line_num, selection)
+        {
+            for style in &mut styles[selected_columns] {
+                *style = style.bg(Color::Blue);
+            }
+        }
+    }
+
+    for column in extra_cursor_columns {
+        if let Some(style) = styles.get_mut(*column) {
+            *style = style.reversed();
+        }
+    }
+
+    styles
 }
 
-fn generate_line_with_full_selection(line: &[char]) -> Vec<Span<'static>> {
-    vec![Span::styled(
-        line.iter().collect::<String>(),
-        Style::new().bg(Color::Blue),
-    )]
+/// Columns (from `0`) of the matched brackets on the line with their colors
+fn get_bracket_styles(
+    bracket_match: Option<&BracketMatch>,
+    line_num: usize,
+) -> Vec<(usize, Color)> {
+    let Some(bracket_match) = bracket_match.filter(|bracket_match| !bracket_match.is_beyond_limit)
+    else {
+        return vec![];
+    };
+    let color = if bracket_match.is_mismatched {
+        BRACKET_MISMATCH_COLOR
+    } else {
+        BRACKET_MATCH_COLOR
+    };
+
+    [Some(bracket_match.bracket), bracket_match.partner]
+        .into_iter()
+        .flatten()
+        .filter(|(line, _)| *line == line_num)
+        .map(|(_, column)| (column - 1, color))
+        .collect()
 }
 
-fn generate_line_with_selection(
+/// Text of a single row on the screen, only the cells in `visible_cells`
+/// are included. Neighbour characters with the same style are merged into
+/// a single span.
+fn generate_row_spans(
     line: &[char],
-    min_col: usize,
-    max_col: usize,
+    styles: &[Style],
+    layout: &LineLayout,
+    row: usize,
+    visible_cells: Range<usize>,
 ) -> Vec<Span<'static>> {
-    let mut result = vec![];
+    let mut result: Vec<Span<'static>> = vec![];
+    let mut current_text = String::new();
+    let mut current_style = Style::new();
 
-    if line.is_empty() {
-        return result;
-    }
+    for (index, character) in line.iter().enumerate() {
+        let (character_row, x) = layout.positions[index];
+        let width = layout.widths[index];
 
-    if min_col == 0 {
-        if max_col > 0 {
-            if max_col > line.len() {
-                result.push(Span::styled(
-                    line[0..line.len()].iter().collect::<String>(),
-                    Style::new().bg(Color::Blue),
-                ));
-            } else {
-                result.push(Span::styled(
-                    line[0..max_col].iter().collect::<String>(),
-                    Style::new().bg(Color::Blue),
-                ));
-                result.push(Span::raw(line[max_col..].iter().collect::<String>()));
-            }
-        } else {
-            result.push(Span::styled(
-                line[min_col..line.len()].iter().collect::<String>(),
-                Style::new().bg(Color::Blue),
-            ));
+        if character_row < row || width > 0 && x + width <= visible_cells.start {
+            continue;
         }
-    } else {
-        if min_col > 1 {
-            result.push(Span::raw(line[0..(min_col - 1)].iter().collect::<String>()));
+        if character_row > row || x >= visible_cells.end {
+            break;
         }
 
-        if max_col > 0 {
-            if max_col > line.len() {
-                result.push(Span::styled(
-                    line[(min_col - 1)..line.len()].iter().collect::<String>(),
-                    Style::new().bg(Color::Blue),
-                ));
-            } else {
-                result.push(Span::styled(
-                    line[(min_col - 1)..max_col].iter().collect::<String>(),
-                    Style::new().bg(Color::Blue),
-                ));
-                result.push(Span::raw(line[max_col..].iter().collect::<String>()));
+        // combining characters are drawn together with the previous one
+        if width == 0 {
+            if !current_text.is_empty() {
+                current_text.push(*character);
             }
-        } else {
+            continue;
+        }
+
+        let style = styles[index];
+        if style != current_style && !current_text.is_empty() {
             result.push(Span::styled(
-                line[(min_col - 1)..line.len()].iter().collect::<String>(),
-                Style::new().bg(Color::Blue),
+                std::mem::take(&mut current_text),
+                current_style,
             ));
         }
+        current_style = style;
+
+        if x < visible_cells.start {
+            // only a part of a wide character or a tab is visible
+            current_text.push_str(&" ".repeat(x + width - visible_cells.start));
+        } else {
+            current_text.push_str(&get_display_text(*character, width));
+        }
+    }
+
+    if !current_text.is_empty() {
+        result.push(Span::styled(current_text, current_style));
     }
 
     result
 }
+
+/// Selected characters of the line, counting from `0`
+fn get_selected_columns(
+    line_len: usize,
+    current_line_num: usize,
+    selection: &Selection,
+) -> Option<Range<usize>> {
+    if selection.is_line_selected(current_line_num) {
+        return Some(0..line_len);
+    }
+
+    let (min_col, max_col) = selection.get_selection_range(current_line_num)?;
+
+    // `0` means the selection goes from the line start or until the line end
+    let start = min_col.saturating_sub(1).min(line_len);
+    let end = if max_col == 0 {
+        line_len
+    } else {
+        max_col.min(line_len)
+    };
+
+    Some(start..end.max(start))
+}
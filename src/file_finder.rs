@@ -0,0 +1,83 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+use crate::app_state::AppState;
+
+const INPUT_PREFIX: &str = "> ";
+
+/// Popup in the middle of the screen: the input, and the best matching paths
+/// below it. The file name is shown brighter than its directory.
+pub fn render_file_finder(frame: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let horizontal = Layout::horizontal([Constraint::Max(80)]).flex(Flex::Center);
+    let vertical = Layout::vertical([Constraint::Max(24)]).flex(Flex::Center);
+    let [popup_area] = horizontal.areas(area);
+    let [popup_area] = vertical.areas(popup_area);
+
+    let Some(file_finder) = &mut app_state.file_finder else {
+        return;
+    };
+
+    // 2 for the borders, 2 for the input and an empty line after it
+    let height = (popup_area.height as usize).saturating_sub(4);
+    file_finder.handle_scrolling(height);
+
+    let input = String::from_iter(&file_finder.input);
+    let mut text = vec![
+        Line::from(format!("{INPUT_PREFIX}{input}")),
+        Line::default(),
+    ];
+
+    for index in (file_finder.scroll_offset..file_finder.results.len()).take(height) {
+        let Some(path) = file_finder.get_path(index) else {
+            continue;
+        };
+
+        let mut style = Style::new();
+        if index == file_finder.selected {
+            style = style.bg(Color::Blue);
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let directory = path
+            .parent()
+            .map(|parent| parent.display().to_string())
+            .filter(|parent| !parent.is_empty())
+            .map(|parent| format!("  {parent}"))
+            .unwrap_or_default();
+
+        text.push(Line::from(vec![
+            Span::styled(file_name, style.white()),
+            Span::styled(directory, style.dark_gray()),
+        ]));
+    }
+
+    let title = format!(
+        " Open file ({} of {}) ",
+        file_finder.results.len(),
+        file_finder.get_files_number()
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Rgb(80, 80, 80)))
+        .title(title)
+        .style(Style::default().bg(app_state.theme.status_bar_color))
+        .padding(Padding::horizontal(1));
+    let popup = Paragraph::new(text)
+        .block(block)
+        .style(Style::new().white());
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+
+    // 1 for the border, 1 for the padding
+    let cursor_x = popup_area.x as usize + 2 + INPUT_PREFIX.len() + file_finder.cursor;
+    frame.set_cursor_position((cursor_x as u16, popup_area.y + 1));
+}
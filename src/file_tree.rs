@@ -6,25 +6,46 @@ use ratatui::{
     widgets::{Block, Borders, Padding, Paragraph},
 };
 
-use crate::app_state::{AppState, FileTreeEntry};
+use crate::app_state::{AppState, Focus, get_file_name};
 
-pub fn render_file_tree(frame: &mut Frame, area: Rect, app_state: &AppState) {
-    let file_tree = app_state
-        .file_tree
-        .get(&app_state.working_directory)
-        .expect("Does not have top folder info");
+pub fn render_file_tree(frame: &mut Frame, area: Rect, app_state: &mut AppState) {
+    // 1 for the padding at the top, 1 for the padding at the bottom
+    let height = (area.height as usize).saturating_sub(2);
+    app_state.handle_file_tree_scrolling(height);
 
-    let text: Vec<Line> = file_tree
+    let is_focused = app_state.focus == Focus::FileTree;
+
+    let text: Vec<Line> = app_state
+        .get_visible_file_tree()
         .iter()
-        .map(|entry| match entry {
-            FileTreeEntry::File(file) => {
-                let filename = file.path.file_name().unwrap().to_string_lossy().to_string();
-                Line::from(vec![Span::raw(filename)])
+        .enumerate()
+        .skip(app_state.file_tree_scroll_offset)
+        .take(height)
+        .map(|(index, entry)| {
+            let indentation = "  ".repeat(entry.depth);
+            let icon = match (entry.is_dir, entry.expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            let name = get_file_name(&entry.path);
+
+            let mut style = Style::new();
+            if entry.is_dir {
+                style = style.light_blue();
             }
-            FileTreeEntry::Dir(dir) => {
-                let dirname = dir.path.file_name().unwrap().to_string_lossy().to_string();
-                Line::from(vec![Span::raw(dirname)])
+            if index == app_state.file_tree_selected {
+                style = if is_focused {
+                    style.bg(Color::Blue)
+                } else {
+                    style.bg(Color::Rgb(60, 60, 60))
+                };
             }
+
+            Line::from(vec![Span::styled(
+                format!("{indentation}{icon}{name}"),
+                style,
+            )])
         })
         .collect();
 
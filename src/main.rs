@@ -3,8 +3,11 @@ use std::io;
 mod app;
 mod app_state;
 mod editor;
+mod file_finder;
 mod file_tree;
+mod project_search;
 mod status_bar;
+mod tabs;
 
 use app::start_tui_editor;
 
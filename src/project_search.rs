@@ -0,0 +1,121 @@
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph},
+};
+
+use crate::app_state::{AppState, Focus, ProjectSearchRow};
+
+const MATCH_COLOR: Color = Color::Rgb(110, 80, 20);
+const INPUT_PREFIX: &str = "Search: ";
+
+/// Shown in place of the file tree: the input with search options, and
+/// then matches grouped by files
+pub fn render_project_search(frame: &mut Frame, area: Rect, app_state: &mut AppState) {
+    let working_directory = app_state.working_directory.clone();
+    let is_focused = app_state.focus == Focus::ProjectSearch;
+    let Some(project_search) = &mut app_state.project_search else {
+        return;
+    };
+
+    // 2 for the padding, 3 for the input, the options and an empty line
+    let height = (area.height as usize).saturating_sub(5);
+    project_search.handle_scrolling(height);
+
+    let pattern = String::from_iter(&project_search.query.pattern);
+    let case_text = if project_search.query.case_sensitive {
+        "case sensitive"
+    } else {
+        "ignore case"
+    };
+    let regex_text = if project_search.query.use_regex {
+        "regex"
+    } else {
+        "plain text"
+    };
+    let status_text = if project_search.is_running {
+        " | searching..."
+    } else {
+        ""
+    };
+
+    let mut text = vec![
+        Line::from(format!("{INPUT_PREFIX}{pattern}")),
+        Line::from(format!(
+            "{case_text} (alt+c) | {regex_text} (alt+r){status_text}"
+        ))
+        .dark_gray(),
+        Line::default(),
+    ];
+
+    let rows: Vec<Line> = project_search
+        .get_rows()
+        .into_iter()
+        .skip(project_search.scroll_offset)
+        .take(height)
+        .map(|row| match row {
+            ProjectSearchRow::File(file) => {
+                let path = file
+                    .path
+                    .strip_prefix(&working_directory)
+                    .unwrap_or(&file.path);
+                Line::from(Span::raw(path.display().to_string()).light_blue())
+            }
+            ProjectSearchRow::Hit(hit, index) => {
+                let mut style = Style::new();
+                if index == project_search.selected {
+                    style = if is_focused {
+                        style.bg(Color::Blue)
+                    } else {
+                        style.bg(Color::Rgb(60, 60, 60))
+                    };
+                }
+
+                let preview: Vec<char> = hit.preview.chars().collect();
+                let start = hit.columns.start.min(preview.len());
+                let end = hit.columns.end.min(preview.len());
+                // leading whitespace only takes space in the narrow panel
+                let indentation = preview[..start]
+                    .iter()
+                    .take_while(|character| character.is_whitespace())
+                    .count();
+
+                Line::from(vec![
+                    Span::styled(format!("  {:>4}: ", hit.line), style.dark_gray()),
+                    Span::styled(String::from_iter(&preview[indentation..start]), style),
+                    Span::styled(
+                        String::from_iter(&preview[start..end]),
+                        style.bg(MATCH_COLOR),
+                    ),
+                    Span::styled(String::from_iter(&preview[end..]), style),
+                ])
+            }
+        })
+        .collect();
+    text.extend(rows);
+
+    let block = Block::default()
+        .borders(Borders::RIGHT)
+        .border_style(Style::default().fg(Color::Rgb(80, 80, 80)))
+        .style(Style::default().bg(app_state.theme.bg_color))
+        .padding(Padding::uniform(1));
+    let panel = Paragraph::new(text)
+        .block(block)
+        .style(Style::new().white())
+        .alignment(Alignment::Left);
+
+    frame.render_widget(panel, area);
+
+    if is_focused
+        && app_state.prompt.is_none()
+        && app_state.command_bar.is_none()
+        && app_state.file_finder.is_none()
+        && let Some(project_search) = &app_state.project_search
+    {
+        // 1 for the padding
+        let cursor_x = area.x as usize + 1 + INPUT_PREFIX.len() + project_search.cursor;
+        frame.set_cursor_position((cursor_x as u16, area.y + 1));
+    }
+}
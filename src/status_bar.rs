@@ -1,21 +1,169 @@
 use ratatui::{
     Frame,
-    layout::{Alignment, Rect},
-    style::Style,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
     widgets::{Block, Paragraph},
 };
 
-use crate::app_state::AppState;
+use crate::app_state::{AppState, CommandBar, SearchBar, SearchInput, get_file_name};
 
 pub fn render_status_bar(frame: &mut Frame, area: Rect, app_state: &AppState) {
     let block = Block::default().style(Style::default().bg(app_state.theme.status_bar_color));
+    frame.render_widget(block, area);
 
-    let line_num = app_state.ui_state.cursor_line;
-    let column_num = app_state.ui_state.cursor_column;
-    let formatted_text = format!("Line {line_num} | Column {column_num}");
-    let text = Paragraph::new(formatted_text)
-        .block(block)
-        .alignment(Alignment::Right);
+    let horizontal = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]);
+    let [left_area, right_area] = horizontal.areas(area);
 
-    frame.render_widget(text, area);
+    // prompt requires an answer, so it replaces everything else
+    if let Some(message) = app_state.get_prompt_message() {
+        let prompt_text = Paragraph::new(Line::from(Span::raw(message).yellow()));
+        frame.render_widget(prompt_text, area);
+        return;
+    }
+
+    if let Some(command_bar) = &app_state.command_bar {
+        render_command_bar(frame, area, app_state, command_bar);
+        return;
+    }
+
+    if let Some(search_bar) = &app_state.search_bar {
+        render_search_bar(frame, area, app_state, search_bar);
+        return;
+    }
+
+    let buffer = app_state.get_active_buffer();
+
+    let file_name = match buffer {
+        Some(buffer) => buffer.get_name(),
+        None => get_file_name(&app_state.working_directory),
+    };
+    let mut file_info = vec![Span::raw(file_name)];
+
+    if let Some(buffer) = buffer
+        && buffer.ui_state.is_dirty
+    {
+        file_info.push(Span::raw(" [modified]").yellow());
+    }
+
+    if let Some(message) = &app_state.status_message {
+        file_info.push(Span::raw(format!(" | {message}")).dark_gray());
+    }
+
+    let file_text = Paragraph::new(Line::from(file_info)).alignment(Alignment::Left);
+    frame.render_widget(file_text, left_area);
+
+    if let Some(buffer) = buffer {
+        let line_num = buffer.ui_state.cursor_line;
+        let column_num = buffer.ui_state.cursor_column;
+        let file_format = buffer
+            .ui_state
+            .file_format
+            .describe(buffer.ui_state.lines.as_ref());
+        let mut formatted_text = format!("{file_format} | Line {line_num} | Column {column_num}");
+        if buffer.ui_state.has_multiple_cursors() {
+            let cursors_num = buffer.ui_state.extra_cursors.len() + 1;
+            formatted_text = format!("{cursors_num} cursors | {formatted_text}");
+        }
+        let text = Paragraph::new(formatted_text).alignment(Alignment::Right);
+
+        frame.render_widget(text, right_area);
+    }
+}
+
+/// The first line is the input, the second one shows matching commands,
+/// the best match together with its description
+fn render_command_bar(
+    frame: &mut Frame,
+    area: Rect,
+    app_state: &AppState,
+    command_bar: &CommandBar,
+) {
+    let input = String::from_iter(&command_bar.input);
+    let mut lines = vec![Line::from(format!(":{input}"))];
+
+    let completions = app_state.get_command_bar_completions();
+    if let Some((best_match, other_matches)) = completions.split_first() {
+        let mut spans = vec![
+            Span::raw(best_match.name).yellow(),
+            Span::raw(format!(" {} ", best_match.description)).dark_gray(),
+        ];
+        for command in other_matches {
+            spans.push(Span::raw(format!(" {}", command.name)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+
+    // 1 for the `:` at the beginning
+    let cursor_x = area.x as usize + 1 + command_bar.cursor;
+    frame.set_cursor_position((cursor_x as u16, area.y));
+}
+
+/// The first line is the pattern, the second one is the replacement if we are
+/// replacing; both of them are followed by hints, like the number of matches
+fn render_search_bar(frame: &mut Frame, area: Rect, app_state: &AppState, search_bar: &SearchBar) {
+    const PATTERN_PREFIX: &str = "Find: ";
+    const REPLACEMENT_PREFIX: &str = "Replace: ";
+
+    let pattern = app_state.get_search_pattern();
+    let replacement = app_state.get_replacement();
+    let summary = search_bar.summary.as_ref();
+
+    let matches_text = match summary {
+        Some(summary) => match (&summary.regex, summary.matches_number) {
+            (Err(error), _) => error.clone(),
+            (Ok(_), 1) => String::from("1 match"),
+            (Ok(_), count) => format!("{count} matches"),
+        },
+        None => String::new(),
+    };
+    let case_text = if app_state.search.case_sensitive {
+        "case sensitive"
+    } else {
+        "ignore case"
+    };
+    let regex_text = if app_state.search.use_regex {
+        "regex"
+    } else {
+        "plain text"
+    };
+
+    let mut pattern_line = vec![
+        Span::raw(format!("{PATTERN_PREFIX}{pattern}   ")),
+        Span::raw(matches_text).yellow(),
+        Span::raw(format!(" | {case_text} (alt+c) | {regex_text} (alt+r)")).dark_gray(),
+    ];
+
+    let mut lines = vec![];
+    let (prefix, cursor_y) = if search_bar.replacing {
+        let preview_text = match summary.and_then(|summary| summary.lines_to_replace) {
+            Some(1) => String::from("1 line will change"),
+            Some(lines_number) => format!("{lines_number} lines will change"),
+            None => String::new(),
+        };
+
+        lines.push(Line::from(pattern_line));
+        lines.push(Line::from(vec![
+            Span::raw(format!("{REPLACEMENT_PREFIX}{replacement}   ")),
+            Span::raw(preview_text).yellow(),
+            Span::raw(" | enter replace, alt+a replace all, tab switch").dark_gray(),
+        ]));
+
+        match search_bar.focused_input {
+            SearchInput::Pattern => (PATTERN_PREFIX, area.y),
+            SearchInput::Replacement => (REPLACEMENT_PREFIX, area.y + 1),
+        }
+    } else {
+        pattern_line.push(Span::raw(" | enter/down next, up previous").dark_gray());
+        lines.push(Line::from(pattern_line));
+
+        (PATTERN_PREFIX, area.y)
+    };
+
+    frame.render_widget(Paragraph::new(lines), area);
+
+    let cursor_x = area.x as usize + prefix.len() + search_bar.cursor;
+    frame.set_cursor_position((cursor_x as u16, cursor_y));
 }
@@ -0,0 +1,49 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+
+use crate::app_state::AppState;
+
+/// Render names of all opened buffers in a single row above the editor
+pub fn render_tabs(frame: &mut Frame, area: Rect, app_state: &AppState) {
+    let mut tabs: Vec<Span> = app_state
+        .buffers
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| {
+            let modified_mark = if buffer.ui_state.is_dirty { " ●" } else { "" };
+            let title = format!(" {}{modified_mark} ", buffer.get_name());
+
+            if index == app_state.active_buffer {
+                Span::styled(title, Style::new().bg(app_state.theme.bg_color).white())
+            } else {
+                Span::styled(title, Style::new().dark_gray())
+            }
+        })
+        .collect();
+
+    // if there are too many tabs, we hide the first ones so the active one is visible
+    let active_tab_end: usize = tabs
+        .iter()
+        .take(app_state.active_buffer + 1)
+        .map(|tab| tab.width())
+        .sum();
+    let mut hidden_width = 0;
+    let mut hidden_tabs = 0;
+    while active_tab_end - hidden_width > area.width as usize
+        && hidden_tabs < app_state.active_buffer
+    {
+        hidden_width += tabs[hidden_tabs].width();
+        hidden_tabs += 1;
+    }
+    tabs.drain(0..hidden_tabs);
+
+    let block = Block::default().style(Style::default().bg(Color::Rgb(12, 4, 2)));
+    let tabs_widget = Paragraph::new(Line::from(tabs)).block(block);
+
+    frame.render_widget(tabs_widget, area);
+}